@@ -1,3 +1,4 @@
+import csv
 import os
 import subprocess
 
@@ -9,6 +10,8 @@ Y_STEP = 0.007
 
 
 RUNS_PER_POS = 100
+# NOTE: Every position runs the same seeds, so the runs can be reproduced
+SEED = 0
 
 BALLS_TO_WAIT_FOR = 8
 DELTA_T = 0.0001
@@ -21,26 +24,31 @@ for y_pos in np.arange(Y_MIN_POS, Y_MAX_POS, Y_STEP):
     y_pos_str = str(round(y_pos, 3))
     y_offset_str = str(round(Y_MAX_POS - y_pos, 3))
     print(f"Starting runs for Y position {y_pos_str} and offset {y_offset_str}")
-    times = []
-    for run_idx in range(RUNS_PER_POS):
-        run = subprocess.run(
-            [
-                "./target/release/billiards",
-                "gear-predictor-corrector",
-                "-w" + y_offset_str,
-                "--simulation-delta-t",
-                str(DELTA_T),
-                "--output-delta-t",
-                "0.01",
-                "-b",
-                str(BALLS_TO_WAIT_FOR),
-            ],
-            capture_output=True,
-            text=True,
-        )
-
-        times.append(run.stdout.split(" ")[2])
+    ensemble_path = RESULTS_PATH + y_pos_str + ".csv"
+    subprocess.run(
+        [
+            "./target/release/billiards",
+            "gear-predictor-corrector",
+            "-w" + y_offset_str,
+            "--simulation-delta-t",
+            str(DELTA_T),
+            "--output-delta-t",
+            "0.01",
+            "-b",
+            str(BALLS_TO_WAIT_FOR),
+            "--seed",
+            str(SEED),
+            "--ensemble-size",
+            str(RUNS_PER_POS),
+            "--ensemble-output-path",
+            ensemble_path,
+        ],
+        check=True,
+    )
+
+    with open(ensemble_path, "r") as f:
+        runs = list(csv.DictReader(f))
 
     with open(RESULTS_PATH + y_pos_str + ".txt", "a") as f:
-        for time in times:
-            f.write(time)
+        for run in runs:
+            f.write(run["time"] + "\n")
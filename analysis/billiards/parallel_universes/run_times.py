@@ -2,6 +2,8 @@ import os
 import subprocess
 
 DELTA_T = [0.01, 0.001, 0.0001, 0.00001, 0.000001]
+# NOTE: Every time step runs the same seed, so the runs can be reproduced
+SEED = 0
 
 RESULTS_PATH = "./analysis/billiards/parallel_universes/data/"
 
@@ -22,6 +24,8 @@ def main():
                 "--output-delta-t",
                 "0.01",
                 "-m100",
+                "--seed",
+                str(SEED),
             ]
         )
 
@@ -1,6 +1,8 @@
 import os
 import subprocess
 
+# NOTE: The symplectic methods need forces which depend on the positions alone, which the
+# damping of the oscillator does not
 METHODS = [
     "euler",
     "euler-mod",
@@ -10,6 +12,13 @@ METHODS = [
     "beeman",
     "euler-predictor-corrector",
     "gear-predictor-corrector",
+    "runge-kutta-midpoint",
+    "heun",
+    "runge-kutta4",
+    "runge-kutta38",
+    "implicit-euler",
+    "implicit-midpoint",
+    "trapezoidal",
 ]
 
 DELTA_T = [0.01, 0.001, 0.0001, 0.00001, 0.000001]
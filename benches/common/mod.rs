@@ -0,0 +1,168 @@
+//! Scenarios shared by the benches: the oscillator and billiards break of the binaries, a
+//! grid of colliding balls of any size, and the integration method of each variant.
+
+// Each bench only uses some of the scenarios
+#![allow(dead_code)]
+
+use integration_dynamics::{
+    forces::{ForceModel, LinearDamper, LinearSpring, Pairwise, SoftSphereContact, WallContact},
+    methods::IntegrationMethod,
+    particle::Particle,
+    system::ParticleSystem,
+    Integration, MethodSettings,
+};
+
+const OSCILLATOR_MASS: f64 = 70.0;
+const SPRING_CONSTANT: f64 = 1e4;
+const DAMPING_CONSTANT: f64 = 1e2;
+const AMPLITUDE: f64 = 1.0;
+
+pub const TABLE: [f64; 2] = [2.24, 1.12];
+const BALL_RADIUS: f64 = 0.057 / 2.0;
+const BALL_MASS: f64 = 0.165;
+const BALL_SPACING: f64 = 2.5e-4;
+const BALL_SPEED: f64 = 1.0;
+const RESTORING_FORCE_CONSTANT: f64 = 1e4;
+/// Distance within which the balls touch.
+pub const BALL_CONTACT_CUTOFF: f64 = 2.0 * BALL_RADIUS;
+
+/// Gap between the white ball and the rack, and between the balls of a grid, small
+/// enough for the balls to collide within the first hundred steps.
+const CONTACT_GAP: f64 = 1e-3;
+
+const ABSOLUTE_TOLERANCE: f64 = 1e-9;
+const RELATIVE_TOLERANCE: f64 = 1e-6;
+const GEAR_ORDER: usize = 5;
+
+pub fn oscillator_force_model() -> impl ForceModel<1> {
+    LinearSpring::new(SPRING_CONSTANT, [0.0]).plus(LinearDamper::new(DAMPING_CONSTANT))
+}
+
+/// Damped oscillator of the oscillator binary.
+pub fn oscillator() -> ParticleSystem<1> {
+    let position = AMPLITUDE;
+    let velocity = -AMPLITUDE * DAMPING_CONSTANT / (2.0 * OSCILLATOR_MASS);
+    let acceleration =
+        (-SPRING_CONSTANT * position - DAMPING_CONSTANT * velocity) / OSCILLATOR_MASS;
+
+    ParticleSystem::new(&[Particle::new(
+        0,
+        [position],
+        [velocity],
+        [acceleration],
+        0.0,
+        OSCILLATOR_MASS,
+    )])
+}
+
+pub fn ball_contact() -> SoftSphereContact {
+    SoftSphereContact::new(RESTORING_FORCE_CONSTANT)
+}
+
+pub fn cushions(table: [f64; 2]) -> WallContact<2> {
+    WallContact::new(RESTORING_FORCE_CONSTANT, table)
+}
+
+pub fn billiards_force_model(table: [f64; 2]) -> impl ForceModel<2> {
+    Pairwise::with_cutoff(ball_contact(), BALL_CONTACT_CUTOFF).plus(cushions(table))
+}
+
+/// Force model of the table the billiards break is played on.
+pub fn billiards_break_force_model() -> impl ForceModel<2> {
+    billiards_force_model(TABLE)
+}
+
+/// White ball shot at a triangle of 15 balls as in the billiards binary, starting just
+/// short of the rack so that the steps measured include the break itself.
+pub fn billiards_break() -> Vec<Particle<2>> {
+    let [length, width] = TABLE;
+    let spaced_radius = BALL_RADIUS + BALL_SPACING / 2.0;
+    let rack_x = length - width / 2.0;
+
+    let mut balls = vec![ball(
+        0,
+        [rack_x - 2.0 * BALL_RADIUS - CONTACT_GAP, width / 2.0],
+        [BALL_SPEED, 0.0],
+    )];
+
+    for row in 0..5 {
+        let x = rack_x + 3f64.sqrt() * spaced_radius * row as f64;
+
+        for column in 0..=row {
+            let y = width / 2.0 + spaced_radius * (2 * column) as f64 - spaced_radius * row as f64;
+            balls.push(ball(balls.len(), [x, y], [0.0; 2]));
+        }
+    }
+
+    balls
+}
+
+/// Table holding a square grid of `side` by `side` balls.
+pub fn ball_grid_table(side: usize) -> [f64; 2] {
+    [side as f64 * ball_grid_spacing(); 2]
+}
+
+/// Square grid of `side` by `side` balls moving in different directions, which
+/// collide with their neighbours and the walls of [`ball_grid_table`].
+pub fn ball_grid(side: usize) -> Vec<Particle<2>> {
+    let spacing = ball_grid_spacing();
+    let golden_angle = std::f64::consts::PI * (3.0 - 5f64.sqrt());
+
+    (0..side * side)
+        .map(|id| {
+            let (row, column) = (id / side, id % side);
+            let angle = golden_angle * id as f64;
+
+            ball(
+                id,
+                [column as f64 + 0.5, row as f64 + 0.5].map(|k| k * spacing),
+                [angle.cos(), angle.sin()].map(|v| v * BALL_SPEED),
+            )
+        })
+        .collect()
+}
+
+fn ball_grid_spacing() -> f64 {
+    2.0 * BALL_RADIUS + CONTACT_GAP
+}
+
+fn ball(id: usize, position: [f64; 2], velocity: [f64; 2]) -> Particle<2> {
+    Particle::new(id, position, velocity, [0.0; 2], BALL_RADIUS, BALL_MASS)
+}
+
+/// Name of the variant as given on the command line of the binaries.
+pub fn integration_name(integration: &Integration) -> String {
+    use clap::ValueEnum;
+
+    integration
+        .to_possible_value()
+        .expect("every integration variant has a name")
+        .get_name()
+        .to_owned()
+}
+
+/// Integration method of each variant with the defaults of the binaries, initialising
+/// `system` for the methods which need it.
+pub fn method<const DIM: usize, F: ForceModel<DIM> + 'static>(
+    integration: &Integration,
+    force_model: F,
+    force_depends_on_velocity: bool,
+    system: &mut ParticleSystem<DIM>,
+    delta_t: f64,
+) -> Box<dyn IntegrationMethod<DIM>> {
+    integration
+        .method(
+            force_model,
+            system,
+            delta_t,
+            MethodSettings {
+                absolute_tolerance: ABSOLUTE_TOLERANCE,
+                relative_tolerance: RELATIVE_TOLERANCE,
+                max_step: None,
+                gear_order: GEAR_ORDER,
+                force_depends_on_velocity,
+                step_observer: None,
+            },
+        )
+        .expect("the method supports the gear order and the forces")
+}
@@ -0,0 +1,128 @@
+//! Steps per second of every integration method on the oscillator and the billiards
+//! break, and how the throughput of a few of them scales with the number of particles.
+//!
+//! Each iteration starts over from the same initial system, so the billiards steps always
+//! cover the same collisions. Run a single scenario or method by filtering on its name,
+//! for instance `cargo bench --bench integrators -- billiards_break/runge-kutta4`.
+
+mod common;
+
+use clap::ValueEnum;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use integration_dynamics::{
+    forces::ForceModel, methods::IntegrationMethod, system::ParticleSystem, Integration,
+};
+
+const DELTA_T: f64 = 1e-4;
+
+const OSCILLATOR_STEPS: u64 = 1000;
+const BILLIARDS_STEPS: u64 = 100;
+
+/// Balls per side of the grids the scaling is measured on.
+const GRID_SIDES: [usize; 4] = [4, 8, 16, 32];
+const SCALING_METHODS: [Integration; 3] = [
+    Integration::VelocityVerlet,
+    Integration::RungeKutta4,
+    Integration::DormandPrince,
+];
+
+/// Measures `steps` steps of `method` from `system`, which is left untouched.
+fn bench_steps<const DIM: usize>(
+    group: &mut criterion::BenchmarkGroup<'_, criterion::measurement::WallTime>,
+    id: BenchmarkId,
+    method: &dyn IntegrationMethod<DIM>,
+    system: &ParticleSystem<DIM>,
+    steps: u64,
+) {
+    group.bench_function(id, |b| {
+        b.iter_batched_ref(
+            || system.clone(),
+            |system| {
+                for _ in 0..steps {
+                    method.advance_system(system);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+}
+
+fn bench_every_method<const DIM: usize, F: ForceModel<DIM> + 'static>(
+    c: &mut Criterion,
+    scenario: &str,
+    force_model: impl Fn() -> F,
+    force_depends_on_velocity: bool,
+    initial_system: impl Fn() -> ParticleSystem<DIM>,
+    steps: u64,
+) {
+    let mut group = c.benchmark_group(scenario);
+    group.throughput(Throughput::Elements(steps));
+
+    for integration in Integration::value_variants() {
+        if force_depends_on_velocity && !integration.supports_velocity_dependent_forces() {
+            continue;
+        }
+
+        let mut system = initial_system();
+        let method = common::method(
+            integration,
+            force_model(),
+            force_depends_on_velocity,
+            &mut system,
+            DELTA_T,
+        );
+
+        let id = BenchmarkId::from_parameter(common::integration_name(integration));
+        bench_steps(&mut group, id, method.as_ref(), &system, steps);
+    }
+
+    group.finish();
+}
+
+fn oscillator(c: &mut Criterion) {
+    bench_every_method(
+        c,
+        "oscillator",
+        common::oscillator_force_model,
+        true,
+        common::oscillator,
+        OSCILLATOR_STEPS,
+    );
+}
+
+fn billiards_break(c: &mut Criterion) {
+    bench_every_method(
+        c,
+        "billiards_break",
+        common::billiards_break_force_model,
+        false,
+        || ParticleSystem::new(&common::billiards_break()),
+        BILLIARDS_STEPS,
+    );
+}
+
+/// Particle steps per second on growing grids of balls, where a method which scales
+/// linearly keeps the same throughput.
+fn particle_count_scaling(c: &mut Criterion) {
+    for integration in &SCALING_METHODS {
+        let mut group = c.benchmark_group(format!(
+            "particle_count_scaling/{}",
+            common::integration_name(integration)
+        ));
+
+        for side in GRID_SIDES {
+            let mut system = ParticleSystem::new(&common::ball_grid(side));
+            let force_model = common::billiards_force_model(common::ball_grid_table(side));
+            let method = common::method(integration, force_model, false, &mut system, DELTA_T);
+
+            group.throughput(Throughput::Elements(BILLIARDS_STEPS * system.len() as u64));
+            let id = BenchmarkId::from_parameter(system.len());
+            bench_steps(&mut group, id, method.as_ref(), &system, BILLIARDS_STEPS);
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, oscillator, billiards_break, particle_count_scaling);
+criterion_main!(benches);
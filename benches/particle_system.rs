@@ -0,0 +1,224 @@
+//! Throughput of stepping the 16 ball billiards break with the particles stored one by
+//! one, which copies them in and out of a system on every step, against keeping them in
+//! a [`ParticleSystem`] across steps, and of the storage the integrators had before
+//! [`ParticleSystem`], which cloned the derivatives of every particle on every step.
+
+mod common;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use integration_dynamics::{
+    forces::{ForceModel, PairInteraction},
+    methods::{IntegrationMethod, VelocityVerlet},
+    neighbours::CellList,
+    particle::{Particle, ParticleState},
+    system::ParticleSystem,
+    Integration,
+};
+
+const DELTA_T: f64 = 1e-4;
+const STEPS: u64 = 100;
+
+fn bench_method(c: &mut Criterion, integration: &Integration) {
+    let mut group = c.benchmark_group(format!(
+        "billiards_break/{}",
+        common::integration_name(integration)
+    ));
+    group.throughput(Throughput::Elements(STEPS));
+
+    let mut system = ParticleSystem::new(&common::billiards_break());
+    let method = common::method(
+        integration,
+        common::billiards_break_force_model(),
+        false,
+        &mut system,
+        DELTA_T,
+    );
+
+    let particles = system.to_particles();
+    group.bench_function(BenchmarkId::from_parameter("particle_slice"), |b| {
+        b.iter_batched_ref(
+            || particles.clone(),
+            |particles| {
+                for _ in 0..STEPS {
+                    method.advance_step(particles);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function(BenchmarkId::from_parameter("particle_system"), |b| {
+        b.iter_batched_ref(
+            || system.clone(),
+            |system| {
+                for _ in 0..STEPS {
+                    method.advance_system(system);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.finish();
+}
+
+fn billiards(c: &mut Criterion) {
+    for integration in [
+        Integration::VelocityVerlet,
+        Integration::Beeman,
+        Integration::RungeKutta4,
+    ] {
+        bench_method(c, &integration);
+    }
+}
+
+/// Particle stored the way the integrators stored it before [`ParticleSystem`], with
+/// its own heap allocated derivatives.
+#[derive(Clone)]
+struct StoredParticle {
+    id: usize,
+    derivatives: Vec<[f64; 2]>,
+    prev_derivatives: Vec<[f64; 2]>,
+    radius: f64,
+    mass: f64,
+}
+
+impl StoredParticle {
+    fn new(particle: &Particle<2>) -> Self {
+        Self {
+            id: particle.id(),
+            derivatives: particle.derivatives().clone(),
+            prev_derivatives: particle.derivatives().clone(),
+            radius: particle.radius(),
+            mass: particle.mass(),
+        }
+    }
+
+    fn state(&self) -> ParticleState<2> {
+        ParticleState {
+            id: self.id,
+            position: self.derivatives[0],
+            velocity: self.derivatives[1],
+            radius: self.radius,
+            mass: self.mass,
+        }
+    }
+}
+
+/// Forces of the billiards break on the stored particles, visiting each pair of balls
+/// in adjacent cells once as [`integration_dynamics::forces::Pairwise`] does.
+fn stored_forces(particles: &[StoredParticle], cushions: &impl ForceModel<2>) -> Vec<[f64; 2]> {
+    let contact = common::ball_contact();
+    let states: Vec<_> = particles.iter().map(StoredParticle::state).collect();
+    let positions: Vec<_> = states.iter().map(|state| state.position).collect();
+
+    // NOTE: The cushions only look at the particle they push, never at the system
+    let no_system = ParticleSystem::new(&[]);
+    let mut forces: Vec<_> = states
+        .iter()
+        .map(|state| cushions.force(state, &no_system))
+        .collect();
+
+    CellList::new(common::BALL_CONTACT_CUTOFF, &positions).for_each_pair(|n, m| {
+        let squared_distance: f64 = (0..2)
+            .map(|i| (positions[m][i] - positions[n][i]).powi(2))
+            .sum();
+        if squared_distance > common::BALL_CONTACT_CUTOFF.powi(2) {
+            return;
+        }
+
+        let pair_force = contact.pair_force(&states[n], &states[m]);
+        for i in 0..2 {
+            forces[n][i] += pair_force[i];
+            forces[m][i] -= pair_force[i];
+        }
+    });
+
+    forces
+}
+
+/// Velocity Verlet step of the stored particles, cloning the derivatives of each one to
+/// predict where it moves, as the integrators did before [`ParticleSystem`].
+fn stored_velocity_verlet_step(particles: &mut [StoredParticle], cushions: &impl ForceModel<2>) {
+    let old_particles = particles.to_vec();
+
+    for particle in particles.iter_mut() {
+        let r = &particle.derivatives;
+        let mut new_r = r.clone();
+
+        for i in 0..2 {
+            new_r[0][i] += DELTA_T * r[1][i] + DELTA_T.powi(2) / 2.0 * r[2][i];
+            // NOTE: Predict v(t + delta_t) to evaluate velocity dependent forces
+            new_r[1][i] += DELTA_T * r[2][i];
+        }
+
+        particle.prev_derivatives = std::mem::replace(&mut particle.derivatives, new_r);
+    }
+
+    let forces = stored_forces(particles, cushions);
+
+    for (n, particle) in particles.iter_mut().enumerate() {
+        let r = &old_particles[n].derivatives;
+        let acceleration = forces[n].map(|f| f / particle.mass);
+
+        for i in 0..2 {
+            particle.derivatives[1][i] = r[1][i] + DELTA_T / 2.0 * (r[2][i] + acceleration[i]);
+        }
+        particle.derivatives[2] = acceleration;
+    }
+}
+
+/// Velocity Verlet on the particles stored one by one as before [`ParticleSystem`],
+/// against the same steps on a [`ParticleSystem`].
+fn storage_layouts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("billiards_break_storage/velocity-verlet");
+    group.throughput(Throughput::Elements(STEPS));
+
+    let cushions = common::cushions(common::TABLE);
+    let method = VelocityVerlet::new(common::billiards_break_force_model(), DELTA_T);
+    let system = ParticleSystem::new(&common::billiards_break());
+    let particles: Vec<_> = common::billiards_break()
+        .iter()
+        .map(StoredParticle::new)
+        .collect();
+
+    // NOTE: Both layouts must take the same steps for the comparison to be fair
+    let mut stored = particles.clone();
+    let mut stepped = system.clone();
+    for _ in 0..STEPS {
+        stored_velocity_verlet_step(&mut stored, &cushions);
+        method.advance_system(&mut stepped);
+    }
+    for (particle, position) in stored.iter().zip(stepped.positions()) {
+        assert_eq!(particle.derivatives[0], *position);
+    }
+
+    group.bench_function(BenchmarkId::from_parameter("per_particle"), |b| {
+        b.iter_batched_ref(
+            || particles.clone(),
+            |particles| {
+                for _ in 0..STEPS {
+                    stored_velocity_verlet_step(particles, &cushions);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function(BenchmarkId::from_parameter("particle_system"), |b| {
+        b.iter_batched_ref(
+            || system.clone(),
+            |system| {
+                for _ in 0..STEPS {
+                    method.advance_system(system);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, billiards, storage_layouts);
+criterion_main!(benches);
@@ -1,6 +1,8 @@
 use clap::Parser;
 use integration_dynamics::Integration;
 
+use crate::constants::DIM;
+
 #[derive(Parser, Debug)]
 #[command(name = "Billiards Integration", author, version, about)]
 pub struct Cli {
@@ -16,12 +18,75 @@ pub struct Cli {
     #[arg(short, long, default_value_t = false)]
     pub fixed_spacing: bool,
 
+    /// Seed of the noise in the spacing of the balls, drawn at random if not given. It is
+    /// written in the header of every frame of the xyz output, to reproduce the run
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Initial position of the cue ball, as its coordinates separated by a comma, instead
+    /// of the one of the scenario
+    #[arg(long, value_parser = parse_position)]
+    pub cue_position: Option<[f64; DIM]>,
+
+    /// Shift of the cue ball across the table from its initial position
     #[arg(short, long, default_value_t = 0.0)]
     pub white_offset: f64,
 
+    /// Direction of the shot of the cue ball in degrees, counterclockwise from the length of
+    /// the table, instead of the direction of its velocity in the scenario
+    #[arg(long, conflicts_with_all = ["aim_ball", "aim_point"])]
+    pub shot_angle: Option<f64>,
+
+    /// Shoot the cue ball at the centre of the ball with this id
+    #[arg(long, conflicts_with = "aim_point")]
+    pub aim_ball: Option<usize>,
+
+    /// Shoot the cue ball at this point of the table, given as its coordinates separated by
+    /// a comma
+    #[arg(long, value_parser = parse_position)]
+    pub aim_point: Option<[f64; DIM]>,
+
+    /// Speed of the cue ball, instead of the speed of its velocity in the scenario
+    #[arg(long)]
+    pub shot_speed: Option<f64>,
+
+    /// Spin of the cue ball about the horizontal axis across the shot, as a multiple of the
+    /// spin of a ball rolling at its speed: 1 rolls it and negative values spin it back.
+    /// The scenario must have spin friction
+    #[arg(long, allow_hyphen_values = true)]
+    pub top_spin: Option<f64>,
+
+    /// Spin of the cue ball about the vertical, counterclockwise seen from above, as a
+    /// multiple of the spin of a ball rolling at its speed
+    #[arg(long, allow_hyphen_values = true)]
+    pub side_spin: Option<f64>,
+
+    /// Path of the scenario to simulate, a TOML file or a JSON file ending in `.json`,
+    /// instead of the default pool table with a triangle of 15 balls
+    #[arg(long)]
+    pub scenario: Option<String>,
+
+    /// Time step of the simulation. Adaptive methods ignore it, advancing a whole output
+    /// step at a time in internal steps of up to the max step, so that the spin, pockets
+    /// and stop condition are only looked at on every output
     #[arg(short, long, default_value_t = 1e-4)]
     pub simulation_delta_t: f64,
 
+    /// Largest internal step of the adaptive methods, which is otherwise only bounded by
+    /// the end of the output step
+    #[arg(long)]
+    pub max_step: Option<f64>,
+
+    #[arg(long, default_value_t = 1e-9)]
+    pub absolute_tolerance: f64,
+
+    #[arg(long, default_value_t = 1e-6)]
+    pub relative_tolerance: f64,
+
+    /// Highest derivative of the position tracked by the Gear predictor corrector
+    #[arg(long, default_value_t = 5)]
+    pub gear_order: usize,
+
     #[arg(short, long, default_value_t = 5e-2)]
     pub output_delta_t: f64,
 
@@ -33,4 +98,56 @@ pub struct Cli {
 
     #[arg(short, long)]
     pub data_output_path: Option<String>,
+
+    #[arg(long)]
+    pub step_sizes_output_path: Option<String>,
+
+    /// Path of the exact time each ball fell into a hole, found inside the step it did
+    #[arg(long)]
+    pub pockets_output_path: Option<String>,
+
+    /// Path of the energy and momenta of the balls, spin included, at every output step
+    #[arg(long)]
+    pub diagnostics_output_path: Option<String>,
+
+    /// Path of a checkpoint of the simulation, overwritten at every output step
+    #[arg(long)]
+    pub checkpoint_path: Option<String>,
+
+    /// Resume from a checkpoint, given the arguments of the run which saved it, appending
+    /// to its output files. The run goes on with the seed the checkpoint was taken with
+    #[arg(long)]
+    pub resume: Option<String>,
+
+    /// Run this many simulations, with consecutive seeds starting at the seed, writing the
+    /// pocket statistics of each instead of their outputs
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "xyz_output_path",
+            "data_output_path",
+            "step_sizes_output_path",
+            "pockets_output_path",
+            "diagnostics_output_path",
+            "checkpoint_path",
+            "resume",
+        ]
+    )]
+    pub ensemble_size: Option<u64>,
+
+    /// Path of the pocket statistics of every seed of the ensemble, written as CSV
+    #[arg(long, default_value_t = String::from("./ensemble.csv"))]
+    pub ensemble_output_path: String,
+}
+
+/// Parses a position or point given as its coordinates separated by commas.
+fn parse_position(value: &str) -> Result<[f64; DIM], String> {
+    let coordinates = value
+        .split(',')
+        .map(|x| x.trim().parse())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|error| error.to_string())?;
+
+    <[f64; DIM]>::try_from(coordinates)
+        .map_err(|coordinates| format!("expected {DIM} coordinates, found {}", coordinates.len()))
 }
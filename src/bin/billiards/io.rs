@@ -1,22 +1,24 @@
 use std::{
-    fs::File,
+    fs::{File, OpenOptions},
     io::{BufWriter, Write},
 };
 
-use integration_dynamics::particle::Particle;
+use integration_dynamics::{diagnostics::Diagnostics, system::ParticleSystem};
 
-use crate::constants::{DIM, HOLE_RADIUS, HOLE_VARIANTS};
+use crate::constants::DIM;
+use crate::scenario::Hole;
+use crate::simulation::Pocket;
 use crate::Result;
 
-struct RGB {
+struct Rgb {
     r: f64,
     g: f64,
     b: f64,
 }
 
-impl RGB {
+impl Rgb {
     fn new(r: f64, g: f64, b: f64) -> Self {
-        RGB { r, g, b }
+        Rgb { r, g, b }
     }
 }
 
@@ -33,17 +35,17 @@ enum Color {
 }
 
 impl Color {
-    fn get_rgb(&self) -> RGB {
+    fn get_rgb(&self) -> Rgb {
         match self {
-            Color::White => RGB::new(1.0, 1.0, 1.0),
-            Color::Black => RGB::new(0.0, 0.0, 0.0),
-            Color::Yellow => RGB::new(1.0, 1.0, 0.0),
-            Color::Red => RGB::new(1.0, 0.0, 0.0),
-            Color::Green => RGB::new(0.0, 0.5, 0.0),
-            Color::Blue => RGB::new(0.0, 0.0, 1.0),
-            Color::Purple => RGB::new(1.0, 0.0, 1.0),
-            Color::Orange => RGB::new(1.0, 0.5, 0.0),
-            Color::Maroon => RGB::new(0.5, 0.0, 0.0),
+            Color::White => Rgb::new(1.0, 1.0, 1.0),
+            Color::Black => Rgb::new(0.0, 0.0, 0.0),
+            Color::Yellow => Rgb::new(1.0, 1.0, 0.0),
+            Color::Red => Rgb::new(1.0, 0.0, 0.0),
+            Color::Green => Rgb::new(0.0, 0.5, 0.0),
+            Color::Blue => Rgb::new(0.0, 0.0, 1.0),
+            Color::Purple => Rgb::new(1.0, 0.0, 1.0),
+            Color::Orange => Rgb::new(1.0, 0.5, 0.0),
+            Color::Maroon => Rgb::new(0.5, 0.0, 0.0),
         }
     }
 }
@@ -67,34 +69,46 @@ const COLORS: [Color; 16] = [
     Color::Maroon,
 ];
 
+/// Opens an output file, appending to it when resuming a simulation which wrote its start.
+pub fn open_output(path: &str, append: bool) -> Result<File> {
+    let file = if append {
+        OpenOptions::new().append(true).create(true).open(path)?
+    } else {
+        File::create(path)?
+    };
+
+    Ok(file)
+}
+
 pub fn output_simulation(
     file: &File,
-    particles: &Vec<Particle<DIM>>,
+    particles: &ParticleSystem<DIM>,
+    holes: &[Hole],
     include_holes: bool,
+    seed: u64,
 ) -> Result<()> {
     let mut writer = BufWriter::new(file);
 
-    let particle_count = particles.len() + HOLE_VARIANTS.len();
+    let particle_count = particles.len() + holes.len();
     writeln!(writer, "{particle_count}")?;
     writeln!(
         writer,
-        "Properties=pos:R:{DIM}:velo:R:{DIM}:radius:R:1:color:R:3 pbc=\"F F\"",
+        "Properties=pos:R:{DIM}:velo:R:{DIM}:radius:R:1:color:R:3 pbc=\"F F\" seed={seed}",
     )?;
 
     // NOTE: Write the particles
-    for particle in particles {
-        let derivatives = particle.derivatives();
-        let particle_color = COLORS[particle.id() % COLORS.len()].get_rgb();
+    for particle in particles.states() {
+        let particle_color = COLORS[particle.id % COLORS.len()].get_rgb();
 
         // TODO: Generalize into DIM-dimensional, not just 2D
         writeln!(
             writer,
             "{:.12} {:.12} {:.12} {:.12} {:.4} {} {} {}",
-            derivatives[0][0],
-            derivatives[0][1],
-            derivatives[1][0],
-            derivatives[1][1],
-            particle.radius(),
+            particle.position[0],
+            particle.position[1],
+            particle.velocity[0],
+            particle.velocity[1],
+            particle.radius,
             particle_color.r,
             particle_color.g,
             particle_color.b
@@ -103,9 +117,9 @@ pub fn output_simulation(
 
     let holes_color = Color::White.get_rgb();
     // NOTE: Write the holes
-    for hole in &HOLE_VARIANTS {
-        let hole_coordinates = hole.coordinates();
-        let hole_radius = if include_holes { HOLE_RADIUS } else { 0.001 };
+    for hole in holes {
+        let hole_coordinates = hole.position;
+        let hole_radius = if include_holes { hole.radius } else { 0.001 };
 
         writeln!(
             writer,
@@ -124,14 +138,83 @@ pub fn output_simulation(
     Ok(())
 }
 
-pub fn output_positions(file: &File, particles: &Vec<Particle<DIM>>, time: f64) -> Result<()> {
+pub fn output_positions(file: &File, particles: &ParticleSystem<DIM>, time: f64) -> Result<()> {
     let mut writer = BufWriter::new(file);
 
     writeln!(writer, "{time}")?;
-    for ball in particles {
-        let r = ball.derivatives()[0];
+    for r in particles.positions() {
         writeln!(writer, "{} {}", r[0], r[1])?;
     }
 
     Ok(())
 }
+
+/// Writes the time, kinetic, potential and total energy, linear momentum and angular
+/// momentum of the balls on a line, counting their spin in the kinetic energy and
+/// angular momentum.
+pub fn output_diagnostics(file: &File, time: f64, diagnostics: &Diagnostics<DIM>) -> Result<()> {
+    let mut writer = BufWriter::new(file);
+
+    writeln!(
+        writer,
+        "{time} {} {} {} {} {} {}",
+        diagnostics.kinetic_energy,
+        diagnostics.potential_energy.unwrap_or(f64::NAN),
+        diagnostics.total_energy().unwrap_or(f64::NAN),
+        diagnostics.linear_momentum[0],
+        diagnostics.linear_momentum[1],
+        diagnostics.angular_momentum[0][1]
+    )?;
+
+    Ok(())
+}
+
+/// Writes the time and id of every ball which fell into a hole, on a line each.
+pub fn output_pockets(file: &File, pockets: &[Pocket]) -> Result<()> {
+    let mut writer = BufWriter::new(file);
+
+    for pocket in pockets {
+        writeln!(writer, "{} {}", pocket.time, pocket.id)?;
+    }
+
+    Ok(())
+}
+
+pub fn output_step_sizes(file: &File, step_sizes: &[f64]) -> Result<()> {
+    let mut writer = BufWriter::new(file);
+
+    for step_size in step_sizes {
+        writeln!(writer, "{step_size}")?;
+    }
+
+    Ok(())
+}
+
+pub fn output_ensemble_header(file: &File) -> Result<()> {
+    let mut writer = BufWriter::new(file);
+
+    writeln!(
+        writer,
+        "seed,time,pocketed,white_pocketed,first_pocket_time,last_pocket_time"
+    )?;
+
+    Ok(())
+}
+
+/// Writes the seed of a run of the ensemble, the time it stopped at, how many balls fell
+/// into a hole, whether the white ball did, and when the first and last did. The times of
+/// the pockets are left empty if no ball fell.
+pub fn output_ensemble_run(file: &File, seed: u64, time: f64, pockets: &[Pocket]) -> Result<()> {
+    let mut writer = BufWriter::new(file);
+
+    let white_pocketed = pockets.iter().any(|pocket| pocket.id == 0);
+    write!(writer, "{seed},{time},{},{white_pocketed},", pockets.len())?;
+    if let (Some(first), Some(last)) = (pockets.first(), pockets.last()) {
+        write!(writer, "{},{}", first.time, last.time)?;
+    } else {
+        write!(writer, ",")?;
+    }
+    writeln!(writer)?;
+
+    Ok(())
+}
@@ -1,73 +1,265 @@
-use std::fs::File;
+use std::{fs::File, ops::ControlFlow};
 
-use anyhow::{Ok, Result};
+use anyhow::{bail, Ok, Result};
 use clap::Parser;
+use integration_dynamics::{
+    checkpoint::Checkpoint, diagnostics::Diagnostics, simulation::steps_per_output,
+};
 
 use args::Cli;
-use constants::INITIAL_WHITE_BALL_VELOCITY;
-use io::{output_positions, output_simulation};
-use simulation::Billiards;
+use io::{
+    open_output, output_diagnostics, output_ensemble_header, output_ensemble_run, output_pockets,
+    output_positions, output_simulation, output_step_sizes,
+};
+use scenario::Scenario;
+use shot::{Aim, Shot};
+use simulation::{Billiards, BilliardsSimulation};
 
 mod args;
 mod constants;
 mod io;
+mod scenario;
+mod shot;
 mod simulation;
 
 fn main() -> Result<()> {
     let args = Cli::parse();
+    let scenario = match &args.scenario {
+        Some(path) => Scenario::load(path)?,
+        None => Scenario::default(),
+    };
+
+    match args.ensemble_size {
+        Some(size) => {
+            let seed = args.seed.unwrap_or_else(rand::random);
+            run_ensemble(&args, &scenario, seed, size)
+        }
+        None => run_simulation(&args, &scenario),
+    }
+}
+
+/// Seed of a single run, which when resuming is the one the checkpoint was taken with, so
+/// that the run goes on as it started.
+fn run_seed(args: &Cli, checkpoint: Option<&Checkpoint>) -> Result<u64> {
+    let Some(checkpoint) = checkpoint else {
+        return Ok(args.seed.unwrap_or_else(rand::random));
+    };
+
+    match (checkpoint.seed, args.seed) {
+        (Some(saved), Some(seed)) if saved != seed => {
+            bail!("the checkpoint was taken with the seed {saved}, not {seed}")
+        }
+        (Some(seed), _) | (None, Some(seed)) => Ok(seed),
+        (None, None) => bail!("the checkpoint holds no seed, which must be given to resume it"),
+    }
+}
+
+fn run_simulation(args: &Cli, scenario: &Scenario) -> Result<()> {
+    let checkpoint = args.resume.as_deref().map(Checkpoint::load).transpose()?;
+    let seed = run_seed(args, checkpoint.as_ref())?;
+    let append = checkpoint.is_some();
+
     let mut data_file = None;
-    if let Some(path) = args.data_output_path {
-        data_file = Some(File::create(path)?);
+    if let Some(path) = &args.data_output_path {
+        data_file = Some(open_output(path, append)?);
     }
 
     let mut xyz_file = None;
-    if let Some(path) = args.xyz_output_path {
-        xyz_file = Some(File::create(path)?);
+    if let Some(path) = &args.xyz_output_path {
+        xyz_file = Some(open_output(path, append)?);
     }
 
-    let mut simulation = Billiards::new(
-        args.simulation_delta_t,
-        &args.integration_method,
-        args.fixed_spacing,
-        args.white_offset,
-        INITIAL_WHITE_BALL_VELOCITY,
-        !args.ignore_holes,
-        args.ball_count_stop_condition,
-    );
+    let mut diagnostics_file = None;
+    if let Some(path) = &args.diagnostics_output_path {
+        diagnostics_file = Some(open_output(path, append)?);
+    }
 
-    let simulation_iters = (args.output_delta_t / args.simulation_delta_t) as usize;
+    let mut step_sizes_file = None;
+    if let Some(path) = &args.step_sizes_output_path {
+        step_sizes_file = Some(open_output(path, append)?);
+    }
 
-    if let Some(file) = &data_file {
-        output_positions(file, simulation.balls(), 0.0)?;
+    let mut pockets_file = None;
+    if let Some(path) = &args.pockets_output_path {
+        pockets_file = Some(open_output(path, append)?);
     }
+
+    let force_model = scenario.force_model();
+    let include_holes = !args.ignore_holes;
+
+    let mut billiards = billiards(args, scenario, seed, checkpoint.as_ref())?;
+
+    let step_sizes = billiards.step_sizes();
+    let pockets = billiards.pockets();
+    let simulation = billiards.simulation_mut();
     if let Some(file) = &xyz_file {
-        output_simulation(file, simulation.balls(), !args.ignore_holes)?;
+        simulation.on_output(move |_, balls| {
+            stop_on_error(output_simulation(
+                file,
+                balls,
+                &scenario.holes,
+                include_holes,
+                seed,
+            ))
+        });
+    }
+    if let Some(file) = &data_file {
+        simulation.on_output(|time, balls| stop_on_error(output_positions(file, balls, time)));
+    }
+    if let Some(file) = &diagnostics_file {
+        simulation.on_output(|time, balls| {
+            stop_on_error(output_diagnostics(
+                file,
+                time,
+                &Diagnostics::new(balls, &force_model),
+            ))
+        });
+    }
+    // NOTE: The step sizes and pockets are written as they come, so that a checkpoint is
+    // never ahead of the output files
+    if let Some(file) = &step_sizes_file {
+        simulation
+            .on_output(move |_, _| stop_on_error(output_step_sizes(file, &step_sizes.take())));
+    }
+    if let Some(file) = &pockets_file {
+        simulation.on_output(move |_, _| stop_on_error(output_pockets(file, &pockets.take())));
+    }
+    if let Some(max_time) = args.max_time {
+        stop_at(simulation, max_time);
+    }
+
+    // NOTE: Output the initial state, which is never reason enough to stop, unless the
+    // checkpoint resumed from already did
+    if checkpoint.is_none() {
+        if let ControlFlow::Break(Err(error)) = simulation.output() {
+            return Err(error);
+        }
     }
-    let mut time = args.output_delta_t;
-    loop {
-        let particles = simulation.run(simulation_iters);
 
-        if let Some(file) = &xyz_file {
-            output_simulation(file, particles, !args.ignore_holes)?;
+    loop {
+        let stopped = simulation.run_outputs(1);
+        if let ControlFlow::Break(Err(error)) = stopped {
+            return Err(error);
         }
-        if let Some(file) = &data_file {
-            output_positions(file, particles, time)?;
+
+        if let Some(path) = &args.checkpoint_path {
+            simulation
+                .checkpoint(args.integration_method)
+                .with_seed(seed)
+                .save(path)?;
         }
 
-        if particles.len() == args.ball_count_stop_condition {
+        if stopped.is_break() {
             break;
         }
+    }
+
+    println!("Simulation Time: {:.4}", simulation.time());
+    println!("Seed: {seed}");
 
+    Ok(())
+}
+
+/// Runs the simulation with `size` consecutive seeds from `seed` on, writing the pocket
+/// statistics of each run as it ends.
+fn run_ensemble(args: &Cli, scenario: &Scenario, seed: u64, size: u64) -> Result<()> {
+    let file = File::create(&args.ensemble_output_path)?;
+    output_ensemble_header(&file)?;
+
+    for seed in (0..size).map(|n| seed.wrapping_add(n)) {
+        let mut billiards = billiards(args, scenario, seed, None)?;
+
+        let simulation = billiards.simulation_mut();
         if let Some(max_time) = args.max_time {
-            if time >= max_time {
-                break;
-            }
+            stop_at(simulation, max_time);
         }
+        simulation.run()?;
 
-        time += args.output_delta_t;
+        let time = simulation.time();
+        output_ensemble_run(&file, seed, time, &billiards.pockets().borrow())?;
     }
 
-    println!("Simulation Time: {time:.4}");
-
     Ok(())
 }
+
+/// Sets up the balls of the scenario and the integration method given on the command line.
+fn billiards<'a>(
+    args: &Cli,
+    scenario: &Scenario,
+    seed: u64,
+    checkpoint: Option<&Checkpoint>,
+) -> Result<Billiards<'a>> {
+    // NOTE: Adaptive methods choose their own steps, so the simulation only stops them at
+    // every output
+    let delta_t = if args.integration_method.is_adaptive() {
+        args.output_delta_t
+    } else {
+        args.simulation_delta_t
+    };
+
+    let Some(steps_per_output) = steps_per_output(args.output_delta_t, delta_t) else {
+        bail!(
+            "the simulation delta t {delta_t} is larger than the output delta t {}",
+            args.output_delta_t
+        );
+    };
+
+    Billiards::new(
+        delta_t,
+        steps_per_output,
+        &args.integration_method,
+        args.absolute_tolerance,
+        args.relative_tolerance,
+        args.max_step,
+        args.gear_order,
+        scenario,
+        args.fixed_spacing,
+        seed,
+        args.cue_position,
+        args.white_offset,
+        shot(args),
+        !args.ignore_holes,
+        args.ball_count_stop_condition,
+        checkpoint,
+    )
+}
+
+/// Shot given on the command line, if any part of it was.
+fn shot(args: &Cli) -> Option<Shot> {
+    let aim = args
+        .shot_angle
+        .map(Aim::Angle)
+        .or(args.aim_ball.map(Aim::Ball))
+        .or(args.aim_point.map(Aim::Point));
+
+    let given = aim.is_some()
+        || args.shot_speed.is_some()
+        || args.top_spin.is_some()
+        || args.side_spin.is_some();
+
+    given.then_some(Shot {
+        aim,
+        speed: args.shot_speed,
+        top_spin: args.top_spin,
+        side_spin: args.side_spin,
+    })
+}
+
+/// Stops the simulation at the first output at or after `max_time`.
+fn stop_at(simulation: &mut BilliardsSimulation, max_time: f64) {
+    simulation.on_output(move |time, _| {
+        if time >= max_time {
+            ControlFlow::Break(Ok(()))
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+}
+
+/// Stops the simulation with the error of an output, if it failed.
+fn stop_on_error(result: Result<()>) -> ControlFlow<Result<()>> {
+    result.map_or_else(
+        |error| ControlFlow::Break(Err(error)),
+        ControlFlow::Continue,
+    )
+}
@@ -0,0 +1,683 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context};
+use integration_dynamics::{
+    forces::{ForceModel, KineticFriction, Pairwise, SoftSphereContact, WallContact},
+    particle::Particle,
+    spin::Spin,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{CREEP_SPEED, DEFAULT_SCENARIO, DIM, GRAVITY},
+    Result,
+};
+
+/// Table, holes, balls and force constants of a simulation, read from a TOML file, or a
+/// JSON file if its name ends in `.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Largest random shift of each coordinate of the initial position of every ball but
+    /// the cue ball
+    #[serde(default)]
+    pub position_noise: f64,
+    pub table: Table,
+    pub forces: ForceConstants,
+    pub holes: Vec<Hole>,
+    /// Balls in the order of their ids, starting from the cue ball, followed by those of
+    /// the rack
+    #[serde(default)]
+    pub balls: Vec<Ball>,
+    pub rack: Option<Rack>,
+    /// Friction spinning the balls, which only translate without it
+    pub spin: Option<SpinFriction>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct Table {
+    pub length: f64,
+    pub width: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct ForceConstants {
+    /// Restoring force constant of the contacts between balls
+    pub contact_stiffness: f64,
+    /// Restoring force constant of the contacts of the balls with the cushions
+    pub cushion_stiffness: f64,
+    /// Coefficient of the friction of the cloth slowing the balls down, which leaves them
+    /// rolling forever if zero
+    #[serde(default)]
+    pub rolling_friction: f64,
+    /// Coefficient of restitution of the collisions between balls, elastic if one
+    #[serde(default = "elastic")]
+    pub ball_restitution: f64,
+    /// Coefficient of restitution of the collisions of the balls with the cushions
+    #[serde(default = "elastic")]
+    pub cushion_restitution: f64,
+}
+
+fn elastic() -> f64 {
+    1.0
+}
+
+/// Coefficients of the friction which couples the spin of the balls to their motion.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct SpinFriction {
+    /// Friction of the cloth against balls sliding on it, until they roll
+    pub sliding_friction: f64,
+    /// Friction of the cloth against the spin of the balls about the vertical
+    #[serde(default)]
+    pub spinning_friction: f64,
+    /// Friction between the surfaces of colliding balls
+    #[serde(default)]
+    pub ball_friction: f64,
+    /// Friction of the cushions against the balls bouncing off them
+    #[serde(default)]
+    pub cushion_friction: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct Hole {
+    pub position: [f64; DIM],
+    pub radius: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct Ball {
+    pub position: [f64; DIM],
+    #[serde(default)]
+    pub velocity: [f64; DIM],
+    /// Spin about the length and width of the table and the vertical, which needs the
+    /// friction of the [`Spin`] section to act
+    #[serde(default)]
+    pub angular_velocity: [f64; 3],
+    pub radius: f64,
+    pub mass: f64,
+}
+
+/// Balls laid out at rest by a generator, with rows that grow along the length of the table
+/// away from the apex, the ball nearest to the cue ball.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "layout", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum Rack {
+    /// Triangle of `rows` rows, the first holding one ball and each other one more
+    Triangle {
+        rows: usize,
+        apex: [f64; DIM],
+        /// Space left between neighbouring balls
+        #[serde(default)]
+        gap: f64,
+        radius: f64,
+        mass: f64,
+    },
+    /// Diamond of the 9 ball game, in rows of 1, 2, 3, 2 and 1 balls
+    Diamond {
+        apex: [f64; DIM],
+        #[serde(default)]
+        gap: f64,
+        radius: f64,
+        mass: f64,
+    },
+    /// Positions read from a file, a ball per line with its coordinates separated by
+    /// whitespace. Empty lines and those starting with `#` are skipped, and a relative path
+    /// is taken from the directory of the scenario.
+    File {
+        path: String,
+        radius: f64,
+        mass: f64,
+    },
+}
+
+impl Rack {
+    pub fn balls(&self) -> Result<Vec<Ball>> {
+        match *self {
+            Rack::Triangle {
+                rows,
+                apex,
+                gap,
+                radius,
+                mass,
+            } => {
+                let row_lengths: Vec<_> = (1..=rows).collect();
+                Ok(Self::rows(&row_lengths, apex, gap, radius, mass))
+            }
+            Rack::Diamond {
+                apex,
+                gap,
+                radius,
+                mass,
+            } => Ok(Self::rows(&[1, 2, 3, 2, 1], apex, gap, radius, mass)),
+            Rack::File {
+                ref path,
+                radius,
+                mass,
+            } => read_positions(path)?
+                .into_iter()
+                .map(|position| Ok(Ball::at_rest(position, radius, mass)))
+                .collect(),
+        }
+    }
+
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Rack::Triangle { radius, .. }
+            | Rack::Diamond { radius, .. }
+            | Rack::File { radius, .. } => radius,
+        }
+    }
+
+    /// Balls in rows of the given lengths, packed as tightly as the gap between them allows.
+    fn rows(
+        row_lengths: &[usize],
+        apex: [f64; DIM],
+        gap: f64,
+        radius: f64,
+        mass: f64,
+    ) -> Vec<Ball> {
+        let spaced_radius = radius + gap / 2.0;
+        let mut balls = Vec::new();
+
+        for (row, &length) in row_lengths.iter().enumerate() {
+            let x = 3f64.sqrt() * spaced_radius * row as f64;
+
+            for n in 0..length {
+                let y = (2.0 * n as f64 - (length - 1) as f64) * spaced_radius;
+                balls.push(Ball::at_rest([apex[0] + x, apex[1] + y], radius, mass));
+            }
+        }
+
+        balls
+    }
+}
+
+/// Checks that the value called `name` in the scenario is positive, which NaN is not.
+fn check_positive(name: &str, value: f64) -> Result<()> {
+    if value > 0.0 {
+        Ok(())
+    } else {
+        bail!("the {name} is {value}, which is not positive")
+    }
+}
+
+/// Checks that the value called `name` in the scenario is finite and not negative.
+fn check_non_negative(name: &str, value: f64) -> Result<()> {
+    // NOTE: NaN fails the comparison
+    if value >= 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        bail!("the {name} is {value}, which is negative or not finite")
+    }
+}
+
+/// Positions listed in a file, as described in [`Rack::File`].
+fn read_positions(path: &str) -> Result<Vec<[f64; DIM]>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("could not read the rack {path}"))?;
+
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let coordinates = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<f64>, _>>()
+                .with_context(|| format!("invalid position {line:?} in the rack {path}"))?;
+
+            <[f64; DIM]>::try_from(coordinates).map_err(|coordinates| {
+                anyhow::anyhow!(
+                    "position {line:?} in the rack {path} has {} coordinates instead of {DIM}",
+                    coordinates.len()
+                )
+            })
+        })
+        .collect()
+}
+
+impl Ball {
+    fn at_rest(position: [f64; DIM], radius: f64, mass: f64) -> Self {
+        Self {
+            position,
+            velocity: [0.0; DIM],
+            angular_velocity: [0.0; 3],
+            radius,
+            mass,
+        }
+    }
+}
+
+impl Table {
+    /// Checks that every ball lies on the table, and that no two balls overlap.
+    pub fn check_placement(&self, balls: &[Particle<DIM>]) -> Result<()> {
+        let bounds = [self.length, self.width];
+
+        for ball in balls {
+            let position = ball.derivatives()[0];
+            let on_table = position
+                .iter()
+                .zip(bounds)
+                .all(|(x, bound)| ball.radius() <= *x && *x <= bound - ball.radius());
+            if !on_table {
+                bail!("ball {} at {position:?} is off the table", ball.id());
+            }
+        }
+
+        for (n, ball) in balls.iter().enumerate() {
+            for other in &balls[n + 1..] {
+                if ball.get_distance(other) < ball.radius() + other.radius() {
+                    bail!("balls {} and {} overlap", ball.id(), other.id());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Scenario {
+    /// Pool table with the white ball shot at a triangle of 15 balls, shipped in
+    /// `scenarios/pool.toml`.
+    fn default() -> Self {
+        toml::from_str(DEFAULT_SCENARIO).expect("the default scenario is valid")
+    }
+}
+
+impl Scenario {
+    pub fn load(path: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+
+        let mut scenario: Self = if path.ends_with(".json") {
+            serde_json::from_str(&contents)?
+        } else {
+            toml::from_str(&contents)?
+        };
+
+        if let Some(Rack::File {
+            path: rack_path, ..
+        }) = &mut scenario.rack
+        {
+            if let Some(directory) = Path::new(path).parent() {
+                *rack_path = directory.join(&*rack_path).to_string_lossy().into_owned();
+            }
+        }
+
+        scenario.validate()?;
+
+        Ok(scenario)
+    }
+
+    /// Checks that the scenario has balls, and that the sizes, masses, stiffnesses,
+    /// restitutions, frictions and position noise in it have values the forces can work with.
+    fn validate(&self) -> Result<()> {
+        // NOTE: A negative noise has no range to draw from, and negative friction would
+        // speed the balls up instead of slowing them down
+        check_non_negative("position noise", self.position_noise)?;
+        check_non_negative("rolling friction", self.forces.rolling_friction)?;
+        if let Some(friction) = self.spin {
+            check_non_negative("sliding friction", friction.sliding_friction)?;
+            check_non_negative("spinning friction", friction.spinning_friction)?;
+            check_non_negative("ball friction", friction.ball_friction)?;
+            check_non_negative("cushion friction", friction.cushion_friction)?;
+        }
+
+        check_positive("table length", self.table.length)?;
+        check_positive("table width", self.table.width)?;
+        check_positive("contact stiffness", self.forces.contact_stiffness)?;
+        check_positive("cushion stiffness", self.forces.cushion_stiffness)?;
+
+        for (name, restitution) in [
+            ("ball", self.forces.ball_restitution),
+            ("cushion", self.forces.cushion_restitution),
+        ] {
+            if !(0.0..=1.0).contains(&restitution) {
+                bail!("the {name} restitution {restitution} lies outside [0, 1]");
+            }
+        }
+
+        for hole in &self.holes {
+            check_positive("hole radius", hole.radius)?;
+        }
+
+        let balls = self.all_balls()?;
+        if balls.is_empty() {
+            bail!("the scenario has no balls");
+        }
+        for (id, ball) in balls.iter().enumerate() {
+            check_positive(&format!("radius of ball {id}"), ball.radius)?;
+            check_positive(&format!("mass of ball {id}"), ball.mass)?;
+        }
+
+        Ok(())
+    }
+
+    /// Every ball of the scenario, those listed first and then those of the rack.
+    pub fn all_balls(&self) -> Result<Vec<Ball>> {
+        let mut balls = self.balls.clone();
+        if let Some(rack) = &self.rack {
+            balls.extend(rack.balls()?);
+        }
+
+        Ok(balls)
+    }
+
+    /// Observer spinning the balls, when the scenario has friction to do it.
+    pub fn spin(&self, delta_t: f64) -> Option<Spin> {
+        self.spin.map(|friction| {
+            Spin::new(delta_t, GRAVITY, friction.sliding_friction)
+                .with_rolling_friction(self.forces.rolling_friction)
+                .with_spinning_friction(friction.spinning_friction)
+                .with_contact_friction(friction.ball_friction, self.forces.contact_stiffness)
+                .with_wall_friction(
+                    friction.cushion_friction,
+                    self.forces.cushion_stiffness,
+                    [self.table.length, self.table.width],
+                )
+        })
+    }
+
+    /// Whether the forces of [`Scenario::force_model`] depend on the velocities of the balls,
+    /// as the damping of inelastic contacts and the friction of the cloth do.
+    pub fn forces_depend_on_velocity(&self) -> bool {
+        let forces = &self.forces;
+        let friction = self.spin.is_none() && forces.rolling_friction > 0.0;
+
+        forces.ball_restitution < 1.0 || forces.cushion_restitution < 1.0 || friction
+    }
+
+    /// Contacts between the balls and with the cushions, and the friction of the cloth,
+    /// unless the balls spin and [`Scenario::spin`] applies it.
+    pub fn force_model(&self) -> impl ForceModel<DIM> {
+        let largest_radius = self
+            .balls
+            .iter()
+            .map(|ball| ball.radius)
+            .chain(self.rack.as_ref().map(Rack::radius))
+            .fold(0.0, f64::max);
+
+        Pairwise::with_cutoff(
+            SoftSphereContact::with_restitution(
+                self.forces.contact_stiffness,
+                self.forces.ball_restitution,
+            ),
+            2.0 * largest_radius,
+        )
+        .plus(WallContact::with_restitution(
+            self.forces.cushion_stiffness,
+            [self.table.length, self.table.width],
+            self.forces.cushion_restitution,
+        ))
+        .plus(KineticFriction::new(
+            if self.spin.is_some() {
+                0.0
+            } else {
+                self.forces.rolling_friction
+            },
+            GRAVITY,
+            CREEP_SPEED,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Default scenario with the spin friction of every kind, which validates.
+    fn spinning_scenario() -> Scenario {
+        Scenario {
+            spin: Some(SpinFriction {
+                sliding_friction: 0.2,
+                spinning_friction: 0.01,
+                ball_friction: 0.05,
+                cushion_friction: 0.1,
+            }),
+            ..Scenario::default()
+        }
+    }
+
+    /// Change of the scenario, with the name of the field it sets as in its errors.
+    type Change = (&'static str, fn(&mut Scenario));
+
+    #[test]
+    fn default_scenario_is_valid() {
+        Scenario::default().validate().unwrap();
+        spinning_scenario().validate().unwrap();
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let cases: [Change; 15] = [
+            ("position noise", |s| s.position_noise = -1e-3),
+            ("position noise", |s| s.position_noise = f64::NAN),
+            ("table length", |s| s.table.length = 0.0),
+            ("table width", |s| s.table.width = -1.0),
+            ("contact stiffness", |s| s.forces.contact_stiffness = 0.0),
+            ("cushion stiffness", |s| {
+                s.forces.cushion_stiffness = f64::NAN
+            }),
+            ("ball restitution", |s| s.forces.ball_restitution = 1.5),
+            ("cushion restitution", |s| {
+                s.forces.cushion_restitution = -0.1
+            }),
+            ("rolling friction", |s| s.forces.rolling_friction = -0.01),
+            ("sliding friction", |s| {
+                s.spin.as_mut().unwrap().sliding_friction = -0.2;
+            }),
+            ("spinning friction", |s| {
+                s.spin.as_mut().unwrap().spinning_friction = -0.01;
+            }),
+            ("ball friction", |s| {
+                s.spin.as_mut().unwrap().ball_friction = f64::INFINITY;
+            }),
+            ("cushion friction", |s| {
+                s.spin.as_mut().unwrap().cushion_friction = -0.1;
+            }),
+            ("hole radius", |s| s.holes[0].radius = 0.0),
+            ("mass of ball 0", |s| s.balls[0].mass = -0.17),
+        ];
+
+        for (field, invalidate) in cases {
+            let mut scenario = spinning_scenario();
+            invalidate(&mut scenario);
+
+            let error = scenario.validate().unwrap_err().to_string();
+            assert!(error.contains(field), "{field}: {error}");
+        }
+    }
+
+    #[test]
+    fn dissipative_scenarios_have_forces_which_depend_on_velocity() {
+        assert!(!Scenario::default().forces_depend_on_velocity());
+
+        let nine_ball: Scenario =
+            toml::from_str(include_str!("../../../scenarios/nine_ball.toml")).unwrap();
+        assert!(nine_ball.forces_depend_on_velocity());
+
+        let dissipations: [Change; 3] = [
+            ("ball restitution", |scenario| {
+                scenario.forces.ball_restitution = 0.9
+            }),
+            ("cushion restitution", |scenario| {
+                scenario.forces.cushion_restitution = 0.9
+            }),
+            ("rolling friction", |scenario| {
+                scenario.forces.rolling_friction = 0.01
+            }),
+        ];
+        for (name, dissipate) in dissipations {
+            let mut scenario = Scenario::default();
+            dissipate(&mut scenario);
+            assert!(scenario.forces_depend_on_velocity(), "{name}");
+        }
+
+        // NOTE: The spin applies the friction of the cloth outside of the force model
+        let mut scenario = spinning_scenario();
+        scenario.forces.rolling_friction = 0.01;
+        assert!(!scenario.forces_depend_on_velocity());
+    }
+
+    #[test]
+    fn scenarios_without_balls_are_rejected() {
+        let mut scenario = Scenario::default();
+        scenario.balls.clear();
+        scenario.rack = None;
+
+        let error = scenario.validate().unwrap_err().to_string();
+        assert!(error.contains("no balls"), "{error}");
+    }
+
+    const RADIUS: f64 = 0.5;
+    const MASS: f64 = 0.17;
+
+    fn assert_positions(balls: &[Ball], expected: &[[f64; DIM]]) {
+        assert_eq!(balls.len(), expected.len());
+
+        for (ball, expected) in balls.iter().zip(expected) {
+            for i in 0..DIM {
+                assert!(
+                    (ball.position[i] - expected[i]).abs() < 1e-12,
+                    "{:?} instead of {expected:?}",
+                    ball.position
+                );
+            }
+            assert_eq!((ball.radius, ball.mass), (RADIUS, MASS));
+        }
+    }
+
+    #[test]
+    fn triangles_grow_a_ball_per_row() {
+        let rack = Rack::Triangle {
+            rows: 5,
+            apex: [1.0, 2.0],
+            gap: 0.0,
+            radius: RADIUS,
+            mass: MASS,
+        };
+        let balls = rack.balls().unwrap();
+        assert_eq!(balls.len(), 15);
+
+        // NOTE: Touching balls sit sqrt(3) radii apart along the table and a diameter across it
+        let row = 3f64.sqrt() * RADIUS;
+        assert_positions(
+            &balls[..6],
+            &[
+                [1.0, 2.0],
+                [1.0 + row, 1.5],
+                [1.0 + row, 2.5],
+                [1.0 + 2.0 * row, 1.0],
+                [1.0 + 2.0 * row, 2.0],
+                [1.0 + 2.0 * row, 3.0],
+            ],
+        );
+    }
+
+    #[test]
+    fn gaps_space_the_balls_apart() {
+        let rack = Rack::Triangle {
+            rows: 2,
+            apex: [0.0, 0.0],
+            gap: 0.2,
+            radius: RADIUS,
+            mass: MASS,
+        };
+
+        let row = 3f64.sqrt() * 0.6;
+        assert_positions(
+            &rack.balls().unwrap(),
+            &[[0.0, 0.0], [row, -0.6], [row, 0.6]],
+        );
+    }
+
+    #[test]
+    fn diamonds_hold_nine_balls() {
+        let rack = Rack::Diamond {
+            apex: [0.0, 0.0],
+            gap: 0.0,
+            radius: RADIUS,
+            mass: MASS,
+        };
+        let balls = rack.balls().unwrap();
+
+        let row = 3f64.sqrt() * RADIUS;
+        assert_positions(
+            &balls,
+            &[
+                [0.0, 0.0],
+                [row, -0.5],
+                [row, 0.5],
+                [2.0 * row, -1.0],
+                [2.0 * row, 0.0],
+                [2.0 * row, 1.0],
+                [3.0 * row, -0.5],
+                [3.0 * row, 0.5],
+                [4.0 * row, 0.0],
+            ],
+        );
+    }
+
+    #[test]
+    fn files_list_a_ball_per_line() {
+        let path = std::env::temp_dir().join(format!("rack-{}.txt", std::process::id()));
+        fs::write(&path, "# Two balls\n1.0 0.5\n\n  2.5 0.25  \n").unwrap();
+        let rack = Rack::File {
+            path: path.to_string_lossy().into_owned(),
+            radius: RADIUS,
+            mass: MASS,
+        };
+
+        let balls = rack.balls();
+        fs::write(&path, "1.0 0.5 0.0\n").unwrap();
+        let too_many_coordinates = rack.balls();
+        fs::remove_file(&path).unwrap();
+
+        assert_positions(&balls.unwrap(), &[[1.0, 0.5], [2.5, 0.25]]);
+        let error = too_many_coordinates.unwrap_err().to_string();
+        assert!(error.contains("3 coordinates"), "{error}");
+    }
+
+    fn particles(positions: &[[f64; DIM]]) -> Vec<Particle<DIM>> {
+        positions
+            .iter()
+            .enumerate()
+            .map(|(id, &position)| Particle::new(id, position, [0.0; DIM], [0.0; DIM], 0.1, MASS))
+            .collect()
+    }
+
+    #[test]
+    fn placements_must_lie_on_the_table_without_overlaps() {
+        let table = Table {
+            length: 2.0,
+            width: 1.0,
+        };
+
+        table
+            .check_placement(&particles(&[[0.1, 0.1], [0.5, 0.1], [1.9, 0.9]]))
+            .unwrap();
+
+        for (positions, expected) in [
+            (
+                vec![[0.5, 0.5], [0.05, 0.5]],
+                "ball 1 at [0.05, 0.5] is off the table",
+            ),
+            (
+                vec![[0.5, 0.5], [1.5, 0.95]],
+                "ball 1 at [1.5, 0.95] is off the table",
+            ),
+            (vec![[0.5, 0.5], [0.5, 0.69]], "balls 0 and 1 overlap"),
+        ] {
+            let error = table
+                .check_placement(&particles(&positions))
+                .unwrap_err()
+                .to_string();
+            assert_eq!(error, expected);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use anyhow::bail;
+
+use crate::{constants::DIM, Result};
+
+/// Direction the cue ball is shot in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aim {
+    /// Angle in degrees, counterclockwise from the length of the table
+    Angle(f64),
+    /// Centre of the ball with this id, for a full hit
+    Ball(usize),
+    /// Point on the table
+    Point([f64; DIM]),
+}
+
+/// Initial velocity and spin of the cue ball, in place of those of the scenario. The
+/// direction, speed or spin left out are those of the scenario.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Shot {
+    pub aim: Option<Aim>,
+    pub speed: Option<f64>,
+    /// Spin about the horizontal axis across the shot, as a multiple of the spin of the ball
+    /// rolling at its speed, negative for back spin
+    pub top_spin: Option<f64>,
+    /// Spin about the vertical, counterclockwise seen from above, as a multiple of the spin
+    /// of the ball rolling at its speed
+    pub side_spin: Option<f64>,
+}
+
+impl Shot {
+    /// Velocity of the cue ball, the first of `positions`, which would otherwise move with
+    /// `velocity`. Balls and points are aimed at from where the cue ball is placed.
+    pub fn velocity(&self, positions: &[[f64; DIM]], velocity: [f64; DIM]) -> Result<[f64; DIM]> {
+        let Some(&cue_position) = positions.first() else {
+            bail!("there is no cue ball to shoot");
+        };
+
+        let speed = self.speed.unwrap_or_else(|| norm(velocity));
+        let direction = match self.aim {
+            None => velocity,
+            Some(Aim::Angle(angle)) => {
+                let angle = angle.to_radians();
+                [angle.cos(), angle.sin()]
+            }
+            Some(Aim::Ball(0)) => bail!("the cue ball cannot be aimed at itself"),
+            Some(Aim::Ball(id)) => match positions.get(id) {
+                Some(&target) => aim_at(target, cue_position)?,
+                None => bail!("there is no ball {id} to aim at"),
+            },
+            Some(Aim::Point(target)) => aim_at(target, cue_position)?,
+        };
+
+        let length = norm(direction);
+        if length == 0.0 {
+            if speed == 0.0 {
+                return Ok([0.0; DIM]);
+            }
+            bail!("the shot has a speed but no direction");
+        }
+
+        Ok(direction.map(|x| speed * x / length))
+    }
+
+    /// Angular velocity of the cue ball of `radius` shot with `velocity`, which would
+    /// otherwise spin with `angular_velocity`.
+    pub fn angular_velocity(
+        &self,
+        velocity: [f64; DIM],
+        radius: f64,
+        mut angular_velocity: [f64; 3],
+    ) -> [f64; 3] {
+        if let Some(top_spin) = self.top_spin {
+            // NOTE: Rolling without sliding, the bottom of the ball stands still on the cloth
+            angular_velocity[0] = -top_spin * velocity[1] / radius;
+            angular_velocity[1] = top_spin * velocity[0] / radius;
+        }
+        if let Some(side_spin) = self.side_spin {
+            angular_velocity[2] = side_spin * norm(velocity) / radius;
+        }
+
+        angular_velocity
+    }
+}
+
+/// Direction from the cue ball at `cue_position` to `target`, which has none if they coincide.
+fn aim_at(target: [f64; DIM], cue_position: [f64; DIM]) -> Result<[f64; DIM]> {
+    if target == cue_position {
+        bail!("the cue ball cannot be aimed at {target:?}, where it stands");
+    }
+
+    Ok(difference(target, cue_position))
+}
+
+fn difference(a: [f64; DIM], b: [f64; DIM]) -> [f64; DIM] {
+    std::array::from_fn(|i| a[i] - b[i])
+}
+
+fn norm(vector: [f64; DIM]) -> f64 {
+    vector.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSITIONS: [[f64; DIM]; 3] = [[1.0, 1.0], [4.0, 5.0], [1.0, 3.0]];
+    const VELOCITY: [f64; DIM] = [0.0, -2.0];
+
+    fn aimed(aim: Aim, speed: f64) -> Shot {
+        Shot {
+            aim: Some(aim),
+            speed: Some(speed),
+            ..Shot::default()
+        }
+    }
+
+    fn assert_close<const N: usize>(found: [f64; N], expected: [f64; N]) {
+        for i in 0..N {
+            assert!(
+                (found[i] - expected[i]).abs() < 1e-12,
+                "{found:?} instead of {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn shots_without_aim_or_speed_keep_the_velocity_of_the_scenario() {
+        let velocity = Shot::default().velocity(&POSITIONS, VELOCITY).unwrap();
+        assert_eq!(velocity, VELOCITY);
+
+        let shot = Shot {
+            speed: Some(3.0),
+            ..Shot::default()
+        };
+        assert_close(shot.velocity(&POSITIONS, VELOCITY).unwrap(), [0.0, -3.0]);
+    }
+
+    #[test]
+    fn angles_turn_counterclockwise_from_the_length_of_the_table() {
+        for (angle, expected) in [
+            (0.0, [2.0, 0.0]),
+            (90.0, [0.0, 2.0]),
+            (180.0, [-2.0, 0.0]),
+            (-45.0, [2f64.sqrt(), -(2f64.sqrt())]),
+        ] {
+            let velocity = aimed(Aim::Angle(angle), 2.0)
+                .velocity(&POSITIONS, VELOCITY)
+                .unwrap();
+            assert_close(velocity, expected);
+        }
+    }
+
+    #[test]
+    fn balls_and_points_are_aimed_at_from_the_cue_ball() {
+        // NOTE: Ball 1 lies on a 3-4-5 triangle from the cue ball
+        let velocity = aimed(Aim::Ball(1), 10.0)
+            .velocity(&POSITIONS, VELOCITY)
+            .unwrap();
+        assert_close(velocity, [6.0, 8.0]);
+
+        let velocity = aimed(Aim::Ball(2), 0.5)
+            .velocity(&POSITIONS, VELOCITY)
+            .unwrap();
+        assert_close(velocity, [0.0, 0.5]);
+
+        let velocity = aimed(Aim::Point([0.0, 1.0]), 3.0)
+            .velocity(&POSITIONS, VELOCITY)
+            .unwrap();
+        assert_close(velocity, [-3.0, 0.0]);
+    }
+
+    #[test]
+    fn aims_without_a_direction_are_rejected() {
+        for (aim, speed, expected) in [
+            (Aim::Ball(0), 1.0, "the cue ball cannot be aimed at itself"),
+            (Aim::Ball(3), 1.0, "there is no ball 3 to aim at"),
+            (
+                Aim::Point([1.0, 1.0]),
+                1.0,
+                "the cue ball cannot be aimed at [1.0, 1.0], where it stands",
+            ),
+            (
+                Aim::Point([1.0, 1.0]),
+                0.0,
+                "the cue ball cannot be aimed at [1.0, 1.0], where it stands",
+            ),
+        ] {
+            let error = aimed(aim, speed)
+                .velocity(&POSITIONS, VELOCITY)
+                .unwrap_err();
+            assert_eq!(error.to_string(), expected);
+        }
+
+        let error = Shot::default().velocity(&[], VELOCITY).unwrap_err();
+        assert_eq!(error.to_string(), "there is no cue ball to shoot");
+    }
+
+    #[test]
+    fn shots_without_speed_or_direction_leave_the_ball_at_rest() {
+        let velocity = Shot::default().velocity(&POSITIONS, [0.0; DIM]).unwrap();
+        assert_eq!(velocity, [0.0; DIM]);
+
+        let shot = Shot {
+            speed: Some(1.0),
+            ..Shot::default()
+        };
+        let error = shot.velocity(&POSITIONS, [0.0; DIM]).unwrap_err();
+        assert_eq!(error.to_string(), "the shot has a speed but no direction");
+    }
+
+    #[test]
+    fn spins_are_multiples_of_the_spin_of_rolling() {
+        let velocity = [3.0, 4.0];
+        let radius = 0.5;
+        let angular_velocity = [1.0, 2.0, 3.0];
+
+        let unchanged = Shot::default().angular_velocity(velocity, radius, angular_velocity);
+        assert_eq!(unchanged, angular_velocity);
+
+        // NOTE: Rolling at [3, 4] the ball spins at [-4, 3] / 0.5 about the horizontal
+        let rolling = Shot {
+            top_spin: Some(1.0),
+            ..Shot::default()
+        };
+        assert_close(
+            rolling.angular_velocity(velocity, radius, angular_velocity),
+            [-8.0, 6.0, 3.0],
+        );
+
+        let back_and_side = Shot {
+            top_spin: Some(-0.5),
+            side_spin: Some(2.0),
+            ..Shot::default()
+        };
+        assert_close(
+            back_and_side.angular_velocity(velocity, radius, angular_velocity),
+            [4.0, -3.0, 20.0],
+        );
+    }
+}
@@ -1,162 +1,264 @@
+use std::{cell::RefCell, ops::ControlFlow, rc::Rc};
+
+use anyhow::bail;
 use integration_dynamics::{
-    methods::{
-        Beeman, Euler, EulerMod, EulerPredictorCorrector, GearPredictorCorrector,
-        IntegrationMethod, VelocityVerlet, Verlet, VerletLeapFrog,
-    },
-    particle::Particle,
-    Integration,
+    checkpoint::Checkpoint,
+    particle::{Particle, ParticleState},
+    simulation::Simulation,
+    system::ParticleSystem,
+    Integration, MethodSettings,
 };
 
-use crate::constants::{
-    self, acceleration_function, BALL_COUNT, BALL_MASS, BALL_RADIUS, BALL_SPACING_LOWER_BOUND,
-    BALL_SPACING_RANGE, DIM, HOLE_RADIUS, HOLE_VARIANTS, TABLE_WIDTH,
+use crate::{
+    constants::{CREEP_SPEED, DIM},
+    scenario::{Hole, Scenario},
+    shot::Shot,
+    Result,
 };
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Simulation of the balls, which stops with `Ok` once only the stop condition number of
+/// balls is left on the table or, on a cloth with friction, once every ball rests without
+/// spinning, or with the first error of an output.
+pub type BilliardsSimulation<'a> = Simulation<'a, DIM, Result<()>>;
 
-pub struct Billiards {
-    balls: Vec<Particle<DIM>>,
-    integration_method: Box<dyn IntegrationMethod<DIM>>,
-    include_holes: bool,
-    ball_count_stop_condition: usize,
+/// Time a ball fell into a hole, and its id.
+#[derive(Debug, Clone, Copy)]
+pub struct Pocket {
+    pub time: f64,
+    pub id: usize,
 }
 
-impl Billiards {
+pub struct Billiards<'a> {
+    simulation: BilliardsSimulation<'a>,
+    step_sizes: Rc<RefCell<Vec<f64>>>,
+    pockets: Rc<RefCell<Vec<Pocket>>>,
+}
+
+impl<'a> Billiards<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         delta_t: f64,
-        integration_method: &Integration,
+        steps_per_output: usize,
+        integration: &Integration,
+        absolute_tolerance: f64,
+        relative_tolerance: f64,
+        max_step: Option<f64>,
+        gear_order: usize,
+        scenario: &Scenario,
         fixed_ball_spacing: bool,
+        seed: u64,
+        cue_position: Option<[f64; DIM]>,
         white_offset: f64,
-        initial_velocity: [f64; DIM],
+        shot: Option<Shot>,
         include_holes: bool,
         ball_count_stop_condition: usize,
-    ) -> Self {
-        assert!(ball_count_stop_condition <= BALL_COUNT);
+        checkpoint: Option<&Checkpoint>,
+    ) -> Result<Self> {
+        let scenario_balls = scenario.all_balls()?;
+        if scenario_balls.len() < ball_count_stop_condition {
+            bail!(
+                "the scenario has {} balls, fewer than the {ball_count_stop_condition} to stop at",
+                scenario_balls.len()
+            );
+        }
 
-        let mut rng = rand::thread_rng();
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let noise = scenario.position_noise;
 
         let mut get_ball_spacing = move || {
             if fixed_ball_spacing {
                 0.0
             } else {
-                rng.gen_range(BALL_SPACING_RANGE)
+                rng.gen_range(-noise..=noise)
             }
         };
 
-        let mut balls = Vec::with_capacity(BALL_COUNT);
-        let mut ball_id = 0;
-
-        let white_ball = Particle::new(
-            ball_id,
-            [TABLE_WIDTH / 2.0, TABLE_WIDTH / 2.0 + white_offset],
-            initial_velocity,
-            [0.0, 0.0],
-            BALL_RADIUS,
-            BALL_MASS,
-        );
-
-        balls.push(white_ball);
-
-        for (x, y) in constants::get_balls_starting_position() {
-            let x_spacing = get_ball_spacing();
-            let y_spacing = get_ball_spacing();
-            ball_id += 1;
-            let ball = Particle::new(
-                ball_id,
-                [x + x_spacing, y + y_spacing],
-                [0.0, 0.0],
-                [0.0, 0.0],
-                BALL_RADIUS,
-                BALL_MASS,
-            );
+        let positions: Vec<_> = scenario_balls
+            .iter()
+            .enumerate()
+            .map(|(ball_id, ball)| {
+                let mut position = ball.position;
+                if ball_id == 0 {
+                    position = cue_position.unwrap_or(position);
+                    position[1] += white_offset;
+                } else {
+                    for x in &mut position {
+                        *x += get_ball_spacing();
+                    }
+                }
 
-            balls.push(ball);
+                position
+            })
+            .collect();
+
+        let mut velocities: Vec<_> = scenario_balls.iter().map(|ball| ball.velocity).collect();
+        let mut angular_velocities: Vec<_> = scenario_balls
+            .iter()
+            .map(|ball| ball.angular_velocity)
+            .collect();
+        if let (Some(shot), Some(velocity), Some(angular_velocity)) =
+            (shot, velocities.first_mut(), angular_velocities.first_mut())
+        {
+            *velocity = shot.velocity(&positions, *velocity)?;
+            *angular_velocity =
+                shot.angular_velocity(*velocity, scenario_balls[0].radius, *angular_velocity);
         }
 
-        for ball in &balls {
-            for other_ball in &balls {
-                if ball != other_ball {
-                    let distance = ball.get_distance(other_ball);
-                    assert!(BALL_SPACING_LOWER_BOUND <= distance);
-                }
-            }
+        let spin = scenario.spin(delta_t);
+        if spin.is_none() && angular_velocities.iter().flatten().any(|&w| w != 0.0) {
+            bail!("the balls spin, but the scenario has no spin friction to act on them");
+        }
+        if spin.is_some()
+            && matches!(
+                integration,
+                Integration::Verlet | Integration::VerletLeapFrog
+            )
+        {
+            bail!("spin needs a method which steps from the velocities, unlike {integration:?}");
         }
 
-        let integration_method: Box<dyn IntegrationMethod<DIM>> = match integration_method {
-            Integration::Euler => Box::new(Euler::new(acceleration_function, delta_t)),
-            Integration::EulerMod => Box::new(EulerMod::new(acceleration_function, delta_t)),
-            Integration::Verlet => {
-                Box::new(Verlet::new(acceleration_function, &mut balls, delta_t))
-            }
-            Integration::Beeman => {
-                Box::new(Beeman::new(acceleration_function, &mut balls, delta_t))
-            }
-            Integration::GearPredictorCorrector => {
-                let particles_to_init = balls
-                    .iter_mut()
-                    .map(|b| (b, vec![[0.0; DIM], [0.0; DIM], [0.0; DIM]]))
-                    .collect();
-                Box::new(GearPredictorCorrector::new(
-                    acceleration_function,
-                    false,
-                    particles_to_init,
-                    delta_t,
-                ))
-            }
-            Integration::VerletLeapFrog => Box::new(VerletLeapFrog::new(
-                acceleration_function,
-                &mut balls,
-                delta_t,
-            )),
-            Integration::VelocityVerlet => {
-                Box::new(VelocityVerlet::new(acceleration_function, delta_t))
-            }
-            Integration::EulerPredictorCorrector => {
-                Box::new(EulerPredictorCorrector::new(acceleration_function, delta_t))
-            }
+        let balls: Vec<_> = scenario_balls
+            .iter()
+            .enumerate()
+            .map(|(ball_id, ball)| {
+                Particle::new(
+                    ball_id,
+                    positions[ball_id],
+                    velocities[ball_id],
+                    [0.0; DIM],
+                    ball.radius,
+                    ball.mass,
+                )
+                .with_angular_velocity(angular_velocities[ball_id])
+            })
+            .collect();
+        scenario.table.check_placement(&balls)?;
+
+        let mut balls = ParticleSystem::new(&balls);
+        let force_model = scenario.force_model();
+        let force_depends_on_velocity = scenario.forces_depend_on_velocity();
+        let step_sizes = Rc::new(RefCell::new(Vec::new()));
+        let record_step_size = {
+            let step_sizes = Rc::clone(&step_sizes);
+            move |step| step_sizes.borrow_mut().push(step)
         };
 
-        Self {
-            balls,
-            integration_method,
-            include_holes,
-            ball_count_stop_condition,
+        let integration_method = integration.method(
+            force_model,
+            &mut balls,
+            delta_t,
+            MethodSettings {
+                absolute_tolerance,
+                relative_tolerance,
+                max_step,
+                gear_order,
+                force_depends_on_velocity,
+                step_observer: Some(Box::new(record_step_size)),
+            },
+        )?;
+
+        let mut simulation = Simulation::new(balls, integration_method, delta_t)
+            .with_steps_per_output(steps_per_output);
+        simulation.on_step_error(|error| Err(error.into()));
+        if let Some(checkpoint) = checkpoint {
+            simulation.restore(checkpoint, *integration)?;
         }
-    }
 
-    fn is_colliding_with_hole(particle: &Particle<DIM>) -> bool {
-        let r = particle.derivatives()[0];
-        let particle_radius = particle.radius();
-        for hole in &HOLE_VARIANTS {
-            let hole_r = hole.coordinates();
-            let distance = r
-                .iter()
-                .zip(hole_r.iter())
-                .map(|(a, b)| (a - b).powi(2))
-                .sum::<f64>()
-                .sqrt();
-            if distance <= (particle_radius + HOLE_RADIUS) {
-                return true;
-            }
+        // NOTE: Spin the balls before the pockets are looked for, so that the events are
+        // found on the velocities the next step starts from
+        if let Some(spin) = spin {
+            simulation.observe(spin);
         }
-        false
-    }
 
-    pub fn run(&mut self, steps: usize) -> &Vec<Particle<DIM>> {
-        for _ in 0..steps {
-            self.integration_method.advance_step(&mut self.balls);
-            if self.include_holes {
-                self.balls
-                    .retain(|particle| !Self::is_colliding_with_hole(particle));
+        let pockets = Rc::new(RefCell::new(Vec::new()));
+        if include_holes {
+            let pockets = Rc::clone(&pockets);
+            let holes = scenario.holes.clone();
 
-                if self.balls.len() == self.ball_count_stop_condition {
-                    break;
-                }
-            }
+            simulation.on_event(
+                move |ball| Self::hole_clearance(ball, &holes),
+                move |pocket, balls| {
+                    let id = pocket.particle.id;
+                    pockets.borrow_mut().push(Pocket {
+                        time: pocket.time,
+                        id,
+                    });
+                    balls.retain(|ball| ball.id != id);
+
+                    ControlFlow::Continue(())
+                },
+            );
         }
-        &self.balls
+
+        let friction = scenario.forces.rolling_friction > 0.0;
+        simulation.on_step(move |_, balls| {
+            let at_rest = || {
+                (0..balls.len()).all(|n| {
+                    let speed = balls.velocities()[n]
+                        .iter()
+                        .map(|x| x * x)
+                        .sum::<f64>()
+                        .sqrt();
+                    let spin = balls.angular_velocities()[n]
+                        .iter()
+                        .map(|x| x * x)
+                        .sum::<f64>()
+                        .sqrt();
+
+                    speed < CREEP_SPEED && balls.radius(n) * spin < CREEP_SPEED
+                })
+            };
+
+            if balls.len() == ball_count_stop_condition || (friction && at_rest()) {
+                ControlFlow::Break(Ok(()))
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+
+        Ok(Self {
+            simulation,
+            step_sizes,
+            pockets,
+        })
+    }
+
+    /// Distance from the ball to the nearest hole it would fall into, which is zero or
+    /// negative once it overlaps a hole.
+    fn hole_clearance(particle: &ParticleState<DIM>, holes: &[Hole]) -> f64 {
+        let r = particle.position;
+        let particle_radius = particle.radius;
+
+        holes
+            .iter()
+            .map(|hole| {
+                let distance = r
+                    .iter()
+                    .zip(hole.position.iter())
+                    .map(|(a, b)| (a - b).powi(2))
+                    .sum::<f64>()
+                    .sqrt();
+
+                distance - (particle_radius + hole.radius)
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Sizes of the internal steps adaptive methods took since they were last taken out of
+    /// the list.
+    pub fn step_sizes(&self) -> Rc<RefCell<Vec<f64>>> {
+        Rc::clone(&self.step_sizes)
+    }
+
+    /// Balls which fell into a hole since they were last taken out of the list, in the
+    /// order they did.
+    pub fn pockets(&self) -> Rc<RefCell<Vec<Pocket>>> {
+        Rc::clone(&self.pockets)
     }
 
-    pub fn balls(&self) -> &Vec<Particle<DIM>> {
-        &self.balls
+    pub fn simulation_mut(&mut self) -> &mut BilliardsSimulation<'a> {
+        &mut self.simulation
     }
 }
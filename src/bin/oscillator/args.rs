@@ -1,24 +1,114 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 use integration_dynamics::Integration;
 
+use crate::constants::{AMORTIGUATION_CONSTANT, RESTORING_FORCE_CONSTANT};
+
 #[derive(Parser, Debug)]
-#[command(name = "Oscillation Integration", author, version, about)]
+#[command(
+    name = "Oscillation Integration",
+    author,
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct Cli {
-    #[arg(value_enum)]
-    pub integration_method: Integration,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[arg(value_enum, required = true)]
+    pub integration_method: Option<Integration>,
 
+    /// Time step of the simulation. Adaptive methods ignore it, advancing a whole output
+    /// step at a time in internal steps of up to the max step
     #[arg(short, long, default_value_t = 1e-4)]
     pub simulation_delta_t: f64,
 
-    #[arg(short, long, default_value_t = 1e-2)]
-    pub output_delta_t: f64,
+    /// Largest internal step of the adaptive methods, which is otherwise only bounded by
+    /// the end of the output step
+    #[arg(long)]
+    pub max_step: Option<f64>,
 
-    #[arg(short, long, default_value_t = 5.0)]
-    pub max_time: f64,
+    #[command(flatten)]
+    pub model: ModelArgs,
 
     #[arg(short, long, default_value_t = String::from("./oscillator.xyz"))]
     pub xyz_output_path: String,
 
     #[arg(short, long, default_value_t = String::from("./oscillator.txt"))]
     pub data_output_path: String,
+
+    #[arg(long)]
+    pub step_sizes_output_path: Option<String>,
+
+    /// Path of the energy and momentum of the oscillator at every output step
+    #[arg(long)]
+    pub diagnostics_output_path: Option<String>,
+
+    /// Path of a checkpoint of the simulation, overwritten at every output step
+    #[arg(long)]
+    pub checkpoint_path: Option<String>,
+
+    /// Resume from a checkpoint, given the arguments of the run which saved it, appending
+    /// to its output files
+    #[arg(long)]
+    pub resume: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Integrate with every method and step size, and fit the order of convergence of
+    /// the mean squared error against the analytic solution. The symplectic methods are
+    /// left out unless the amortiguation constant is zero
+    Convergence(ConvergenceArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ConvergenceArgs {
+    /// Time steps to integrate with, each no larger than the output delta t
+    #[arg(long, value_delimiter = ',', default_values_t = [1e-2, 1e-3, 1e-4, 1e-5])]
+    pub delta_ts: Vec<f64>,
+
+    /// Mean squared errors below this value are dominated by round-off, and are left out
+    /// of the fit of the order
+    #[arg(long, default_value_t = 1e-24)]
+    pub error_floor: f64,
+
+    #[command(flatten)]
+    pub model: ModelArgs,
+
+    /// Path of the table, written as JSON if it ends in `.json` and as CSV otherwise
+    #[arg(long, default_value_t = String::from("./convergence.csv"))]
+    pub table_output_path: String,
+}
+
+/// Parameters of the oscillator and the integration methods.
+#[derive(Args, Debug)]
+pub struct ModelArgs {
+    #[arg(long, default_value_t = 1e-9)]
+    pub absolute_tolerance: f64,
+
+    #[arg(long, default_value_t = 1e-6)]
+    pub relative_tolerance: f64,
+
+    /// Highest derivative of the position tracked by the Gear predictor corrector
+    #[arg(long, default_value_t = 5)]
+    pub gear_order: usize,
+
+    /// Estimate the initial derivatives above the acceleration numerically for the Gear
+    /// predictor corrector, instead of using their analytic values
+    #[arg(long)]
+    pub bootstrap_gear_derivatives: bool,
+
+    #[arg(short, long, default_value_t = 1e-2)]
+    pub output_delta_t: f64,
+
+    #[arg(short, long, default_value_t = 5.0)]
+    pub max_time: f64,
+
+    #[arg(long, default_value_t = RESTORING_FORCE_CONSTANT)]
+    pub restoring_force_constant: f64,
+
+    #[arg(long, default_value_t = AMORTIGUATION_CONSTANT)]
+    pub amortiguation_constant: f64,
 }
@@ -1,4 +1,4 @@
-use integration_dynamics::particle::Particle;
+use integration_dynamics::forces::{ForceModel, LinearDamper, LinearSpring};
 
 pub const DIM: usize = 1;
 
@@ -8,40 +8,58 @@ pub const AMPLITUDE: f64 = 1.0;
 pub const RESTORING_FORCE_CONSTANT: f64 = 1e4;
 pub const AMORTIGUATION_CONSTANT: f64 = 1e2;
 pub const INITIAL_POSITION: [f64; DIM] = [1.0];
-pub const INITIAL_VELOCITY: [f64; DIM] =
-    [-AMPLITUDE * AMORTIGUATION_CONSTANT / (2.0 * PARTICLE_MASS)];
-pub const INITIAL_ACCELERATION: [f64; DIM] = [(-RESTORING_FORCE_CONSTANT * INITIAL_POSITION[0]
-    - AMORTIGUATION_CONSTANT * INITIAL_VELOCITY[0])
-    / PARTICLE_MASS];
-pub const INITIAL_THIRD_DERIVATIVE: [f64; DIM] = [(-RESTORING_FORCE_CONSTANT
-    * INITIAL_VELOCITY[0]
-    - AMORTIGUATION_CONSTANT * INITIAL_ACCELERATION[0])
-    / PARTICLE_MASS];
-pub const INITIAL_FOURTH_DERIVATIVE: [f64; DIM] = [(-RESTORING_FORCE_CONSTANT
-    * INITIAL_ACCELERATION[0]
-    - AMORTIGUATION_CONSTANT * INITIAL_THIRD_DERIVATIVE[0])
-    / PARTICLE_MASS];
-pub const INITIAL_FIFTH_DERIVATIVE: [f64; DIM] = [(-RESTORING_FORCE_CONSTANT
-    * INITIAL_THIRD_DERIVATIVE[0]
-    - AMORTIGUATION_CONSTANT * INITIAL_FOURTH_DERIVATIVE[0])
-    / PARTICLE_MASS];
-
-pub fn acceleration_function(particle: &Particle<DIM>, _others: &[Particle<DIM>]) -> [f64; DIM] {
-    let mut acceleration = [0.0; DIM];
-    let r = particle.derivatives();
-
-    for i in 0..DIM {
-        acceleration[i] = (-RESTORING_FORCE_CONSTANT * r[0][i] - AMORTIGUATION_CONSTANT * r[1][i])
-            / particle.mass();
-    }
 
-    acceleration
+#[derive(Debug, Clone, Copy)]
+pub struct OscillatorConstants {
+    restoring_force_constant: f64,
+    amortiguation_constant: f64,
 }
 
-const A: f64 = -AMORTIGUATION_CONSTANT / (2.0 * PARTICLE_MASS);
-const B: f64 = RESTORING_FORCE_CONSTANT / PARTICLE_MASS
-    - AMORTIGUATION_CONSTANT * AMORTIGUATION_CONSTANT / (4.0 * PARTICLE_MASS * PARTICLE_MASS);
+impl OscillatorConstants {
+    pub fn new(restoring_force_constant: f64, amortiguation_constant: f64) -> Self {
+        Self {
+            restoring_force_constant,
+            amortiguation_constant,
+        }
+    }
+
+    /// Whether the damping makes the force depend on the velocity.
+    pub fn is_damped(&self) -> bool {
+        self.amortiguation_constant != 0.0
+    }
+
+    fn force(&self, position: f64, velocity: f64) -> f64 {
+        -self.restoring_force_constant * position - self.amortiguation_constant * velocity
+    }
+
+    pub fn initial_velocity(&self) -> [f64; DIM] {
+        [-AMPLITUDE * self.amortiguation_constant / (2.0 * PARTICLE_MASS)]
+    }
+
+    /// Returns the derivatives of the position from the second to the fifth,
+    /// obtained by repeatedly differentiating the equation of motion.
+    pub fn initial_higher_derivatives(&self) -> [[f64; DIM]; 4] {
+        let mut derivatives = [[0.0; DIM]; 4];
+        let mut previous = [INITIAL_POSITION[0], self.initial_velocity()[0]];
+
+        for derivative in &mut derivatives {
+            derivative[0] = self.force(previous[0], previous[1]) / PARTICLE_MASS;
+            previous = [previous[1], derivative[0]];
+        }
+
+        derivatives
+    }
+
+    pub fn force_model(&self) -> impl ForceModel<DIM> {
+        LinearSpring::new(self.restoring_force_constant, [0.0; DIM])
+            .plus(LinearDamper::new(self.amortiguation_constant))
+    }
+
+    pub fn analytic_solution(&self, t: f64) -> f64 {
+        let a = -self.amortiguation_constant / (2.0 * PARTICLE_MASS);
+        let b = self.restoring_force_constant / PARTICLE_MASS
+            - self.amortiguation_constant.powi(2) / (4.0 * PARTICLE_MASS.powi(2));
 
-pub fn analytic_solution(t: f64) -> f64 {
-    AMPLITUDE * (A * t).exp() * (B.sqrt() * t).cos()
+        AMPLITUDE * (a * t).exp() * (b.sqrt() * t).cos()
+    }
 }
@@ -0,0 +1,92 @@
+use std::ops::ControlFlow;
+
+use clap::ValueEnum;
+use integration_dynamics::{convergence::fit_order, Integration};
+
+use crate::{
+    args::{ConvergenceArgs, ModelArgs},
+    constants::OscillatorConstants,
+    simulation::Oscillator,
+    Result,
+};
+
+/// Mean squared error of a method for each of the swept time steps, and the order of
+/// convergence fitted to them.
+pub struct MethodConvergence {
+    pub integration: Integration,
+    pub mean_squared_errors: Vec<f64>,
+    pub order: Option<f64>,
+}
+
+pub fn sweep(args: &ConvergenceArgs) -> Result<Vec<MethodConvergence>> {
+    let constants = OscillatorConstants::new(
+        args.model.restoring_force_constant,
+        args.model.amortiguation_constant,
+    );
+
+    // NOTE: The symplectic compositions are left out of a damped oscillator, where they fall
+    // to first order
+    Integration::value_variants()
+        .iter()
+        .filter(|integration| {
+            !constants.is_damped() || integration.supports_velocity_dependent_forces()
+        })
+        .map(|integration| {
+            let mean_squared_errors = args
+                .delta_ts
+                .iter()
+                .map(|&delta_t| mean_squared_error(integration, delta_t, &args.model, &constants))
+                .collect::<Result<Vec<_>>>()?;
+
+            let order = fit_order(&args.delta_ts, &mean_squared_errors, args.error_floor);
+
+            Ok(MethodConvergence {
+                integration: *integration,
+                mean_squared_errors,
+                order,
+            })
+        })
+        .collect()
+}
+
+/// Mean squared error of the position against the analytic solution, on every output step,
+/// or NaN when the method could not take one of the steps.
+fn mean_squared_error(
+    integration: &Integration,
+    delta_t: f64,
+    model: &ModelArgs,
+    constants: &OscillatorConstants,
+) -> Result<f64> {
+    let output_iters = (model.max_time / model.output_delta_t) as usize;
+    let mut squared_error = 0.0;
+
+    {
+        let mut oscillator = Oscillator::new(
+            delta_t,
+            model.output_delta_t,
+            integration,
+            model.absolute_tolerance,
+            model.relative_tolerance,
+            Some(delta_t),
+            model.gear_order,
+            model.bootstrap_gear_derivatives,
+            constants,
+            None,
+        )?;
+
+        let simulation = oscillator.simulation_mut();
+        simulation.on_output(|time, system| {
+            let position = system.positions()[0][0];
+            squared_error += (position - constants.analytic_solution(time)).powi(2);
+
+            ControlFlow::Continue(())
+        });
+        // NOTE: A step the method could not take leaves the error unknown, which the fit of
+        // the order leaves out as it does every error which is not finite
+        if simulation.run_outputs(output_iters).is_break() {
+            return Ok(f64::NAN);
+        }
+    }
+
+    Ok(squared_error / output_iters as f64)
+}
@@ -1,52 +1,162 @@
 use std::{
-    fs::File,
+    fs::{File, OpenOptions},
     io::{BufWriter, Write},
 };
 
-use crate::Result;
+use clap::ValueEnum;
+use integration_dynamics::diagnostics::Diagnostics;
+use serde::Serialize;
 
-pub fn output_simulation(path: &str, steps: &[(f64, f64)]) -> Result<()> {
-    let file = File::create(path)?;
+use crate::{constants::DIM, convergence::MethodConvergence, Result};
+
+/// Opens an output file, appending to it when resuming a simulation which wrote its start.
+pub fn open_output(path: &str, append: bool) -> Result<File> {
+    let file = if append {
+        OpenOptions::new().append(true).create(true).open(path)?
+    } else {
+        File::create(path)?
+    };
+
+    Ok(file)
+}
+
+pub fn output_simulation(file: &File, position: f64, velocity: f64) -> Result<()> {
     let mut writer = BufWriter::new(file);
 
-    for step in steps {
-        writeln!(writer, "3")?;
-        writeln!(writer, "Properties=pos:R:1:velo:R:1:radius:R:1",)?;
-        writeln!(writer, "{:.8} {:.8} 0.1", step.0, step.1)?;
-        writeln!(writer, "-1.5, 0.0 0.0")?;
-        writeln!(writer, "1.5, 0.0 0.0")?;
-    }
+    writeln!(writer, "3")?;
+    writeln!(writer, "Properties=pos:R:1:velo:R:1:radius:R:1",)?;
+    writeln!(writer, "{position:.8} {velocity:.8} 0.1")?;
+    writeln!(writer, "-1.5, 0.0 0.0")?;
+    writeln!(writer, "1.5, 0.0 0.0")?;
 
     Ok(())
 }
 
-pub struct Data {
+pub fn output_data(
+    file: &File,
     time: f64,
     numeric_position: f64,
     analitic_position: f64,
+) -> Result<()> {
+    let mut writer = BufWriter::new(file);
+
+    writeln!(writer, "{time} {numeric_position} {analitic_position}")?;
+
+    Ok(())
 }
 
-impl Data {
-    pub fn new(time: f64, numeric_position: f64, analitic_position: f64) -> Self {
-        Data {
-            time,
-            numeric_position,
-            analitic_position,
-        }
+pub fn output_step_sizes(file: &File, step_sizes: &[f64]) -> Result<()> {
+    let mut writer = BufWriter::new(file);
+
+    for step_size in step_sizes {
+        writeln!(writer, "{step_size}")?;
     }
+
+    Ok(())
 }
 
-pub fn output_data(path: &str, data: &[Data]) -> Result<()> {
+/// Writes the time, kinetic, potential and total energy, and momentum of the oscillator
+/// on a line.
+pub fn output_diagnostics(file: &File, time: f64, diagnostics: &Diagnostics<DIM>) -> Result<()> {
+    let mut writer = BufWriter::new(file);
+
+    writeln!(
+        writer,
+        "{time} {} {} {} {}",
+        diagnostics.kinetic_energy,
+        diagnostics.potential_energy.unwrap_or(f64::NAN),
+        diagnostics.total_energy().unwrap_or(f64::NAN),
+        diagnostics.linear_momentum[0]
+    )?;
+
+    Ok(())
+}
+
+/// Writes the convergence table as JSON if `path` ends in `.json`, and as CSV otherwise.
+pub fn output_convergence(
+    path: &str,
+    delta_ts: &[f64],
+    convergence: &[MethodConvergence],
+) -> Result<()> {
     let file = File::create(path)?;
     let mut writer = BufWriter::new(file);
 
-    for value in data {
-        writeln!(
-            writer,
-            "{} {} {}",
-            value.time, value.numeric_position, value.analitic_position
-        )?;
+    if path.ends_with(".json") {
+        write_convergence_json(&mut writer, delta_ts, convergence)
+    } else {
+        write_convergence_csv(&mut writer, delta_ts, convergence)
+    }
+}
+
+fn method_name(method: &MethodConvergence) -> String {
+    method
+        .integration
+        .to_possible_value()
+        .map_or_else(String::new, |value| value.get_name().to_owned())
+}
+
+fn write_convergence_csv(
+    writer: &mut impl Write,
+    delta_ts: &[f64],
+    convergence: &[MethodConvergence],
+) -> Result<()> {
+    write!(writer, "method,order")?;
+    for delta_t in delta_ts {
+        write!(writer, ",{delta_t}")?;
+    }
+    writeln!(writer)?;
+
+    for method in convergence {
+        write!(writer, "{},", method_name(method))?;
+        if let Some(order) = method.order {
+            write!(writer, "{order}")?;
+        }
+        for error in &method.mean_squared_errors {
+            write!(writer, ",{error}")?;
+        }
+        writeln!(writer)?;
     }
 
     Ok(())
 }
+
+/// Convergence of a method as written to JSON, where diverged errors are `null`.
+#[derive(Serialize)]
+struct ConvergenceRecord {
+    method: String,
+    order: Option<f64>,
+    errors: Vec<ErrorRecord>,
+}
+
+#[derive(Serialize)]
+struct ErrorRecord {
+    delta_t: f64,
+    mean_squared_error: f64,
+}
+
+fn write_convergence_json(
+    writer: &mut impl Write,
+    delta_ts: &[f64],
+    convergence: &[MethodConvergence],
+) -> Result<()> {
+    let records: Vec<_> = convergence
+        .iter()
+        .map(|method| ConvergenceRecord {
+            method: method_name(method),
+            order: method.order,
+            errors: delta_ts
+                .iter()
+                .zip(&method.mean_squared_errors)
+                .map(|(&delta_t, &mean_squared_error)| ErrorRecord {
+                    delta_t,
+                    mean_squared_error,
+                })
+                .collect(),
+        })
+        .collect();
+
+    serde_json::to_writer_pretty(&mut *writer, &records)?;
+    writeln!(writer)?;
+
+    Ok(())
+}
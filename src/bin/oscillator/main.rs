@@ -1,40 +1,130 @@
+use std::ops::ControlFlow;
+
 use anyhow::Result;
 use clap::Parser;
+use integration_dynamics::{checkpoint::Checkpoint, diagnostics::Diagnostics};
 
-use args::Cli;
-use constants::analytic_solution;
-use io::{output_data, output_simulation, Data};
+use args::{Cli, Command, ConvergenceArgs};
+use constants::OscillatorConstants;
+use io::{
+    open_output, output_convergence, output_data, output_diagnostics, output_simulation,
+    output_step_sizes,
+};
 use simulation::Oscillator;
 
 mod args;
 mod constants;
+mod convergence;
 mod io;
 mod simulation;
 
 fn main() -> Result<()> {
     let args = Cli::parse();
 
-    let mut simulation = Oscillator::new(args.simulation_delta_t, &args.integration_method);
+    match &args.command {
+        Some(Command::Convergence(convergence_args)) => run_convergence(convergence_args),
+        None => run_simulation(&args),
+    }
+}
+
+fn run_simulation(args: &Cli) -> Result<()> {
+    let integration_method = args
+        .integration_method
+        .as_ref()
+        .expect("the integration method is required without a subcommand");
+    let model = &args.model;
+    let checkpoint = args.resume.as_deref().map(Checkpoint::load).transpose()?;
+    let append = checkpoint.is_some();
+
+    let xyz_file = open_output(&args.xyz_output_path, append)?;
+    let data_file = open_output(&args.data_output_path, append)?;
+
+    let mut step_sizes_file = None;
+    if let Some(path) = &args.step_sizes_output_path {
+        step_sizes_file = Some(open_output(path, append)?);
+    }
+
+    let mut diagnostics_file = None;
+    if let Some(path) = &args.diagnostics_output_path {
+        diagnostics_file = Some(open_output(path, append)?);
+    }
+
+    let constants =
+        OscillatorConstants::new(model.restoring_force_constant, model.amortiguation_constant);
 
-    let output_iters = (args.max_time / args.output_delta_t) as usize;
-    let simulation_iters = (args.output_delta_t / args.simulation_delta_t) as usize;
+    let output_iters = (model.max_time / model.output_delta_t) as usize;
 
-    let mut steps = Vec::new();
-    let mut data = Vec::new();
+    let force_model = constants.force_model();
+    let mut oscillator = Oscillator::new(
+        args.simulation_delta_t,
+        model.output_delta_t,
+        integration_method,
+        model.absolute_tolerance,
+        model.relative_tolerance,
+        args.max_step,
+        model.gear_order,
+        model.bootstrap_gear_derivatives,
+        &constants,
+        checkpoint.as_ref(),
+    )?;
 
-    for i in 1..=output_iters {
-        let r = simulation.run(simulation_iters);
-        let numeric_position = r[0][0];
+    let step_sizes = oscillator.step_sizes();
+    let simulation = oscillator.simulation_mut();
+    if let Some(file) = &diagnostics_file {
+        // NOTE: The initial state was already written by the run which saved the checkpoint
+        if checkpoint.is_none() {
+            output_diagnostics(
+                file,
+                0.0,
+                &Diagnostics::new(simulation.system(), &force_model),
+            )?;
+        }
 
-        let time = i as f64 * args.output_delta_t;
-        let analitic_position = analytic_solution(time);
+        simulation.on_output(|time, system| {
+            stop_on_error(output_diagnostics(
+                file,
+                time,
+                &Diagnostics::new(system, &force_model),
+            ))
+        });
+    }
+    simulation.on_output(|time, system| {
+        let particle = system.state(0);
+        let analitic_position = constants.analytic_solution(time);
 
-        steps.push((r[0][0], r[1][0]));
-        data.push(Data::new(time, numeric_position, analitic_position));
+        stop_on_error(
+            output_simulation(&xyz_file, particle.position[0], particle.velocity[0]).and_then(
+                |()| output_data(&data_file, time, particle.position[0], analitic_position),
+            ),
+        )
+    });
+    if let Some(file) = &step_sizes_file {
+        simulation
+            .on_output(move |_, _| stop_on_error(output_step_sizes(file, &step_sizes.take())));
     }
 
-    output_simulation(&args.xyz_output_path, &steps)?;
-    output_data(&args.data_output_path, &data)?;
+    for _ in simulation.outputs()..output_iters {
+        if let ControlFlow::Break(error) = simulation.run_outputs(1) {
+            return Err(error);
+        }
+
+        if let Some(path) = &args.checkpoint_path {
+            simulation.checkpoint(*integration_method).save(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Stops the simulation with the error of an output, if it failed.
+fn stop_on_error(result: Result<()>) -> ControlFlow<anyhow::Error> {
+    result.map_or_else(ControlFlow::Break, ControlFlow::Continue)
+}
+
+fn run_convergence(args: &ConvergenceArgs) -> Result<()> {
+    let convergence = convergence::sweep(args)?;
+
+    output_convergence(&args.table_output_path, &args.delta_ts, &convergence)?;
 
     Ok(())
 }
@@ -1,84 +1,126 @@
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::bail;
 use integration_dynamics::{
-    methods::{
-        Beeman, Euler, EulerMod, EulerPredictorCorrector, GearPredictorCorrector,
-        IntegrationMethod, VelocityVerlet, Verlet, VerletLeapFrog,
-    },
+    checkpoint::Checkpoint,
+    methods::{GearPredictorCorrector, IntegrationMethod},
     particle::Particle,
-    Integration,
+    simulation::{steps_per_output, Simulation},
+    system::ParticleSystem,
+    Integration, MethodSettings,
 };
 
-use crate::constants::{
-    acceleration_function, DIM, INITIAL_ACCELERATION, INITIAL_FIFTH_DERIVATIVE,
-    INITIAL_FOURTH_DERIVATIVE, INITIAL_POSITION, INITIAL_THIRD_DERIVATIVE, INITIAL_VELOCITY,
-    PARTICLE_MASS,
+use crate::{
+    constants::{OscillatorConstants, DIM, INITIAL_POSITION, PARTICLE_MASS},
+    Result,
 };
 
-pub struct Oscillator {
-    particle: [Particle<DIM>; 1],
-    integration_method: Box<dyn IntegrationMethod<DIM>>,
+/// Simulation of the oscillator, which only stops early with the first error of an output.
+pub type OscillatorSimulation<'a> = Simulation<'a, DIM, anyhow::Error>;
+
+pub struct Oscillator<'a> {
+    simulation: OscillatorSimulation<'a>,
+    step_sizes: Rc<RefCell<Vec<f64>>>,
 }
 
-impl Oscillator {
-    pub fn new(delta_t: f64, integration_method: &Integration) -> Self {
+impl<'a> Oscillator<'a> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        delta_t: f64,
+        output_delta_t: f64,
+        integration: &Integration,
+        absolute_tolerance: f64,
+        relative_tolerance: f64,
+        max_step: Option<f64>,
+        gear_order: usize,
+        bootstrap_gear_derivatives: bool,
+        constants: &OscillatorConstants,
+        checkpoint: Option<&Checkpoint>,
+    ) -> Result<Self> {
+        // NOTE: Adaptive methods choose their own steps, so the simulation only stops them at
+        // every output
+        let delta_t = if integration.is_adaptive() {
+            output_delta_t
+        } else {
+            delta_t
+        };
+
+        let force_model = constants.force_model();
+        let higher_derivatives = constants.initial_higher_derivatives();
+        let initial_acceleration = higher_derivatives[0];
+
         let particle: Particle<DIM> = Particle::new(
             0,
             INITIAL_POSITION,
-            INITIAL_VELOCITY,
-            INITIAL_ACCELERATION,
+            constants.initial_velocity(),
+            initial_acceleration,
             0.0,
             PARTICLE_MASS,
         );
 
-        let mut particle = [particle];
-        let integration_method: Box<dyn IntegrationMethod<DIM>> = match integration_method {
-            Integration::Euler => Box::new(Euler::new(acceleration_function, delta_t)),
-            Integration::EulerMod => Box::new(EulerMod::new(acceleration_function, delta_t)),
-            Integration::Verlet => {
-                Box::new(Verlet::new(acceleration_function, &mut particle, delta_t))
-            }
-            Integration::Beeman => {
-                Box::new(Beeman::new(acceleration_function, &mut particle, delta_t))
-            }
-            Integration::GearPredictorCorrector => {
-                let particles_to_init = vec![(
-                    &mut particle[0],
-                    vec![
-                        INITIAL_THIRD_DERIVATIVE,
-                        INITIAL_FOURTH_DERIVATIVE,
-                        INITIAL_FIFTH_DERIVATIVE,
-                    ],
-                )];
+        let mut system = ParticleSystem::new(&[particle]);
+        let step_sizes = Rc::new(RefCell::new(Vec::new()));
+        let record_step_size = {
+            let step_sizes = Rc::clone(&step_sizes);
+            move |step| step_sizes.borrow_mut().push(step)
+        };
+
+        // NOTE: Unless bootstrapped, the Gear predictor corrector starts from the analytic
+        // derivatives above the acceleration
+        let integration_method: Box<dyn IntegrationMethod<DIM>> = match integration {
+            Integration::GearPredictorCorrector if !bootstrap_gear_derivatives => {
+                let derivatives_above_acceleration = higher_derivatives
+                    .get(1..gear_order.saturating_sub(1))
+                    .unwrap_or_default()
+                    .to_vec();
                 Box::new(GearPredictorCorrector::new(
-                    acceleration_function,
-                    true,
-                    particles_to_init,
+                    force_model,
+                    constants.is_damped(),
+                    gear_order,
+                    &mut system,
+                    vec![derivatives_above_acceleration],
                     delta_t,
-                ))
+                )?)
             }
-            Integration::VerletLeapFrog => Box::new(VerletLeapFrog::new(
-                acceleration_function,
-                &mut particle,
+            _ => integration.method(
+                force_model,
+                &mut system,
                 delta_t,
-            )),
-            Integration::VelocityVerlet => {
-                Box::new(VelocityVerlet::new(acceleration_function, delta_t))
-            }
-            Integration::EulerPredictorCorrector => {
-                Box::new(EulerPredictorCorrector::new(acceleration_function, delta_t))
-            }
+                MethodSettings {
+                    absolute_tolerance,
+                    relative_tolerance,
+                    max_step,
+                    gear_order,
+                    force_depends_on_velocity: constants.is_damped(),
+                    step_observer: Some(Box::new(record_step_size)),
+                },
+            )?,
+        };
+
+        let Some(steps_per_output) = steps_per_output(output_delta_t, delta_t) else {
+            bail!("the simulation delta t {delta_t} is larger than the output delta t {output_delta_t}");
         };
 
-        Self {
-            particle,
-            integration_method,
+        let mut simulation = Simulation::new(system, integration_method, delta_t)
+            .with_steps_per_output(steps_per_output);
+        simulation.on_step_error(anyhow::Error::from);
+        if let Some(checkpoint) = checkpoint {
+            simulation.restore(checkpoint, *integration)?;
         }
+
+        Ok(Self {
+            simulation,
+            step_sizes,
+        })
     }
 
-    pub fn run(&mut self, steps: usize) -> &[[f64; DIM]] {
-        for _ in 0..steps {
-            self.integration_method.advance_step(&mut self.particle);
-        }
+    /// Sizes of the internal steps adaptive methods took since they were last taken out of
+    /// the list.
+    pub fn step_sizes(&self) -> Rc<RefCell<Vec<f64>>> {
+        Rc::clone(&self.step_sizes)
+    }
 
-        self.particle[0].derivatives()
+    pub fn simulation_mut(&mut self) -> &mut OscillatorSimulation<'a> {
+        &mut self.simulation
     }
 }
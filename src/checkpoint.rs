@@ -0,0 +1,252 @@
+//! Snapshots of a [`Simulation`](crate::simulation::Simulation) taken mid-run, from which it
+//! resumes exactly as if it had never stopped.
+//!
+//! A checkpoint holds every derivative of every particle, the previous derivatives and the
+//! higher derivatives of the Gear predictor corrector included, and their angular
+//! velocities, together with the state of the integration method and the number of steps
+//! taken. It is saved as JSON, with floats written so that they are read back to the same
+//! bits.
+
+use std::{
+    fmt::Display,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{particle::Particle, system::ParticleSystem, Integration};
+
+/// State of a simulation after a number of steps.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub integration: Integration,
+    pub delta_t: f64,
+    /// Steps taken since the start of the simulation
+    pub steps: usize,
+    pub steps_per_output: usize,
+    /// State the integration method carries between steps
+    pub method_state: Vec<f64>,
+    pub particles: Vec<ParticleSnapshot>,
+    /// Seed of the random numbers the simulation was set up with, kept for its caller to
+    /// resume with
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// A particle with every derivative it holds, each stored as a list of its components.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ParticleSnapshot {
+    pub id: usize,
+    pub radius: f64,
+    pub mass: f64,
+    pub derivatives: Vec<Vec<f64>>,
+    pub prev_derivatives: Vec<Vec<f64>>,
+    /// Zero in checkpoints of particles which do not spin
+    #[serde(default)]
+    pub angular_velocity: [f64; 3],
+}
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    /// The simulation resuming uses a different integration method or time step
+    Integration {
+        expected: (Integration, f64),
+        found: (Integration, f64),
+    },
+    /// The simulation resuming outputs after a different number of steps
+    StepsPerOutput {
+        expected: usize,
+        found: usize,
+    },
+    /// The state of the integration method does not have the length the method expects
+    MethodState {
+        expected: usize,
+        found: usize,
+    },
+    Dimension {
+        particle_id: usize,
+        expected: usize,
+        found: usize,
+    },
+    DerivativeCount {
+        particle_id: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckpointError::Io(error) => write!(f, "checkpoint could not be accessed: {error}"),
+            CheckpointError::Format(error) => write!(f, "checkpoint is malformed: {error}"),
+            CheckpointError::Integration {
+                expected: (expected, expected_delta_t),
+                found: (found, found_delta_t),
+            } => write!(
+                f,
+                "checkpoint was taken integrating with {found:?} at a delta t of {found_delta_t}, \
+                 but the simulation uses {expected:?} at a delta t of {expected_delta_t}"
+            ),
+            CheckpointError::StepsPerOutput { expected, found } => write!(
+                f,
+                "checkpoint was taken outputting every {found} steps, but the simulation outputs every {expected}"
+            ),
+            CheckpointError::MethodState { expected, found } => write!(
+                f,
+                "checkpoint holds {found} values of integration method state but the method has {expected}"
+            ),
+            CheckpointError::Dimension {
+                particle_id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "particle {particle_id} of the checkpoint has {found} dimensions but the simulation has {expected}"
+            ),
+            CheckpointError::DerivativeCount {
+                particle_id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "particle {particle_id} of the checkpoint has {found} derivatives but the simulation needs {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<std::io::Error> for CheckpointError {
+    fn from(error: std::io::Error) -> Self {
+        CheckpointError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for CheckpointError {
+    fn from(error: serde_json::Error) -> Self {
+        CheckpointError::Format(error)
+    }
+}
+
+impl Checkpoint {
+    #[must_use]
+    pub fn time(&self) -> f64 {
+        self.steps as f64 * self.delta_t
+    }
+
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CheckpointError> {
+        let reader = BufReader::new(File::open(path)?);
+
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Saves the checkpoint to `path`, writing it to a temporary file next to it first so
+    /// that a run interrupted while saving leaves the previous checkpoint intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(&temporary, path)?;
+
+        Ok(())
+    }
+
+    /// Copies the particles of the checkpoint into a system, checking that each of them
+    /// has `DIM` dimensions and `derivative_count` derivatives.
+    pub(crate) fn system<const DIM: usize>(
+        &self,
+        derivative_count: usize,
+    ) -> Result<ParticleSystem<DIM>, CheckpointError> {
+        let particles = self
+            .particles
+            .iter()
+            .map(|snapshot| snapshot.particle(derivative_count))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ParticleSystem::new(&particles))
+    }
+}
+
+impl ParticleSnapshot {
+    #[must_use]
+    pub fn new<const DIM: usize>(particle: &Particle<DIM>) -> Self {
+        let components = |derivatives: &Vec<[f64; DIM]>| {
+            derivatives
+                .iter()
+                .map(|derivative| derivative.to_vec())
+                .collect()
+        };
+
+        Self {
+            id: particle.id(),
+            radius: particle.radius(),
+            mass: particle.mass(),
+            derivatives: components(particle.derivatives()),
+            prev_derivatives: components(particle.prev_derivatives()),
+            angular_velocity: particle.angular_velocity(),
+        }
+    }
+
+    fn particle<const DIM: usize>(
+        &self,
+        derivative_count: usize,
+    ) -> Result<Particle<DIM>, CheckpointError> {
+        for derivatives in [&self.derivatives, &self.prev_derivatives] {
+            if derivatives.len() != derivative_count {
+                return Err(CheckpointError::DerivativeCount {
+                    particle_id: self.id,
+                    expected: derivative_count,
+                    found: derivatives.len(),
+                });
+            }
+        }
+
+        let vectors = |derivatives: &Vec<Vec<f64>>| {
+            derivatives
+                .iter()
+                .map(|derivative| {
+                    <[f64; DIM]>::try_from(derivative.as_slice()).map_err(|_| {
+                        CheckpointError::Dimension {
+                            particle_id: self.id,
+                            expected: DIM,
+                            found: derivative.len(),
+                        }
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let derivatives = vectors(&self.derivatives)?;
+
+        let mut particle = Particle::new(
+            self.id,
+            derivatives[0],
+            derivatives[1],
+            derivatives[2],
+            self.radius,
+            self.mass,
+        )
+        .with_angular_velocity(self.angular_velocity);
+        particle.set_derivatives(derivatives);
+        particle.set_prev_derivatives(vectors(&self.prev_derivatives)?);
+
+        Ok(particle)
+    }
+}
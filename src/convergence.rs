@@ -0,0 +1,31 @@
+//! Order of convergence of an integrator, fitted to its errors at several time steps.
+
+/// Least squares slope of the logarithm of the error against the logarithm of the time
+/// step. The mean squared error decreases with twice the order of the method, so the
+/// slope is halved. Errors which are not finite or fall below `error_floor` are skipped,
+/// and `None` is returned when fewer than two are left.
+#[must_use]
+pub fn fit_order(delta_ts: &[f64], mean_squared_errors: &[f64], error_floor: f64) -> Option<f64> {
+    let points: Vec<_> = delta_ts
+        .iter()
+        .zip(mean_squared_errors)
+        .filter(|(_, error)| error.is_finite() && **error > error_floor)
+        .map(|(delta_t, error)| (delta_t.ln(), error.ln()))
+        .collect();
+
+    if points.len() < 2 {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+
+    Some(covariance / variance / 2.0)
+}
@@ -0,0 +1,105 @@
+//! Quantities conserved by the equations of motion, to tell how far an integrator drifts
+//! from them.
+//!
+//! The angular momentum is taken about the origin, as the antisymmetric matrix
+//! `L[i][j] = sum m (r[i] v[j] - r[j] v[i])`, which holds the single component of a
+//! two dimensional system in `L[0][1]` and the usual vector of a three dimensional one
+//! in its upper triangle.
+//!
+//! Particles which spin, as under a [`Spin`](crate::spin::Spin) observer, add the energy
+//! and angular momentum of their rotation about their centre, taking them for solid
+//! spheres, so that what friction moves between translation and rotation is not lost
+//! from the totals. The angular momentum only holds the spin about the axes its matrix
+//! has a component for, which in two dimensions is the one perpendicular to the plane.
+
+use crate::{forces::ForceModel, particle::Particle, system::ParticleSystem};
+
+/// Energy and momenta of a system at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Diagnostics<const DIM: usize> {
+    /// Of the translation and the rotation of the particles
+    pub kinetic_energy: f64,
+    /// `None` when the force model has no known potential
+    pub potential_energy: Option<f64>,
+    pub linear_momentum: [f64; DIM],
+    pub angular_momentum: [[f64; DIM]; DIM],
+}
+
+impl<const DIM: usize> Diagnostics<DIM> {
+    #[must_use]
+    pub fn new<F: ForceModel<DIM>>(system: &ParticleSystem<DIM>, force_model: &F) -> Self {
+        Self {
+            kinetic_energy: kinetic_energy(system),
+            potential_energy: force_model.potential_energy(system),
+            linear_momentum: linear_momentum(system),
+            angular_momentum: angular_momentum(system),
+        }
+    }
+
+    #[must_use]
+    pub fn of_particles<F: ForceModel<DIM>>(particles: &[Particle<DIM>], force_model: &F) -> Self {
+        Self::new(&ParticleSystem::new(particles), force_model)
+    }
+
+    /// Kinetic plus potential energy, or `None` when the potential energy is not known.
+    #[must_use]
+    pub fn total_energy(&self) -> Option<f64> {
+        self.potential_energy
+            .map(|potential_energy| self.kinetic_energy + potential_energy)
+    }
+}
+
+/// Kinetic energy of the translation and the rotation of the particles.
+#[must_use]
+pub fn kinetic_energy<const DIM: usize>(system: &ParticleSystem<DIM>) -> f64 {
+    let squared = |vector: &[f64]| vector.iter().map(|x| x.powi(2)).sum::<f64>();
+
+    (0..system.len())
+        .map(|n| {
+            0.5 * system.mass(n) * squared(&system.velocities()[n])
+                + 0.5 * system.moment_of_inertia(n) * squared(&system.angular_velocities()[n])
+        })
+        .sum()
+}
+
+#[must_use]
+pub fn linear_momentum<const DIM: usize>(system: &ParticleSystem<DIM>) -> [f64; DIM] {
+    let mut momentum = [0.0; DIM];
+
+    for particle in system.states() {
+        let v = particle.velocity;
+
+        for i in 0..DIM {
+            momentum[i] += particle.mass * v[i];
+        }
+    }
+
+    momentum
+}
+
+/// Angular momentum about the origin, as the antisymmetric matrix described in the
+/// [module documentation](self).
+#[must_use]
+pub fn angular_momentum<const DIM: usize>(system: &ParticleSystem<DIM>) -> [[f64; DIM]; DIM] {
+    let mut momentum = [[0.0; DIM]; DIM];
+
+    for (n, particle) in system.states().enumerate() {
+        let (r, v) = (particle.position, particle.velocity);
+
+        for i in 0..DIM {
+            for j in 0..DIM {
+                momentum[i][j] += particle.mass * (r[i] * v[j] - r[j] * v[i]);
+            }
+        }
+
+        let spin = system.angular_velocities()[n].map(|w| system.moment_of_inertia(n) * w);
+        for (i, j, k) in [(0, 1, 2), (1, 2, 0), (2, 0, 1)] {
+            if i < DIM && j < DIM {
+                momentum[i][j] += spin[k];
+                momentum[j][i] -= spin[k];
+            }
+        }
+    }
+
+    momentum
+}
@@ -0,0 +1,206 @@
+//! Events located inside a step, at the exact time a scalar function of the state of a
+//! particle falls to zero, rather than at the end of the step where it is first noticed.
+//!
+//! The state of a particle inside a step is interpolated with the cubic Hermite
+//! polynomial through its positions and velocities at both ends of the step, which is
+//! third order accurate whatever the integration method, and the event time is refined on
+//! it with Brent's method.
+
+use std::{collections::HashMap, ops::ControlFlow};
+
+use crate::{particle::ParticleState, simulation::Observer, system::ParticleSystem};
+
+/// Width of the bracket on the fraction of the step where Brent's method stops.
+const STEP_FRACTION_TOLERANCE: f64 = 1e-12;
+const MAX_BRENT_ITERATIONS: usize = 100;
+
+/// Event of a particle, at the time its event function fell to zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Event<const DIM: usize> {
+    pub time: f64,
+    /// State of the particle at the event time
+    pub particle: ParticleState<DIM>,
+}
+
+/// Observer detecting the events of every particle in each step, and handing them in time
+/// order to a handler which may change the system, for instance removing the particle.
+///
+/// An event happens when the event function of a particle goes from positive at the start
+/// of a step to zero or below at its end, so each particle has at most one event per step.
+/// Particles are told apart by their id, so the system may lose particles between steps.
+pub struct EventDetector<const DIM: usize, G, H> {
+    event_function: G,
+    handler: H,
+    /// Time and particles at the start of the next step
+    start_time: f64,
+    start_states: HashMap<usize, ParticleState<DIM>>,
+}
+
+impl<const DIM: usize, G, H> EventDetector<DIM, G, H>
+where
+    G: Fn(&ParticleState<DIM>) -> f64,
+{
+    /// Detects events from the state of `system` at `time` on.
+    #[must_use]
+    pub fn new(system: &ParticleSystem<DIM>, time: f64, event_function: G, handler: H) -> Self {
+        Self {
+            event_function,
+            handler,
+            start_time: time,
+            start_states: system.states().map(|state| (state.id, state)).collect(),
+        }
+    }
+
+    /// Event of a particle which went from `start` to `end` in the step, if it has one.
+    fn locate(
+        &self,
+        start: &ParticleState<DIM>,
+        end: &ParticleState<DIM>,
+        delta_t: f64,
+    ) -> Option<Event<DIM>> {
+        if (self.event_function)(start) <= 0.0 || (self.event_function)(end) > 0.0 {
+            return None;
+        }
+
+        let fraction = brent(
+            |fraction| (self.event_function)(&interpolate(start, end, fraction, delta_t)),
+            0.0,
+            1.0,
+        );
+
+        Some(Event {
+            time: self.start_time + fraction * delta_t,
+            particle: interpolate(start, end, fraction, delta_t),
+        })
+    }
+}
+
+impl<const DIM: usize, B, G, H> Observer<DIM, B> for EventDetector<DIM, G, H>
+where
+    G: Fn(&ParticleState<DIM>) -> f64,
+    H: FnMut(&Event<DIM>, &mut ParticleSystem<DIM>) -> ControlFlow<B>,
+{
+    fn after_step(&mut self, time: f64, system: &mut ParticleSystem<DIM>) -> ControlFlow<B> {
+        let delta_t = time - self.start_time;
+
+        let mut events: Vec<_> = system
+            .states()
+            .filter_map(|end| {
+                let start = self.start_states.get(&end.id)?;
+                self.locate(start, &end, delta_t)
+            })
+            .collect();
+        events.sort_by(|event, other| event.time.total_cmp(&other.time));
+
+        let handled = events
+            .iter()
+            .try_for_each(|event| (self.handler)(event, system));
+
+        self.start_time = time;
+        self.start_states.clear();
+        self.start_states
+            .extend(system.states().map(|state| (state.id, state)));
+
+        handled
+    }
+}
+
+/// State at `fraction` of a step of `delta_t` from `start` to `end`, on the cubic Hermite
+/// polynomial matching the positions and velocities at both ends.
+fn interpolate<const DIM: usize>(
+    start: &ParticleState<DIM>,
+    end: &ParticleState<DIM>,
+    fraction: f64,
+    delta_t: f64,
+) -> ParticleState<DIM> {
+    let s = fraction;
+    let (h00, h10, h01, h11) = (
+        2.0 * s.powi(3) - 3.0 * s.powi(2) + 1.0,
+        s.powi(3) - 2.0 * s.powi(2) + s,
+        -2.0 * s.powi(3) + 3.0 * s.powi(2),
+        s.powi(3) - s.powi(2),
+    );
+    // NOTE: Derivatives of the basis with respect to the fraction of the step
+    let (dh00, dh10, dh01, dh11) = (
+        6.0 * s.powi(2) - 6.0 * s,
+        3.0 * s.powi(2) - 4.0 * s + 1.0,
+        -6.0 * s.powi(2) + 6.0 * s,
+        3.0 * s.powi(2) - 2.0 * s,
+    );
+
+    let mut state = *start;
+    for i in 0..DIM {
+        let (r0, v0) = (start.position[i], start.velocity[i]);
+        let (r1, v1) = (end.position[i], end.velocity[i]);
+
+        state.position[i] = h00 * r0 + h10 * delta_t * v0 + h01 * r1 + h11 * delta_t * v1;
+        state.velocity[i] = (dh00 * r0 + dh01 * r1) / delta_t + dh10 * v0 + dh11 * v1;
+    }
+
+    state
+}
+
+/// Root of `f` between `a`, where it is positive, and `b`, where it is not, with Brent's
+/// method, which falls back to bisection whenever interpolating converges too slowly.
+fn brent(f: impl Fn(f64) -> f64, mut a: f64, mut b: f64) -> f64 {
+    let (mut fa, mut fb) = (f(a), f(b));
+    let (mut c, mut fc) = (b, fb);
+    let (mut d, mut e) = (b - a, b - a);
+
+    for _ in 0..MAX_BRENT_ITERATIONS {
+        if (fb > 0.0 && fc > 0.0) || (fb < 0.0 && fc < 0.0) {
+            (c, fc) = (a, fa);
+            (d, e) = (b - a, b - a);
+        }
+        // NOTE: Keep b as the best estimate and c on the other side of the root
+        if fc.abs() < fb.abs() {
+            (a, fa) = (b, fb);
+            (b, fb) = (c, fc);
+            (c, fc) = (a, fa);
+        }
+
+        let tolerance = 2.0 * f64::EPSILON * b.abs() + 0.5 * STEP_FRACTION_TOLERANCE;
+        let half_bracket = 0.5 * (c - b);
+        if half_bracket.abs() <= tolerance || fb == 0.0 {
+            return b;
+        }
+
+        if e.abs() >= tolerance && fa.abs() > fb.abs() {
+            // NOTE: Secant step with two points, inverse quadratic interpolation with three
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c {
+                (2.0 * half_bracket * s, 1.0 - s)
+            } else {
+                let (q, r) = (fa / fc, fb / fc);
+                (
+                    s * (2.0 * half_bracket * q * (q - r) - (b - a) * (r - 1.0)),
+                    (q - 1.0) * (r - 1.0) * (s - 1.0),
+                )
+            };
+            if p > 0.0 {
+                q = -q;
+            }
+            p = p.abs();
+
+            let largest_step = (3.0 * half_bracket * q - (tolerance * q).abs()).min((e * q).abs());
+            if 2.0 * p < largest_step {
+                e = d;
+                d = p / q;
+            } else {
+                (d, e) = (half_bracket, half_bracket);
+            }
+        } else {
+            (d, e) = (half_bracket, half_bracket);
+        }
+
+        (a, fa) = (b, fb);
+        b += if d.abs() > tolerance {
+            d
+        } else {
+            tolerance.copysign(half_bracket)
+        };
+        fb = f(b);
+    }
+
+    b
+}
@@ -0,0 +1,905 @@
+use crate::{neighbours::CellList, parallel, particle::ParticleState, system::ParticleSystem};
+
+/// Computes the force acting on a particle given the rest of the system.
+///
+/// Force models can be summed with [`ForceModel::plus`], so scenarios are assembled
+/// from the reusable terms in this module. Any closure with the signature
+/// `Fn(&ParticleState<DIM>, &ParticleSystem<DIM>) -> [f64; DIM]` returning a force is
+/// also a force model.
+///
+/// The particle a force is computed for may be at a different state than its copy in the
+/// system, which it is told apart from by its id. Integrators evaluate the whole system
+/// at once through [`ForceModel::add_forces`], which models can override to share work
+/// between particles, as [`Pairwise`] does.
+///
+/// Force models are `Sync`, so that the `parallel` feature can share them between the
+/// threads evaluating the particles.
+pub trait ForceModel<const DIM: usize>: Sync {
+    fn force(&self, particle: &ParticleState<DIM>, system: &ParticleSystem<DIM>) -> [f64; DIM];
+
+    fn acceleration(
+        &self,
+        particle: &ParticleState<DIM>,
+        system: &ParticleSystem<DIM>,
+    ) -> [f64; DIM] {
+        self.force(particle, system).map(|f| f / particle.mass)
+    }
+
+    /// Adds the force acting on every particle of the system to `forces`.
+    fn add_forces(&self, system: &ParticleSystem<DIM>, forces: &mut [[f64; DIM]]) {
+        parallel::for_each_mut(forces, |n, force| {
+            let particle_force = self.force(&system.state(n), system);
+
+            for i in 0..DIM {
+                force[i] += particle_force[i];
+            }
+        });
+    }
+
+    /// Forces acting on every particle of the system.
+    fn forces(&self, system: &ParticleSystem<DIM>) -> Vec<[f64; DIM]> {
+        let mut forces = vec![[0.0; DIM]; system.len()];
+        self.add_forces(system, &mut forces);
+
+        forces
+    }
+
+    /// Derivatives of the force with respect to the position and velocity of `particle`,
+    /// or `None` when they are not known analytically.
+    fn jacobian(
+        &self,
+        _particle: &ParticleState<DIM>,
+        _system: &ParticleSystem<DIM>,
+    ) -> Option<ForceJacobian<DIM>> {
+        None
+    }
+
+    /// Derivatives of the force with respect to the position and velocity of every other
+    /// particle it depends on, each with the index of that particle in the system, or
+    /// `None` when they are not known analytically. Forces which only depend on the
+    /// particle they act on have none.
+    fn coupling_jacobians(
+        &self,
+        _particle: &ParticleState<DIM>,
+        _system: &ParticleSystem<DIM>,
+    ) -> Option<Vec<(usize, ForceJacobian<DIM>)>> {
+        None
+    }
+
+    /// Potential energy of the whole system, or `None` when the forces do not derive
+    /// from a known potential. Dissipative forces store no energy, so they add nothing.
+    fn potential_energy(&self, _system: &ParticleSystem<DIM>) -> Option<f64> {
+        None
+    }
+
+    fn plus<O: ForceModel<DIM>>(self, other: O) -> Sum<Self, O>
+    where
+        Self: Sized,
+    {
+        Sum(self, other)
+    }
+}
+
+/// Derivatives of a force, where `position[i][j]` is the derivative of its `i` component
+/// with respect to the `j` component of the position, and likewise for `velocity`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForceJacobian<const DIM: usize> {
+    pub position: [[f64; DIM]; DIM],
+    pub velocity: [[f64; DIM]; DIM],
+}
+
+impl<const DIM: usize> ForceJacobian<DIM> {
+    #[must_use]
+    pub fn zero() -> Self {
+        Self {
+            position: [[0.0; DIM]; DIM],
+            velocity: [[0.0; DIM]; DIM],
+        }
+    }
+
+    fn add(&mut self, other: &Self) {
+        for i in 0..DIM {
+            for j in 0..DIM {
+                self.position[i][j] += other.position[i][j];
+                self.velocity[i][j] += other.velocity[i][j];
+            }
+        }
+    }
+}
+
+impl<const DIM: usize, F> ForceModel<DIM> for F
+where
+    F: Fn(&ParticleState<DIM>, &ParticleSystem<DIM>) -> [f64; DIM] + Sync,
+{
+    fn force(&self, particle: &ParticleState<DIM>, system: &ParticleSystem<DIM>) -> [f64; DIM] {
+        self(particle, system)
+    }
+}
+
+/// Superposition of two force models, built with [`ForceModel::plus`].
+#[derive(Debug, Clone, Copy)]
+pub struct Sum<A, B>(A, B);
+
+impl<const DIM: usize, A: ForceModel<DIM>, B: ForceModel<DIM>> ForceModel<DIM> for Sum<A, B> {
+    fn force(&self, particle: &ParticleState<DIM>, system: &ParticleSystem<DIM>) -> [f64; DIM] {
+        let mut force = self.0.force(particle, system);
+        let other_force = self.1.force(particle, system);
+
+        for i in 0..DIM {
+            force[i] += other_force[i];
+        }
+
+        force
+    }
+
+    fn add_forces(&self, system: &ParticleSystem<DIM>, forces: &mut [[f64; DIM]]) {
+        self.0.add_forces(system, forces);
+        self.1.add_forces(system, forces);
+    }
+
+    fn jacobian(
+        &self,
+        particle: &ParticleState<DIM>,
+        system: &ParticleSystem<DIM>,
+    ) -> Option<ForceJacobian<DIM>> {
+        let mut jacobian = self.0.jacobian(particle, system)?;
+        jacobian.add(&self.1.jacobian(particle, system)?);
+
+        Some(jacobian)
+    }
+
+    fn coupling_jacobians(
+        &self,
+        particle: &ParticleState<DIM>,
+        system: &ParticleSystem<DIM>,
+    ) -> Option<Vec<(usize, ForceJacobian<DIM>)>> {
+        let mut couplings = self.0.coupling_jacobians(particle, system)?;
+        couplings.extend(self.1.coupling_jacobians(particle, system)?);
+
+        Some(couplings)
+    }
+
+    fn potential_energy(&self, system: &ParticleSystem<DIM>) -> Option<f64> {
+        Some(self.0.potential_energy(system)? + self.1.potential_energy(system)?)
+    }
+}
+
+/// Force between two particles which obeys Newton's third law, so the force `other`
+/// exerts on `particle` is the opposite of the one `particle` exerts on `other`.
+pub trait PairInteraction<const DIM: usize>: Sync {
+    /// Force exerted by `other` on `particle`.
+    fn pair_force(&self, particle: &ParticleState<DIM>, other: &ParticleState<DIM>) -> [f64; DIM];
+
+    /// Derivatives of the pair force with respect to the position and velocity of
+    /// `particle`, or `None` when they are not known analytically.
+    fn pair_jacobian(
+        &self,
+        _particle: &ParticleState<DIM>,
+        _other: &ParticleState<DIM>,
+    ) -> Option<ForceJacobian<DIM>> {
+        None
+    }
+
+    /// Potential energy of the pair, or `None` when the pair force does not derive from a
+    /// known potential.
+    fn pair_potential_energy(
+        &self,
+        _particle: &ParticleState<DIM>,
+        _other: &ParticleState<DIM>,
+    ) -> Option<f64> {
+        None
+    }
+}
+
+/// Force model summing a [`PairInteraction`] over every pair of particles.
+///
+/// The forces on the whole system are computed visiting each pair once, applying the
+/// pair force to one particle and its opposite to the other. With a cutoff, pairs
+/// farther apart than it do not interact, and only the pairs found in adjacent cells of a
+/// [`CellList`] are visited.
+#[derive(Debug, Clone, Copy)]
+pub struct Pairwise<P> {
+    interaction: P,
+    cutoff: Option<f64>,
+}
+
+impl<P> Pairwise<P> {
+    #[must_use]
+    pub fn new(interaction: P) -> Self {
+        Self {
+            interaction,
+            cutoff: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_cutoff(interaction: P, cutoff: f64) -> Self {
+        Self {
+            interaction,
+            cutoff: Some(cutoff),
+        }
+    }
+
+    /// Whether the pair is close enough to interact, as every pair is without a cutoff.
+    fn interacts<const DIM: usize>(
+        &self,
+        particle: &ParticleState<DIM>,
+        other: &ParticleState<DIM>,
+    ) -> bool {
+        let Some(cutoff) = self.cutoff else {
+            return true;
+        };
+
+        let squared_distance: f64 = (0..DIM)
+            .map(|i| (other.position[i] - particle.position[i]).powi(2))
+            .sum();
+        squared_distance <= cutoff.powi(2)
+    }
+}
+
+impl<const DIM: usize, P: PairInteraction<DIM>> ForceModel<DIM> for Pairwise<P> {
+    fn force(&self, particle: &ParticleState<DIM>, system: &ParticleSystem<DIM>) -> [f64; DIM] {
+        let mut force = [0.0; DIM];
+
+        for other in system.states() {
+            if particle.id == other.id || !self.interacts(particle, &other) {
+                continue;
+            }
+
+            let pair_force = self.interaction.pair_force(particle, &other);
+            for i in 0..DIM {
+                force[i] += pair_force[i];
+            }
+        }
+
+        force
+    }
+
+    fn add_forces(&self, system: &ParticleSystem<DIM>, forces: &mut [[f64; DIM]]) {
+        let mut apply = |n: usize, m: usize, pair_force: [f64; DIM]| {
+            for i in 0..DIM {
+                forces[n][i] += pair_force[i];
+                forces[m][i] -= pair_force[i];
+            }
+        };
+
+        if let Some(cutoff) = self.cutoff {
+            let mut pairs = Vec::new();
+            CellList::new(cutoff, system.positions()).for_each_pair(|n, m| pairs.push((n, m)));
+
+            // NOTE: Pair forces may be computed in parallel, but are accumulated in order
+            let pair_forces = parallel::map(&pairs, |_, &(n, m)| {
+                let (particle, other) = (system.state(n), system.state(m));
+                if self.interacts(&particle, &other) {
+                    self.interaction.pair_force(&particle, &other)
+                } else {
+                    [0.0; DIM]
+                }
+            });
+
+            for (&(n, m), pair_force) in pairs.iter().zip(pair_forces) {
+                apply(n, m, pair_force);
+            }
+        } else {
+            for n in 0..system.len() {
+                for m in n + 1..system.len() {
+                    apply(
+                        n,
+                        m,
+                        self.interaction
+                            .pair_force(&system.state(n), &system.state(m)),
+                    );
+                }
+            }
+        }
+    }
+
+    fn jacobian(
+        &self,
+        particle: &ParticleState<DIM>,
+        system: &ParticleSystem<DIM>,
+    ) -> Option<ForceJacobian<DIM>> {
+        let mut jacobian = ForceJacobian::zero();
+
+        for other in system.states() {
+            if particle.id == other.id || !self.interacts(particle, &other) {
+                continue;
+            }
+
+            jacobian.add(&self.interaction.pair_jacobian(particle, &other)?);
+        }
+
+        Some(jacobian)
+    }
+
+    fn coupling_jacobians(
+        &self,
+        particle: &ParticleState<DIM>,
+        system: &ParticleSystem<DIM>,
+    ) -> Option<Vec<(usize, ForceJacobian<DIM>)>> {
+        let mut couplings = Vec::new();
+
+        for (m, other) in system.states().enumerate() {
+            if particle.id == other.id || !self.interacts(particle, &other) {
+                continue;
+            }
+
+            // NOTE: The pair force only depends on where the particles are and how fast
+            // they move relative to each other, so moving `other` acts the opposite way
+            let pair_jacobian = self.interaction.pair_jacobian(particle, &other)?;
+            couplings.push((
+                m,
+                ForceJacobian {
+                    position: pair_jacobian.position.map(|row| row.map(|x| -x)),
+                    velocity: pair_jacobian.velocity.map(|row| row.map(|x| -x)),
+                },
+            ));
+        }
+
+        Some(couplings)
+    }
+
+    fn potential_energy(&self, system: &ParticleSystem<DIM>) -> Option<f64> {
+        let pair_potential_energy = |n: usize, m: usize| {
+            let (particle, other) = (system.state(n), system.state(m));
+            if self.interacts(&particle, &other) {
+                self.interaction.pair_potential_energy(&particle, &other)
+            } else {
+                Some(0.0)
+            }
+        };
+
+        let mut energy = 0.0;
+        if let Some(cutoff) = self.cutoff {
+            let mut pairs = Vec::new();
+            CellList::new(cutoff, system.positions()).for_each_pair(|n, m| pairs.push((n, m)));
+
+            for (n, m) in pairs {
+                energy += pair_potential_energy(n, m)?;
+            }
+        } else {
+            for n in 0..system.len() {
+                for m in n + 1..system.len() {
+                    energy += pair_potential_energy(n, m)?;
+                }
+            }
+        }
+
+        Some(energy)
+    }
+}
+
+/// Hooke's law spring pulling the particle towards a fixed anchor point.
+#[derive(Debug, Clone, Copy)]
+pub struct LinearSpring<const DIM: usize> {
+    constant: f64,
+    anchor: [f64; DIM],
+}
+
+impl<const DIM: usize> LinearSpring<DIM> {
+    #[must_use]
+    pub fn new(constant: f64, anchor: [f64; DIM]) -> Self {
+        Self { constant, anchor }
+    }
+}
+
+impl<const DIM: usize> ForceModel<DIM> for LinearSpring<DIM> {
+    fn force(&self, particle: &ParticleState<DIM>, _system: &ParticleSystem<DIM>) -> [f64; DIM] {
+        let r = particle.position;
+        let mut force = [0.0; DIM];
+
+        for i in 0..DIM {
+            force[i] = -self.constant * (r[i] - self.anchor[i]);
+        }
+
+        force
+    }
+
+    fn jacobian(
+        &self,
+        _particle: &ParticleState<DIM>,
+        _system: &ParticleSystem<DIM>,
+    ) -> Option<ForceJacobian<DIM>> {
+        let mut jacobian = ForceJacobian::zero();
+        for i in 0..DIM {
+            jacobian.position[i][i] = -self.constant;
+        }
+
+        Some(jacobian)
+    }
+
+    fn coupling_jacobians(
+        &self,
+        _particle: &ParticleState<DIM>,
+        _system: &ParticleSystem<DIM>,
+    ) -> Option<Vec<(usize, ForceJacobian<DIM>)>> {
+        Some(Vec::new())
+    }
+
+    fn potential_energy(&self, system: &ParticleSystem<DIM>) -> Option<f64> {
+        let energy = system
+            .positions()
+            .iter()
+            .map(|r| {
+                let squared_extension = (0..DIM)
+                    .map(|i| (r[i] - self.anchor[i]).powi(2))
+                    .sum::<f64>();
+                0.5 * self.constant * squared_extension
+            })
+            .sum();
+
+        Some(energy)
+    }
+}
+
+/// Viscous damping opposing the velocity of the particle.
+#[derive(Debug, Clone, Copy)]
+pub struct LinearDamper {
+    constant: f64,
+}
+
+impl LinearDamper {
+    #[must_use]
+    pub fn new(constant: f64) -> Self {
+        Self { constant }
+    }
+}
+
+impl<const DIM: usize> ForceModel<DIM> for LinearDamper {
+    fn force(&self, particle: &ParticleState<DIM>, _system: &ParticleSystem<DIM>) -> [f64; DIM] {
+        particle.velocity.map(|v| -self.constant * v)
+    }
+
+    fn jacobian(
+        &self,
+        _particle: &ParticleState<DIM>,
+        _system: &ParticleSystem<DIM>,
+    ) -> Option<ForceJacobian<DIM>> {
+        let mut jacobian = ForceJacobian::zero();
+        for i in 0..DIM {
+            jacobian.velocity[i][i] = -self.constant;
+        }
+
+        Some(jacobian)
+    }
+
+    fn coupling_jacobians(
+        &self,
+        _particle: &ParticleState<DIM>,
+        _system: &ParticleSystem<DIM>,
+    ) -> Option<Vec<(usize, ForceJacobian<DIM>)>> {
+        Some(Vec::new())
+    }
+
+    fn potential_energy(&self, _system: &ParticleSystem<DIM>) -> Option<f64> {
+        Some(0.0)
+    }
+}
+
+/// Uniform field exerting a force proportional to the mass, like gravity.
+#[derive(Debug, Clone, Copy)]
+pub struct ConstantField<const DIM: usize> {
+    acceleration: [f64; DIM],
+}
+
+impl<const DIM: usize> ConstantField<DIM> {
+    #[must_use]
+    pub fn new(acceleration: [f64; DIM]) -> Self {
+        Self { acceleration }
+    }
+}
+
+impl<const DIM: usize> ForceModel<DIM> for ConstantField<DIM> {
+    fn force(&self, particle: &ParticleState<DIM>, _system: &ParticleSystem<DIM>) -> [f64; DIM] {
+        self.acceleration.map(|a| a * particle.mass)
+    }
+
+    fn jacobian(
+        &self,
+        _particle: &ParticleState<DIM>,
+        _system: &ParticleSystem<DIM>,
+    ) -> Option<ForceJacobian<DIM>> {
+        Some(ForceJacobian::zero())
+    }
+
+    fn coupling_jacobians(
+        &self,
+        _particle: &ParticleState<DIM>,
+        _system: &ParticleSystem<DIM>,
+    ) -> Option<Vec<(usize, ForceJacobian<DIM>)>> {
+        Some(Vec::new())
+    }
+
+    fn potential_energy(&self, system: &ParticleSystem<DIM>) -> Option<f64> {
+        let energy = system
+            .states()
+            .map(|particle| {
+                let r = particle.position;
+                -particle.mass * (0..DIM).map(|i| self.acceleration[i] * r[i]).sum::<f64>()
+            })
+            .sum();
+
+        Some(energy)
+    }
+}
+
+/// Linear repulsion between overlapping spheres, proportional to the overlap.
+///
+/// Contacts are elastic unless built [`with_restitution`](Self::with_restitution), which
+/// adds a dashpot damping the speed at which the spheres approach each other.
+///
+/// It is a [`PairInteraction`], to be used as a force model through [`Pairwise`].
+#[derive(Debug, Clone, Copy)]
+pub struct SoftSphereContact {
+    constant: f64,
+    restitution: f64,
+}
+
+impl SoftSphereContact {
+    #[must_use]
+    pub fn new(constant: f64) -> Self {
+        Self::with_restitution(constant, 1.0)
+    }
+
+    /// Contact which leaves a head-on collision with `restitution` times the speed the
+    /// spheres approached at, from 0 for a perfectly inelastic collision to 1.
+    ///
+    /// # Panics
+    ///
+    /// If `restitution` lies outside `[0, 1]`.
+    #[must_use]
+    pub fn with_restitution(constant: f64, restitution: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&restitution),
+            "the coefficient of restitution {restitution} lies outside [0, 1]"
+        );
+
+        Self {
+            constant,
+            restitution,
+        }
+    }
+}
+
+impl<const DIM: usize> PairInteraction<DIM> for SoftSphereContact {
+    fn pair_force(&self, particle: &ParticleState<DIM>, other: &ParticleState<DIM>) -> [f64; DIM] {
+        let position = particle.position;
+        let other_position = other.position;
+        let mut force = [0.0; DIM];
+
+        let mut delta_r = [0.0; DIM];
+        for i in 0..DIM {
+            delta_r[i] = other_position[i] - position[i];
+        }
+
+        let euclidean_distance = delta_r.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
+
+        // NOTE: Coincident centres have no direction to push apart along, and pushing both
+        // along the same one would break Newton's third law
+        let radius_sum = particle.radius + other.radius;
+        if radius_sum < euclidean_distance || euclidean_distance == 0.0 {
+            return force;
+        }
+
+        for i in 0..DIM {
+            force[i] = self.constant
+                // Distance between centers minus the sum of the radii
+                * (euclidean_distance - radius_sum)
+                // Unit vector in the direction of the other particle
+                * (delta_r[i] / euclidean_distance);
+        }
+
+        if self.restitution < 1.0 {
+            let damping = damping_constant(
+                self.constant,
+                self.restitution,
+                reduced_mass(particle.mass, other.mass),
+            );
+            let approach_speed = approach_speed(particle, other, delta_r, euclidean_distance);
+
+            for i in 0..DIM {
+                force[i] -= damping * approach_speed * (delta_r[i] / euclidean_distance);
+            }
+        }
+
+        force
+    }
+
+    fn pair_jacobian(
+        &self,
+        particle: &ParticleState<DIM>,
+        other: &ParticleState<DIM>,
+    ) -> Option<ForceJacobian<DIM>> {
+        let position = particle.position;
+        let other_position = other.position;
+        let mut jacobian = ForceJacobian::zero();
+
+        let mut delta_r = [0.0; DIM];
+        for i in 0..DIM {
+            delta_r[i] = other_position[i] - position[i];
+        }
+
+        let euclidean_distance = delta_r.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
+
+        let radius_sum = particle.radius + other.radius;
+        if radius_sum < euclidean_distance || euclidean_distance == 0.0 {
+            return Some(jacobian);
+        }
+
+        // NOTE: Derivative of k * (1 - R / d) * delta_r, where delta_r decreases with the position
+        let overlap_ratio = radius_sum / euclidean_distance;
+        for i in 0..DIM {
+            for j in 0..DIM {
+                let unit_product = delta_r[i] * delta_r[j] / euclidean_distance.powi(2);
+                let identity = if i == j { 1.0 } else { 0.0 };
+
+                jacobian.position[i][j] -= self.constant
+                    * ((1.0 - overlap_ratio) * identity + overlap_ratio * unit_product);
+            }
+        }
+
+        if self.restitution < 1.0 {
+            let damping = damping_constant(
+                self.constant,
+                self.restitution,
+                reduced_mass(particle.mass, other.mass),
+            );
+            let approach_speed = approach_speed(particle, other, delta_r, euclidean_distance);
+            let unit = delta_r.map(|x| x / euclidean_distance);
+
+            // NOTE: Derivative of -c * (delta_v . n) * n, where n turns with the position
+            for i in 0..DIM {
+                for j in 0..DIM {
+                    let identity = if i == j { 1.0 } else { 0.0 };
+                    let delta_v = particle.velocity[j] - other.velocity[j];
+
+                    jacobian.position[i][j] += damping / euclidean_distance
+                        * ((delta_v - approach_speed * unit[j]) * unit[i]
+                            + approach_speed * (identity - unit[i] * unit[j]));
+                    jacobian.velocity[i][j] -= damping * unit[i] * unit[j];
+                }
+            }
+        }
+
+        Some(jacobian)
+    }
+
+    fn pair_potential_energy(
+        &self,
+        particle: &ParticleState<DIM>,
+        other: &ParticleState<DIM>,
+    ) -> Option<f64> {
+        let euclidean_distance = (0..DIM)
+            .map(|i| (other.position[i] - particle.position[i]).powi(2))
+            .sum::<f64>()
+            .sqrt();
+
+        let overlap = (particle.radius + other.radius - euclidean_distance).max(0.0);
+
+        Some(0.5 * self.constant * overlap.powi(2))
+    }
+}
+
+/// Linear repulsion from the walls of the box spanning from the origin to `bounds`.
+///
+/// Like [`SoftSphereContact`], contacts are elastic unless built
+/// [`with_restitution`](Self::with_restitution).
+#[derive(Debug, Clone, Copy)]
+pub struct WallContact<const DIM: usize> {
+    constant: f64,
+    bounds: [f64; DIM],
+    restitution: f64,
+}
+
+impl<const DIM: usize> WallContact<DIM> {
+    #[must_use]
+    pub fn new(constant: f64, bounds: [f64; DIM]) -> Self {
+        Self::with_restitution(constant, bounds, 1.0)
+    }
+
+    /// Walls which send particles back with `restitution` times the speed they hit them at.
+    ///
+    /// # Panics
+    ///
+    /// If `restitution` lies outside `[0, 1]`.
+    #[must_use]
+    pub fn with_restitution(constant: f64, bounds: [f64; DIM], restitution: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&restitution),
+            "the coefficient of restitution {restitution} lies outside [0, 1]"
+        );
+
+        Self {
+            constant,
+            bounds,
+            restitution,
+        }
+    }
+
+    /// Constant of the dashpot damping the particle while it touches a wall.
+    fn damping(&self, particle: &ParticleState<DIM>) -> f64 {
+        if self.restitution < 1.0 {
+            damping_constant(self.constant, self.restitution, particle.mass)
+        } else {
+            0.0
+        }
+    }
+}
+
+impl<const DIM: usize> ForceModel<DIM> for WallContact<DIM> {
+    fn force(&self, particle: &ParticleState<DIM>, _system: &ParticleSystem<DIM>) -> [f64; DIM] {
+        let position = particle.position;
+        let radius = particle.radius;
+        let damping = self.damping(particle);
+        let mut force = [0.0; DIM];
+
+        for i in 0..DIM {
+            // Lower walls
+            if position[i] <= radius {
+                force[i] = self.constant * (radius - position[i]);
+            }
+            // Upper walls
+            else if position[i] >= self.bounds[i] - radius {
+                force[i] = self.constant * (self.bounds[i] - radius - position[i]);
+            } else {
+                continue;
+            }
+
+            if damping > 0.0 {
+                force[i] -= damping * particle.velocity[i];
+            }
+        }
+
+        force
+    }
+
+    fn jacobian(
+        &self,
+        particle: &ParticleState<DIM>,
+        _system: &ParticleSystem<DIM>,
+    ) -> Option<ForceJacobian<DIM>> {
+        let position = particle.position;
+        let radius = particle.radius;
+        let damping = self.damping(particle);
+        let mut jacobian = ForceJacobian::zero();
+
+        for (i, x) in position.iter().enumerate() {
+            if *x <= radius || *x >= self.bounds[i] - radius {
+                jacobian.position[i][i] = -self.constant;
+                jacobian.velocity[i][i] = -damping;
+            }
+        }
+
+        Some(jacobian)
+    }
+
+    fn coupling_jacobians(
+        &self,
+        _particle: &ParticleState<DIM>,
+        _system: &ParticleSystem<DIM>,
+    ) -> Option<Vec<(usize, ForceJacobian<DIM>)>> {
+        Some(Vec::new())
+    }
+
+    fn potential_energy(&self, system: &ParticleSystem<DIM>) -> Option<f64> {
+        let mut energy = 0.0;
+
+        for particle in system.states() {
+            for (i, x) in particle.position.iter().enumerate() {
+                // NOTE: Depth the particle reaches into the lower or upper wall
+                let penetration = (particle.radius - x)
+                    .max(x - (self.bounds[i] - particle.radius))
+                    .max(0.0);
+
+                energy += 0.5 * self.constant * penetration.powi(2);
+            }
+        }
+
+        Some(energy)
+    }
+}
+
+/// Dry friction against the floor the particles move on, of magnitude `coefficient`
+/// times their weight and opposite to their velocity.
+///
+/// Below `creep_speed` the friction falls linearly to zero with the speed instead, so
+/// particles come to rest smoothly rather than reversing at every step.
+#[derive(Debug, Clone, Copy)]
+pub struct KineticFriction {
+    coefficient: f64,
+    gravity: f64,
+    creep_speed: f64,
+}
+
+impl KineticFriction {
+    #[must_use]
+    pub fn new(coefficient: f64, gravity: f64, creep_speed: f64) -> Self {
+        Self {
+            coefficient,
+            gravity,
+            creep_speed,
+        }
+    }
+}
+
+impl<const DIM: usize> ForceModel<DIM> for KineticFriction {
+    fn force(&self, particle: &ParticleState<DIM>, _system: &ParticleSystem<DIM>) -> [f64; DIM] {
+        let speed = particle.velocity.iter().map(|v| v * v).sum::<f64>().sqrt();
+        let friction = self.coefficient * particle.mass * self.gravity;
+
+        particle
+            .velocity
+            .map(|v| -friction * v / speed.max(self.creep_speed))
+    }
+
+    fn jacobian(
+        &self,
+        particle: &ParticleState<DIM>,
+        _system: &ParticleSystem<DIM>,
+    ) -> Option<ForceJacobian<DIM>> {
+        let speed = particle.velocity.iter().map(|v| v * v).sum::<f64>().sqrt();
+        let friction = self.coefficient * particle.mass * self.gravity;
+        let mut jacobian = ForceJacobian::zero();
+
+        for i in 0..DIM {
+            for j in 0..DIM {
+                let identity = if i == j { 1.0 } else { 0.0 };
+
+                jacobian.velocity[i][j] = if speed > self.creep_speed {
+                    // NOTE: Only the direction of the velocity changes the friction
+                    let unit_product = particle.velocity[i] * particle.velocity[j] / speed.powi(2);
+                    -friction * (identity - unit_product) / speed
+                } else {
+                    -friction * identity / self.creep_speed
+                };
+            }
+        }
+
+        Some(jacobian)
+    }
+
+    fn coupling_jacobians(
+        &self,
+        _particle: &ParticleState<DIM>,
+        _system: &ParticleSystem<DIM>,
+    ) -> Option<Vec<(usize, ForceJacobian<DIM>)>> {
+        Some(Vec::new())
+    }
+
+    fn potential_energy(&self, _system: &ParticleSystem<DIM>) -> Option<f64> {
+        Some(0.0)
+    }
+}
+
+/// Mass which moves like a pair of particles does relative to each other.
+fn reduced_mass(mass: f64, other_mass: f64) -> f64 {
+    mass * other_mass / (mass + other_mass)
+}
+
+/// Constant of the dashpot which, beside a spring of `constant` acting on a body of
+/// `mass`, makes it bounce back with `restitution` times the speed it arrived at.
+fn damping_constant(constant: f64, restitution: f64, mass: f64) -> f64 {
+    // NOTE: Damping ratio of the oscillator whose velocity falls by the restitution in half
+    // a period, critical for a perfectly inelastic contact
+    let damping_ratio = if restitution > 0.0 {
+        let log_restitution = restitution.ln();
+        -log_restitution / (std::f64::consts::PI.powi(2) + log_restitution.powi(2)).sqrt()
+    } else {
+        1.0
+    };
+
+    2.0 * damping_ratio * (constant * mass).sqrt()
+}
+
+/// Speed at which `particle` and `other` approach each other along the line joining them,
+/// `delta_r` pointing from the first to the second and `distance` long.
+fn approach_speed<const DIM: usize>(
+    particle: &ParticleState<DIM>,
+    other: &ParticleState<DIM>,
+    delta_r: [f64; DIM],
+    distance: f64,
+) -> f64 {
+    (0..DIM)
+        .map(|i| (particle.velocity[i] - other.velocity[i]) * delta_r[i] / distance)
+        .sum()
+}
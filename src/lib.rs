@@ -1,9 +1,40 @@
+//! Integration methods for the equations of motion of systems of particles, with the
+//! force models, observers and checkpoints to simulate them.
+//!
+//! # Features
+//!
+//! - `parallel`: evaluates the particles of the system in parallel with rayon, with
+//!   results bitwise identical to the serial build. Force models are `Sync` in either
+//!   build, so code which builds without the feature also builds with it.
+
 use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    forces::ForceModel,
+    methods::{
+        AdaptiveRungeKutta, Beeman, Euler, EulerMod, EulerPredictorCorrector, ExplicitRungeKutta,
+        GearError, GearPredictorCorrector, Implicit, IntegrationMethod, Symplectic, VelocityVerlet,
+        Verlet, VerletLeapFrog,
+    },
+    system::ParticleSystem,
+};
 
+pub mod checkpoint;
+pub mod convergence;
+pub mod diagnostics;
+pub mod events;
+pub mod forces;
 pub mod methods;
+pub mod neighbours;
+mod parallel;
 pub mod particle;
+pub mod simulation;
+pub mod spin;
+pub mod system;
 
-#[derive(ValueEnum, Clone, Debug)]
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
 pub enum Integration {
     Euler,
     EulerMod,
@@ -13,4 +44,179 @@ pub enum Integration {
     Beeman,
     EulerPredictorCorrector,
     GearPredictorCorrector,
+    RungeKuttaMidpoint,
+    Heun,
+    RungeKutta4,
+    RungeKutta38,
+    DormandPrince,
+    Fehlberg,
+    ForestRuth,
+    Yoshida6,
+    Pefrl,
+    ImplicitEuler,
+    ImplicitMidpoint,
+    Trapezoidal,
+}
+
+/// Reason an integration method could not be built.
+#[derive(Debug, PartialEq)]
+pub enum MethodError {
+    Gear(GearError),
+    /// The method only keeps its order for forces which depend on the positions alone.
+    VelocityDependentForces(Integration),
+}
+
+impl std::fmt::Display for MethodError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MethodError::Gear(error) => write!(f, "{error}"),
+            MethodError::VelocityDependentForces(integration) => write!(
+                f,
+                "{integration:?} needs forces which depend on the positions alone"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MethodError {}
+
+impl From<GearError> for MethodError {
+    fn from(error: GearError) -> Self {
+        MethodError::Gear(error)
+    }
+}
+
+/// Settings of the methods which take more than a force model and a step size.
+pub struct MethodSettings {
+    pub absolute_tolerance: f64,
+    pub relative_tolerance: f64,
+    /// Largest internal step of the adaptive methods, which is unbounded without it
+    pub max_step: Option<f64>,
+    /// Highest derivative of the position tracked by the Gear predictor corrector
+    pub gear_order: usize,
+    /// Whether the forces depend on the velocities, which the Gear predictor corrector then
+    /// samples them with and the symplectic compositions do not support
+    pub force_depends_on_velocity: bool,
+    /// Called with the size of every internal step the adaptive methods take
+    pub step_observer: Option<Box<dyn FnMut(f64)>>,
+}
+
+impl Default for MethodSettings {
+    fn default() -> Self {
+        Self {
+            absolute_tolerance: 1e-9,
+            relative_tolerance: 1e-6,
+            max_step: None,
+            gear_order: 5,
+            force_depends_on_velocity: true,
+            step_observer: None,
+        }
+    }
+}
+
+impl Integration {
+    /// Whether the method controls its own internal steps, so that it advances the system
+    /// a whole output interval at a time.
+    #[must_use]
+    pub fn is_adaptive(&self) -> bool {
+        matches!(self, Integration::DormandPrince | Integration::Fehlberg)
+    }
+
+    /// Whether the method keeps its order when the forces depend on the velocities, which
+    /// the symplectic compositions, kicking with the velocity before the kick, do not.
+    #[must_use]
+    pub fn supports_velocity_dependent_forces(&self) -> bool {
+        !matches!(
+            self,
+            Integration::ForestRuth | Integration::Yoshida6 | Integration::Pefrl
+        )
+    }
+
+    /// Integration method of this kind stepping `delta_t` under `force_model`, which
+    /// initialises `system` for the methods which need it. Adaptive methods advance
+    /// `delta_t` in internal steps of their own.
+    ///
+    /// # Errors
+    ///
+    /// If the Gear predictor corrector does not support the order of `settings`, or the
+    /// forces depend on the velocities and the method does not support it.
+    pub fn method<const DIM: usize, F: ForceModel<DIM> + 'static>(
+        &self,
+        force_model: F,
+        system: &mut ParticleSystem<DIM>,
+        delta_t: f64,
+        settings: MethodSettings,
+    ) -> Result<Box<dyn IntegrationMethod<DIM>>, MethodError> {
+        let MethodSettings {
+            absolute_tolerance,
+            relative_tolerance,
+            max_step,
+            gear_order,
+            force_depends_on_velocity,
+            step_observer,
+        } = settings;
+        if force_depends_on_velocity && !self.supports_velocity_dependent_forces() {
+            return Err(MethodError::VelocityDependentForces(*self));
+        }
+
+        let observe = |mut method: AdaptiveRungeKutta<DIM, F>| {
+            if let Some(max_step) = max_step {
+                method = method.with_max_step(max_step);
+            }
+
+            match step_observer {
+                Some(observer) => method.with_step_observer(observer),
+                None => method,
+            }
+        };
+
+        let method: Box<dyn IntegrationMethod<DIM>> = match self {
+            Integration::Euler => Box::new(Euler::new(force_model, delta_t)),
+            Integration::EulerMod => Box::new(EulerMod::new(force_model, delta_t)),
+            Integration::Verlet => Box::new(Verlet::new(force_model, system, delta_t)),
+            Integration::VerletLeapFrog => {
+                Box::new(VerletLeapFrog::new(force_model, system, delta_t))
+            }
+            Integration::VelocityVerlet => Box::new(VelocityVerlet::new(force_model, delta_t)),
+            Integration::Beeman => Box::new(Beeman::new(force_model, system, delta_t)),
+            Integration::EulerPredictorCorrector => {
+                Box::new(EulerPredictorCorrector::new(force_model, delta_t))
+            }
+            Integration::GearPredictorCorrector => Box::new(GearPredictorCorrector::bootstrap(
+                force_model,
+                force_depends_on_velocity,
+                gear_order,
+                system,
+                delta_t,
+            )?),
+            Integration::RungeKuttaMidpoint => {
+                Box::new(ExplicitRungeKutta::midpoint(force_model, delta_t))
+            }
+            Integration::Heun => Box::new(ExplicitRungeKutta::heun(force_model, delta_t)),
+            Integration::RungeKutta4 => Box::new(ExplicitRungeKutta::classic(force_model, delta_t)),
+            Integration::RungeKutta38 => {
+                Box::new(ExplicitRungeKutta::three_eighths_rule(force_model, delta_t))
+            }
+            Integration::DormandPrince => Box::new(observe(AdaptiveRungeKutta::dormand_prince(
+                force_model,
+                delta_t,
+                absolute_tolerance,
+                relative_tolerance,
+            ))),
+            Integration::Fehlberg => Box::new(observe(AdaptiveRungeKutta::fehlberg(
+                force_model,
+                delta_t,
+                absolute_tolerance,
+                relative_tolerance,
+            ))),
+            Integration::ForestRuth => Box::new(Symplectic::forest_ruth(force_model, delta_t)),
+            Integration::Yoshida6 => Box::new(Symplectic::yoshida(force_model, 6, delta_t)),
+            Integration::Pefrl => Box::new(Symplectic::pefrl(force_model, delta_t)),
+            Integration::ImplicitEuler => Box::new(Implicit::backward_euler(force_model, delta_t)),
+            Integration::ImplicitMidpoint => Box::new(Implicit::midpoint(force_model, delta_t)),
+            Integration::Trapezoidal => Box::new(Implicit::trapezoidal(force_model, delta_t)),
+        };
+
+        Ok(method)
+    }
 }
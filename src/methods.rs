@@ -1,511 +1,438 @@
-use crate::particle::Particle;
-
-pub trait IntegrationMethod<const DIM: usize> {
-    fn calculate_step(&self, particle: &Particle<DIM>, others: &[Particle<DIM>])
-        -> Vec<[f64; DIM]>;
-    fn advance_step(&self, particles: &mut [Particle<DIM>]) {
-        let mut derivatives = Vec::new();
-
-        for particle in particles.iter() {
-            derivatives.push(self.calculate_step(particle, particles));
-        }
+use crate::{forces::ForceModel, particle::Particle, system::ParticleSystem};
+
+mod adaptive;
+mod gear;
+mod implicit;
+mod runge_kutta;
+mod symplectic;
+
+pub use adaptive::{AdaptiveRungeKutta, EmbeddedTableau};
+pub use gear::{GearError, GearPredictorCorrector};
+pub use implicit::{Implicit, ImplicitScheme};
+pub use runge_kutta::{ButcherTableau, ExplicitRungeKutta};
+pub use symplectic::Symplectic;
+
+/// Reason an integration method could not take a step.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepError {
+    /// Newton's method of an implicit step did not converge within `iterations`.
+    NotConverged { iterations: usize },
+    /// The step of an adaptive method fell to `step`, `elapsed` into the interval it was
+    /// advancing, with every step still rejected.
+    StepSizeUnderflow { elapsed: f64, step: f64 },
+}
 
-        for (i, particle) in particles.iter_mut().enumerate() {
-            let old = particle.set_derivatives(std::mem::take(&mut derivatives[i]));
-            particle.set_prev_derivatives(old);
+impl std::fmt::Display for StepError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StepError::NotConverged { iterations } => write!(
+                f,
+                "Newton's method did not converge within {iterations} iterations"
+            ),
+            StepError::StepSizeUnderflow { elapsed, step } => write!(
+                f,
+                "step size underflow while integrating adaptively, to {step} at {elapsed} into the step"
+            ),
         }
     }
 }
 
-pub struct Euler<const DIM: usize> {
-    acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
-    delta_t: f64,
-}
+impl std::error::Error for StepError {}
 
-impl<const DIM: usize> Euler<DIM> {
-    pub fn new(
-        acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
-        delta_t: f64,
-    ) -> Self {
-        Self {
-            acceleration_function,
-            delta_t,
-        }
+/// Integrator of the equations of motion of a system of particles.
+///
+/// The forces are evaluated on the whole system at once with [`ForceModel::add_forces`],
+/// so every particle sees the rest of the system at the same stage of the step.
+pub trait IntegrationMethod<const DIM: usize> {
+    /// Advances every particle of the system one step, keeping the derivatives at the
+    /// start of the step as the previous derivatives.
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>);
+
+    /// Advances the system like [`IntegrationMethod::advance_system`], but returns an error
+    /// instead of panicking when the method cannot take the step, leaving the system at
+    /// the start of it. Only implicit and adaptive methods fail.
+    fn try_advance_system(&self, system: &mut ParticleSystem<DIM>) -> Result<(), StepError> {
+        self.advance_system(system);
+        Ok(())
     }
-}
 
-impl<const DIM: usize> IntegrationMethod<DIM> for Euler<DIM> {
-    fn calculate_step(
-        &self,
-        particle: &Particle<DIM>,
-        others: &[Particle<DIM>],
-    ) -> Vec<[f64; DIM]> {
-        let r = particle.derivatives();
-        let mut new_r = particle.cloned_derivatives();
+    /// State the method carries from one step to the next besides the system, which a
+    /// checkpoint must hold to resume exactly. Most methods carry none.
+    fn state(&self) -> Vec<f64> {
+        Vec::new()
+    }
 
-        for i in 0..DIM {
-            new_r[0][i] += self.delta_t * r[1][i] + self.delta_t.powi(2) / 2.0 * r[2][i];
-            new_r[1][i] += self.delta_t * r[2][i];
-        }
+    /// Restores the state returned by [`IntegrationMethod::state`], which is checked to be
+    /// as long as the method's own.
+    fn restore_state(&self, _state: &[f64]) {}
 
-        let new_p = Particle::new(
-            particle.id(),
-            new_r[0],
-            new_r[1],
-            new_r[2],
-            particle.radius(),
-            particle.mass(),
-        );
-        new_r[2] = (self.acceleration_function)(&new_p, others);
-
-        new_r
+    /// Advances particles stored one by one, copying them into a [`ParticleSystem`] for
+    /// the step and back, which is slower than keeping them in a system.
+    fn advance_step(&self, particles: &mut [Particle<DIM>]) {
+        let mut system = ParticleSystem::new(particles);
+        self.advance_system(&mut system);
+        system.write_to(particles);
     }
 }
 
-pub struct EulerMod<const DIM: usize> {
-    acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
+pub struct Euler<const DIM: usize, F> {
+    force_model: F,
     delta_t: f64,
 }
 
-impl<const DIM: usize> EulerMod<DIM> {
-    pub fn new(
-        acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
-        delta_t: f64,
-    ) -> Self {
+impl<const DIM: usize, F: ForceModel<DIM>> Euler<DIM, F> {
+    pub fn new(force_model: F, delta_t: f64) -> Self {
         Self {
-            acceleration_function,
+            force_model,
             delta_t,
         }
     }
 }
 
-impl<const DIM: usize> IntegrationMethod<DIM> for EulerMod<DIM> {
-    fn calculate_step(
-        &self,
-        particle: &Particle<DIM>,
-        others: &[Particle<DIM>],
-    ) -> Vec<[f64; DIM]> {
-        let r = particle.derivatives();
-        let mut new_r = particle.cloned_derivatives();
-
-        for i in 0..DIM {
-            new_r[1][i] += self.delta_t * r[2][i];
-            new_r[0][i] += self.delta_t * new_r[1][i] + self.delta_t.powi(2) / 2.0 * r[2][i];
-        }
-
-        let new_p = Particle::new(
-            particle.id(),
-            new_r[0],
-            new_r[1],
-            new_r[2],
-            particle.radius(),
-            particle.mass(),
-        );
-        new_r[2] = (self.acceleration_function)(&new_p, others);
-
-        new_r
+impl<const DIM: usize, F: ForceModel<DIM>> IntegrationMethod<DIM> for Euler<DIM, F> {
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>) {
+        euler_step(&self.force_model, self.delta_t, system);
     }
 }
 
-pub struct Verlet<const DIM: usize> {
-    acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
+fn euler_step<const DIM: usize, F: ForceModel<DIM>>(
+    force_model: &F,
     delta_t: f64,
-}
-
-impl<const DIM: usize> Verlet<DIM> {
-    pub fn new(
-        acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
-        particles_to_init: &mut [Particle<DIM>],
-        delta_t: f64,
-    ) -> Self {
-        let euler = Euler::new(acceleration_function, -delta_t);
-
-        let mut prev_derivatives = Vec::new();
-        for particle in particles_to_init.iter() {
-            prev_derivatives.push(euler.calculate_step(particle, particles_to_init));
-        }
+    system: &mut ParticleSystem<DIM>,
+) {
+    system.save_prev_derivatives();
 
-        for (i, particle) in particles_to_init.iter_mut().enumerate() {
-            particle.set_prev_derivatives(std::mem::take(&mut prev_derivatives[i]));
-        }
+    system.update_derivative(0, |n, position, system| {
+        let r = &system.prev_derivatives;
 
-        Self {
-            acceleration_function,
-            delta_t,
+        for i in 0..DIM {
+            position[i] += delta_t * r[1][n][i] + delta_t.powi(2) / 2.0 * r[2][n][i];
         }
-    }
-}
-
-impl<const DIM: usize> IntegrationMethod<DIM> for Verlet<DIM> {
-    fn calculate_step(
-        &self,
-        particle: &Particle<DIM>,
-        others: &[Particle<DIM>],
-    ) -> Vec<[f64; DIM]> {
-        let r = particle.derivatives();
-        let old_r = particle.prev_derivatives();
-        let mut new_r = particle.cloned_derivatives();
+    });
+    system.update_derivative(1, |n, velocity, system| {
+        let r = &system.prev_derivatives;
 
         for i in 0..DIM {
-            new_r[0][i] *= 2.0;
-            new_r[0][i] += -old_r[0][i] + self.delta_t.powi(2) * r[2][i];
-
-            new_r[1][i] = (new_r[0][i] - old_r[0][i]) / (2.0 * self.delta_t);
+            velocity[i] += delta_t * r[2][n][i];
         }
+    });
 
-        let new_p = Particle::new(
-            particle.id(),
-            new_r[0],
-            new_r[1],
-            new_r[2],
-            particle.radius(),
-            particle.mass(),
-        );
-        new_r[2] = (self.acceleration_function)(&new_p, others);
-
-        new_r
-    }
+    system.update_accelerations(force_model);
 }
 
-pub struct VerletLeapFrog<const DIM: usize> {
-    acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
+/// Estimates the previous derivatives of every particle with an Euler step of `delta_t`,
+/// which is negative to step backwards in time.
+fn init_prev_derivatives<const DIM: usize, F: ForceModel<DIM>>(
+    force_model: &F,
+    system: &mut ParticleSystem<DIM>,
     delta_t: f64,
+) {
+    let mut previous = system.clone();
+    euler_step(force_model, delta_t, &mut previous);
+
+    system.prev_derivatives = previous.derivatives;
 }
 
-impl<const DIM: usize> VerletLeapFrog<DIM> {
-    pub fn new(
-        acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
-        particles_to_init: &mut [Particle<DIM>],
-        delta_t: f64,
-    ) -> Self {
-        let euler = Euler::new(acceleration_function, -delta_t / 2.0);
-
-        let mut prev_derivatives = Vec::new();
-        for particle in particles_to_init.iter() {
-            prev_derivatives.push(euler.calculate_step(particle, particles_to_init));
-        }
+pub struct EulerMod<const DIM: usize, F> {
+    force_model: F,
+    delta_t: f64,
+}
 
-        for (i, particle) in particles_to_init.iter_mut().enumerate() {
-            particle.set_prev_derivatives(std::mem::take(&mut prev_derivatives[i]));
-        }
+impl<const DIM: usize, F: ForceModel<DIM>> EulerMod<DIM, F> {
+    pub fn new(force_model: F, delta_t: f64) -> Self {
         Self {
-            acceleration_function,
+            force_model,
             delta_t,
         }
     }
+}
 
-    fn get_v_half_step(&self, particle: &Particle<DIM>) -> [f64; DIM] {
-        let r = particle.derivatives();
-        let old_r = particle.prev_derivatives();
+impl<const DIM: usize, F: ForceModel<DIM>> IntegrationMethod<DIM> for EulerMod<DIM, F> {
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>) {
+        let delta_t = self.delta_t;
+        system.save_prev_derivatives();
 
-        let mut v_half_step = [0.0; DIM];
+        system.update_derivative(1, |n, velocity, system| {
+            let r = &system.prev_derivatives;
 
-        for i in 0..DIM {
-            v_half_step[i] = old_r[1][i] + self.delta_t * r[2][i];
-        }
+            for i in 0..DIM {
+                velocity[i] += delta_t * r[2][n][i];
+            }
+        });
+        system.update_derivative(0, |n, position, system| {
+            let r = &system.prev_derivatives;
+            let new_velocity = system.velocities()[n];
+
+            for i in 0..DIM {
+                position[i] += delta_t * new_velocity[i] + delta_t.powi(2) / 2.0 * r[2][n][i];
+            }
+        });
 
-        v_half_step
+        system.update_accelerations(&self.force_model);
     }
 }
 
-impl<const DIM: usize> IntegrationMethod<DIM> for VerletLeapFrog<DIM> {
-    fn calculate_step(
-        &self,
-        particle: &Particle<DIM>,
-        others: &[Particle<DIM>],
-    ) -> Vec<[f64; DIM]> {
-        let old_r = particle.prev_derivatives();
-        let mut new_r = particle.cloned_derivatives();
+pub struct Verlet<const DIM: usize, F> {
+    force_model: F,
+    delta_t: f64,
+}
 
-        let v_half_step = self.get_v_half_step(particle);
+impl<const DIM: usize, F: ForceModel<DIM>> Verlet<DIM, F> {
+    pub fn new(force_model: F, system: &mut ParticleSystem<DIM>, delta_t: f64) -> Self {
+        init_prev_derivatives(&force_model, system, -delta_t);
 
-        for i in 0..DIM {
-            new_r[0][i] += self.delta_t * v_half_step[i];
-
-            new_r[1][i] = (old_r[1][i] - v_half_step[i]) / 2.0;
+        Self {
+            force_model,
+            delta_t,
         }
-
-        let new_p = Particle::new(
-            particle.id(),
-            new_r[0],
-            new_r[1],
-            new_r[2],
-            particle.radius(),
-            particle.mass(),
-        );
-        new_r[2] = (self.acceleration_function)(&new_p, others);
-
-        new_r
     }
+}
 
-    fn advance_step(&self, particles: &mut [Particle<DIM>]) {
-        let mut derivatives = Vec::new();
+impl<const DIM: usize, F: ForceModel<DIM>> IntegrationMethod<DIM> for Verlet<DIM, F> {
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>) {
+        let delta_t = self.delta_t;
+        system.shift_prev_derivatives();
 
-        for particle in particles.iter() {
-            derivatives.push(self.calculate_step(particle, particles));
-        }
+        system.update_derivative(0, |n, position, system| {
+            let (r, old_r) = (&system.prev_derivatives, &system.scratch);
 
-        for (i, particle) in particles.iter_mut().enumerate() {
-            let mut old = particle.set_derivatives(std::mem::take(&mut derivatives[i]));
+            for i in 0..DIM {
+                position[i] *= 2.0;
+                position[i] += -old_r[0][n][i] + delta_t.powi(2) * r[2][n][i];
+            }
+        });
+        system.update_derivative(1, |n, velocity, system| {
+            let (r, old_r) = (&system.prev_derivatives, &system.scratch);
+            let new_position = system.positions()[n];
+
+            for i in 0..DIM {
+                // NOTE: Backward difference of the positions, second order accurate at t + delta_t
+                velocity[i] =
+                    (3.0 * new_position[i] - 4.0 * r[0][n][i] + old_r[0][n][i]) / (2.0 * delta_t);
+            }
+        });
 
-            // NOTE: Use v(t + delta_t/2) for previous instead of v(t)
-            old[1] = self.get_v_half_step(particle);
-            particle.set_prev_derivatives(old);
-        }
+        system.update_accelerations(&self.force_model);
     }
 }
 
-pub struct VelocityVerlet<const DIM: usize> {
-    acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
+pub struct VerletLeapFrog<const DIM: usize, F> {
+    force_model: F,
     delta_t: f64,
 }
 
-impl<const DIM: usize> VelocityVerlet<DIM> {
-    pub fn new(
-        acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
-        delta_t: f64,
-    ) -> Self {
+impl<const DIM: usize, F: ForceModel<DIM>> VerletLeapFrog<DIM, F> {
+    pub fn new(force_model: F, system: &mut ParticleSystem<DIM>, delta_t: f64) -> Self {
+        init_prev_derivatives(&force_model, system, -delta_t / 2.0);
         Self {
-            acceleration_function,
+            force_model,
             delta_t,
         }
     }
 }
 
-impl<const DIM: usize> IntegrationMethod<DIM> for VelocityVerlet<DIM> {
-    fn calculate_step(
-        &self,
-        particle: &Particle<DIM>,
-        others: &[Particle<DIM>],
-    ) -> Vec<[f64; DIM]> {
-        let r = particle.derivatives();
-        let mut new_r = particle.cloned_derivatives();
-
-        let new_p = Particle::new(
-            particle.id(),
-            new_r[0],
-            new_r[1],
-            new_r[2],
-            particle.radius(),
-            particle.mass(),
-        );
-        new_r[2] = (self.acceleration_function)(&new_p, others);
+impl<const DIM: usize, F: ForceModel<DIM>> IntegrationMethod<DIM> for VerletLeapFrog<DIM, F> {
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>) {
+        let delta_t = self.delta_t;
+        system.shift_prev_derivatives();
 
-        for i in 0..DIM {
-            new_r[0][i] += self.delta_t * r[1][i] + self.delta_t.powi(2) * r[2][i];
-            new_r[1][i] += self.delta_t / 2.0 * (r[2][i] + new_r[2][i]);
-        }
+        // NOTE: Use v(t + delta_t/2) for previous instead of v(t)
+        system.update_prev_derivative(1, |n, v_half_step, system| {
+            let (r, old_r) = (&system.prev_derivatives, &system.scratch);
+
+            for i in 0..DIM {
+                v_half_step[i] = old_r[1][n][i] + delta_t * r[2][n][i];
+            }
+        });
+
+        system.update_derivative(0, |n, position, system| {
+            let v_half_step = system.prev_derivatives[1][n];
+
+            for i in 0..DIM {
+                position[i] += delta_t * v_half_step[i];
+            }
+        });
+        system.update_derivative(1, |n, velocity, system| {
+            let r = &system.prev_derivatives;
+            let v_half_step = r[1][n];
+
+            for i in 0..DIM {
+                // NOTE: Predict v(t + delta_t) with a(t) to evaluate velocity dependent forces
+                velocity[i] = v_half_step[i] + delta_t / 2.0 * r[2][n][i];
+            }
+        });
+
+        system.update_accelerations(&self.force_model);
 
-        new_r
+        system.update_derivative(1, |n, velocity, system| {
+            let v_half_step = system.prev_derivatives[1][n];
+            let acceleration = system.accelerations()[n];
+
+            for i in 0..DIM {
+                velocity[i] = v_half_step[i] + delta_t / 2.0 * acceleration[i];
+            }
+        });
     }
 }
 
-pub struct Beeman<const DIM: usize> {
-    acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
+pub struct VelocityVerlet<const DIM: usize, F> {
+    force_model: F,
     delta_t: f64,
 }
 
-impl<const DIM: usize> Beeman<DIM> {
-    pub fn new(
-        acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
-        particles_to_init: &mut [Particle<DIM>],
-        delta_t: f64,
-    ) -> Self {
-        let euler = Euler::new(acceleration_function, -delta_t);
-
-        let mut prev_derivatives = Vec::new();
-        for particle in particles_to_init.iter() {
-            prev_derivatives.push(euler.calculate_step(particle, particles_to_init));
-        }
-
-        for (i, particle) in particles_to_init.iter_mut().enumerate() {
-            particle.set_prev_derivatives(std::mem::take(&mut prev_derivatives[i]));
-        }
-
+impl<const DIM: usize, F: ForceModel<DIM>> VelocityVerlet<DIM, F> {
+    pub fn new(force_model: F, delta_t: f64) -> Self {
         Self {
-            acceleration_function,
+            force_model,
             delta_t,
         }
     }
 }
 
-impl<const DIM: usize> IntegrationMethod<DIM> for Beeman<DIM> {
-    fn calculate_step(
-        &self,
-        particle: &Particle<DIM>,
-        others: &[Particle<DIM>],
-    ) -> Vec<[f64; DIM]> {
-        let r = particle.derivatives();
-        let old_r = particle.prev_derivatives();
-        let mut new_r = particle.cloned_derivatives();
+impl<const DIM: usize, F: ForceModel<DIM>> IntegrationMethod<DIM> for VelocityVerlet<DIM, F> {
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>) {
+        let delta_t = self.delta_t;
+        system.save_prev_derivatives();
 
-        for i in 0..DIM {
-            new_r[0][i] += r[1][i] * self.delta_t + 2.0 / 3.0 * r[2][i] * self.delta_t.powi(2)
-                - 1.0 / 6.0 * old_r[2][i] * self.delta_t.powi(2);
+        system.update_derivative(0, |n, position, system| {
+            let r = &system.prev_derivatives;
 
-            new_r[1][i] += 1.0 / 3.0 * new_r[2][i] * self.delta_t
-                + 5.0 / 6.0 * r[2][i] * self.delta_t
-                - 1.0 / 6.0 * old_r[2][i] * self.delta_t;
-        }
+            for i in 0..DIM {
+                position[i] += delta_t * r[1][n][i] + delta_t.powi(2) / 2.0 * r[2][n][i];
+            }
+        });
+        system.update_derivative(1, |n, velocity, system| {
+            let r = &system.prev_derivatives;
+
+            for i in 0..DIM {
+                // NOTE: Predict v(t + delta_t) to evaluate velocity dependent forces
+                velocity[i] += delta_t * r[2][n][i];
+            }
+        });
+
+        system.update_accelerations(&self.force_model);
+
+        system.update_derivative(1, |n, velocity, system| {
+            let r = &system.prev_derivatives;
+            let acceleration = system.accelerations()[n];
 
-        let new_p = Particle::new(
-            particle.id(),
-            new_r[0],
-            new_r[1],
-            new_r[2],
-            particle.radius(),
-            particle.mass(),
-        );
-        new_r[2] = (self.acceleration_function)(&new_p, others);
-
-        new_r
+            for i in 0..DIM {
+                velocity[i] = r[1][n][i] + delta_t / 2.0 * (r[2][n][i] + acceleration[i]);
+            }
+        });
     }
 }
 
-pub struct EulerPredictorCorrector<const DIM: usize> {
-    acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
+pub struct Beeman<const DIM: usize, F> {
+    force_model: F,
     delta_t: f64,
 }
 
-impl<const DIM: usize> EulerPredictorCorrector<DIM> {
-    pub fn new(
-        acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
-        delta_t: f64,
-    ) -> Self {
+impl<const DIM: usize, F: ForceModel<DIM>> Beeman<DIM, F> {
+    pub fn new(force_model: F, system: &mut ParticleSystem<DIM>, delta_t: f64) -> Self {
+        init_prev_derivatives(&force_model, system, -delta_t);
+
         Self {
-            acceleration_function,
+            force_model,
             delta_t,
         }
     }
 }
 
-impl<const DIM: usize> IntegrationMethod<DIM> for EulerPredictorCorrector<DIM> {
-    fn calculate_step(
-        &self,
-        particle: &Particle<DIM>,
-        others: &[Particle<DIM>],
-    ) -> Vec<[f64; DIM]> {
-        let r = particle.derivatives();
-        let mut new_r = particle.cloned_derivatives();
+impl<const DIM: usize, F: ForceModel<DIM>> IntegrationMethod<DIM> for Beeman<DIM, F> {
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>) {
+        let delta_t = self.delta_t;
+        system.shift_prev_derivatives();
 
-        // Predict
-        for i in 0..DIM {
-            new_r[1][i] += r[2][i] * self.delta_t;
-            new_r[0][i] += r[1][i] * self.delta_t;
-        }
+        system.update_derivative(0, |n, position, system| {
+            let (r, old_r) = (&system.prev_derivatives, &system.scratch);
 
-        // Evalate
-        let new_p = Particle::new(
-            particle.id(),
-            new_r[0],
-            new_r[1],
-            new_r[2],
-            particle.radius(),
-            particle.mass(),
-        );
-        new_r[2] = (self.acceleration_function)(&new_p, others);
+            for i in 0..DIM {
+                position[i] += r[1][n][i] * delta_t + 2.0 / 3.0 * r[2][n][i] * delta_t.powi(2)
+                    - 1.0 / 6.0 * old_r[2][n][i] * delta_t.powi(2);
+            }
+        });
+        system.update_derivative(1, |n, velocity, system| {
+            let (r, old_r) = (&system.prev_derivatives, &system.scratch);
+
+            for i in 0..DIM {
+                // NOTE: Predict v(t + delta_t) to evaluate velocity dependent forces
+                velocity[i] +=
+                    3.0 / 2.0 * r[2][n][i] * delta_t - 1.0 / 2.0 * old_r[2][n][i] * delta_t;
+            }
+        });
 
-        // Correct
-        for i in 0..DIM {
-            new_r[1][i] = r[1][i] + new_r[2][i] * self.delta_t;
-            new_r[0][i] = r[0][i] + new_r[1][i] * self.delta_t;
-        }
+        system.update_accelerations(&self.force_model);
 
-        new_r
+        system.update_derivative(1, |n, velocity, system| {
+            let (r, old_r) = (&system.prev_derivatives, &system.scratch);
+            let acceleration = system.accelerations()[n];
+
+            for i in 0..DIM {
+                velocity[i] = r[1][n][i]
+                    + 1.0 / 3.0 * acceleration[i] * delta_t
+                    + 5.0 / 6.0 * r[2][n][i] * delta_t
+                    - 1.0 / 6.0 * old_r[2][n][i] * delta_t;
+            }
+        });
     }
 }
 
-pub struct GearPredictorCorrector<const DIM: usize> {
-    acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
-    acceleration_function_depends_on_velocity: bool,
+pub struct EulerPredictorCorrector<const DIM: usize, F> {
+    force_model: F,
     delta_t: f64,
 }
 
-impl<const DIM: usize> GearPredictorCorrector<DIM> {
-    pub fn new(
-        acceleration_function: fn(particle: &Particle<DIM>, others: &[Particle<DIM>]) -> [f64; DIM],
-        acceleration_function_depends_on_velocity: bool,
-        particles_to_init: Vec<(&mut Particle<DIM>, Vec<[f64; DIM]>)>,
-        delta_t: f64,
-    ) -> Self {
-        for (particle, derivatives) in particles_to_init {
-            for derivative in derivatives {
-                particle.add_derivative(derivative);
-            }
-        }
-
+impl<const DIM: usize, F: ForceModel<DIM>> EulerPredictorCorrector<DIM, F> {
+    pub fn new(force_model: F, delta_t: f64) -> Self {
         Self {
-            acceleration_function,
-            acceleration_function_depends_on_velocity,
+            force_model,
             delta_t,
         }
     }
 }
 
-impl<const DIM: usize> IntegrationMethod<DIM> for GearPredictorCorrector<DIM> {
-    fn calculate_step(
-        &self,
-        particle: &Particle<DIM>,
-        others: &[Particle<DIM>],
-    ) -> Vec<[f64; DIM]> {
-        let r = particle.derivatives();
+impl<const DIM: usize, F: ForceModel<DIM>> IntegrationMethod<DIM>
+    for EulerPredictorCorrector<DIM, F>
+{
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>) {
+        let delta_t = self.delta_t;
+        system.save_prev_derivatives();
 
         // Predict
-        let mut new_r = particle.cloned_derivatives();
-        let delta_time_2 = self.delta_t.powi(2);
-        let delta_time_3 = self.delta_t.powi(3);
-        let delta_time_4 = self.delta_t.powi(4);
-        let delta_time_5 = self.delta_t.powi(5);
+        system.update_derivative(1, |n, velocity, system| {
+            let r = &system.prev_derivatives;
 
-        for i in 0..DIM {
-            new_r[0][i] += r[1][i] * self.delta_t
-                + 1.0 / 2.0 * delta_time_2 * r[2][i]
-                + 1.0 / 6.0 * delta_time_3 * r[3][i]
-                + 1.0 / 24.0 * delta_time_4 * r[4][i]
-                + 1.0 / 120.0 * delta_time_5 * r[5][i];
-            new_r[1][i] += r[2][i] * self.delta_t
-                + 1.0 / 2.0 * delta_time_2 * r[3][i]
-                + 1.0 / 6.0 * delta_time_3 * r[4][i]
-                + 1.0 / 24.0 * delta_time_4 * r[5][i];
-            new_r[2][i] += r[3][i] * self.delta_t
-                + 1.0 / 2.0 * delta_time_2 * r[4][i]
-                + 1.0 / 6.0 * delta_time_3 * r[5][i];
-            new_r[3][i] += r[4][i] * self.delta_t + 1.0 / 2.0 * delta_time_2 * r[5][i];
-            new_r[4][i] += r[5][i] * self.delta_t;
-        }
+            for i in 0..DIM {
+                velocity[i] += r[2][n][i] * delta_t;
+            }
+        });
+        system.update_derivative(0, |n, position, system| {
+            let r = &system.prev_derivatives;
 
-        // Evaluate
-        let new_p = Particle::new(
-            particle.id(),
-            new_r[0],
-            new_r[1],
-            new_r[2],
-            particle.radius(),
-            particle.mass(),
-        );
-        let new_acceleration = (self.acceleration_function)(&new_p, others);
-
-        let mut delta_acc = [0.0; DIM];
-        for i in 0..DIM {
-            delta_acc[i] = (new_acceleration[i] - new_r[2][i]) * delta_time_2 / 2.0;
-        }
+            for i in 0..DIM {
+                position[i] += r[1][n][i] * delta_t;
+            }
+        });
 
-        let alpha_0 = if self.acceleration_function_depends_on_velocity {
-            3.0 / 16.0
-        } else {
-            3.0 / 20.0
-        };
+        system.update_accelerations(&self.force_model);
 
         // Correct
-        for i in 0..DIM {
-            new_r[0][i] += alpha_0 * delta_acc[i];
-            new_r[1][i] += 251.0 / 360.0 * delta_acc[i] / self.delta_t;
-            new_r[2][i] += 2.0 * delta_acc[i] / delta_time_2;
-            new_r[3][i] += 11.0 / 3.0 * delta_acc[i] / delta_time_3;
-            new_r[4][i] += 4.0 * delta_acc[i] / delta_time_4;
-            new_r[5][i] += 2.0 * delta_acc[i] / delta_time_5;
-        }
+        system.update_derivative(1, |n, velocity, system| {
+            let r = &system.prev_derivatives;
+            let acceleration = system.accelerations()[n];
 
-        new_r
+            for i in 0..DIM {
+                velocity[i] = r[1][n][i] + acceleration[i] * delta_t;
+            }
+        });
+        system.update_derivative(0, |n, position, system| {
+            let r = &system.prev_derivatives;
+            let new_velocity = system.velocities()[n];
+
+            for i in 0..DIM {
+                position[i] = r[0][n][i] + new_velocity[i] * delta_t;
+            }
+        });
     }
 }
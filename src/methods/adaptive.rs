@@ -0,0 +1,359 @@
+use std::cell::{Cell, RefCell};
+
+use crate::{forces::ForceModel, system::ParticleSystem};
+
+use super::{
+    runge_kutta::{combine_stages, runge_kutta_stages},
+    ButcherTableau, IntegrationMethod, StepError,
+};
+
+const SAFETY_FACTOR: f64 = 0.9;
+const MIN_STEP_FACTOR: f64 = 0.2;
+const MAX_STEP_FACTOR: f64 = 5.0;
+
+type StepObserver = Box<dyn FnMut(f64)>;
+
+/// Explicit Runge-Kutta pair sharing its stages between two solutions of different order.
+///
+/// `b` of the inner tableau gives the propagated solution, while `error` holds the
+/// difference between its weights and those of the embedded solution.
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedTableau {
+    pub tableau: ButcherTableau,
+    pub error: &'static [f64],
+    /// Lowest order of the pair, which sets the exponent of the step size control
+    pub order: i32,
+}
+
+impl EmbeddedTableau {
+    pub const DORMAND_PRINCE: Self = Self {
+        tableau: ButcherTableau {
+            a: &[
+                &[],
+                &[1.0 / 5.0],
+                &[3.0 / 40.0, 9.0 / 40.0],
+                &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+                &[
+                    19372.0 / 6561.0,
+                    -25360.0 / 2187.0,
+                    64448.0 / 6561.0,
+                    -212.0 / 729.0,
+                ],
+                &[
+                    9017.0 / 3168.0,
+                    -355.0 / 33.0,
+                    46732.0 / 5247.0,
+                    49.0 / 176.0,
+                    -5103.0 / 18656.0,
+                ],
+                &[
+                    35.0 / 384.0,
+                    0.0,
+                    500.0 / 1113.0,
+                    125.0 / 192.0,
+                    -2187.0 / 6784.0,
+                    11.0 / 84.0,
+                ],
+            ],
+            b: &[
+                35.0 / 384.0,
+                0.0,
+                500.0 / 1113.0,
+                125.0 / 192.0,
+                -2187.0 / 6784.0,
+                11.0 / 84.0,
+                0.0,
+            ],
+            c: &[0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0],
+        },
+        error: &[
+            71.0 / 57600.0,
+            0.0,
+            -71.0 / 16695.0,
+            71.0 / 1920.0,
+            -17253.0 / 339200.0,
+            22.0 / 525.0,
+            -1.0 / 40.0,
+        ],
+        order: 4,
+    };
+
+    pub const FEHLBERG: Self = Self {
+        tableau: ButcherTableau {
+            a: &[
+                &[],
+                &[1.0 / 4.0],
+                &[3.0 / 32.0, 9.0 / 32.0],
+                &[1932.0 / 2197.0, -7200.0 / 2197.0, 7296.0 / 2197.0],
+                &[439.0 / 216.0, -8.0, 3680.0 / 513.0, -845.0 / 4104.0],
+                &[
+                    -8.0 / 27.0,
+                    2.0,
+                    -3544.0 / 2565.0,
+                    1859.0 / 4104.0,
+                    -11.0 / 40.0,
+                ],
+            ],
+            b: &[
+                16.0 / 135.0,
+                0.0,
+                6656.0 / 12825.0,
+                28561.0 / 56430.0,
+                -9.0 / 50.0,
+                2.0 / 55.0,
+            ],
+            c: &[0.0, 1.0 / 4.0, 3.0 / 8.0, 12.0 / 13.0, 1.0, 1.0 / 2.0],
+        },
+        error: &[
+            1.0 / 360.0,
+            0.0,
+            -128.0 / 4275.0,
+            -2197.0 / 75240.0,
+            1.0 / 50.0,
+            2.0 / 55.0,
+        ],
+        order: 4,
+    };
+}
+
+/// Embedded Runge-Kutta method with error control on the step size.
+///
+/// Every call to [`IntegrationMethod::advance_system`] advances the system exactly `delta_t`,
+/// taking as many internal steps as the tolerances require. The steps grow up to the
+/// largest step, unbounded unless set with [`AdaptiveRungeKutta::with_max_step`], and only
+/// the last one is truncated to land on the end of the interval. The state is thus always
+/// available at multiples of `delta_t`, usually the output interval, and the previous
+/// derivatives are left at the start of the whole interval.
+///
+/// # Panics
+///
+/// [`IntegrationMethod::advance_system`] panics once the step size underflows, as it does
+/// when the forces are not finite and every step is rejected, where
+/// [`IntegrationMethod::try_advance_system`] returns a [`StepError`] instead.
+pub struct AdaptiveRungeKutta<const DIM: usize, F> {
+    force_model: F,
+    tableau: EmbeddedTableau,
+    delta_t: f64,
+    absolute_tolerance: f64,
+    relative_tolerance: f64,
+    max_step: f64,
+    step: Cell<f64>,
+    step_observer: RefCell<Option<StepObserver>>,
+    /// Derivatives at the start of the interval, while the internal steps use the previous
+    /// derivatives of the system
+    interval_start: RefCell<Vec<Vec<[f64; DIM]>>>,
+}
+
+impl<const DIM: usize, F: ForceModel<DIM>> AdaptiveRungeKutta<DIM, F> {
+    pub fn new(
+        force_model: F,
+        tableau: EmbeddedTableau,
+        delta_t: f64,
+        absolute_tolerance: f64,
+        relative_tolerance: f64,
+    ) -> Self {
+        Self {
+            force_model,
+            tableau,
+            delta_t,
+            absolute_tolerance,
+            relative_tolerance,
+            max_step: f64::INFINITY,
+            step: Cell::new(delta_t),
+            step_observer: RefCell::new(None),
+            interval_start: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn dormand_prince(
+        force_model: F,
+        delta_t: f64,
+        absolute_tolerance: f64,
+        relative_tolerance: f64,
+    ) -> Self {
+        Self::new(
+            force_model,
+            EmbeddedTableau::DORMAND_PRINCE,
+            delta_t,
+            absolute_tolerance,
+            relative_tolerance,
+        )
+    }
+
+    pub fn fehlberg(
+        force_model: F,
+        delta_t: f64,
+        absolute_tolerance: f64,
+        relative_tolerance: f64,
+    ) -> Self {
+        Self::new(
+            force_model,
+            EmbeddedTableau::FEHLBERG,
+            delta_t,
+            absolute_tolerance,
+            relative_tolerance,
+        )
+    }
+
+    /// Bounds the internal steps by `max_step`, which may be larger than `delta_t`.
+    #[must_use]
+    pub fn with_max_step(mut self, max_step: f64) -> Self {
+        self.max_step = max_step;
+        self.step.set(self.step.get().min(max_step));
+        self
+    }
+
+    /// Registers a function called with the size of every accepted internal step.
+    #[must_use]
+    pub fn with_step_observer(self, observer: impl FnMut(f64) + 'static) -> Self {
+        *self.step_observer.borrow_mut() = Some(Box::new(observer));
+        self
+    }
+
+    /// Advances the system a step of `step` from its previous derivatives, leaving the
+    /// error estimates of the positions and velocities in the scratch arrays after those
+    /// of the stages.
+    fn try_step(&self, system: &mut ParticleSystem<DIM>, step: f64) {
+        let tableau = &self.tableau.tableau;
+        runge_kutta_stages(&self.force_model, tableau, step, system);
+        combine_stages(tableau.b, step, system);
+
+        let errors = 2 * tableau.stages();
+        system.resize_scratch(errors + 2);
+
+        let error_weights = self.tableau.error;
+        for derivative in 0..2 {
+            system.update_scratch(errors + derivative, |n, error, system| {
+                *error = [0.0; DIM];
+
+                for (j, e_j) in error_weights.iter().enumerate() {
+                    let slope = system.scratch[2 * j + derivative][n];
+                    for i in 0..DIM {
+                        error[i] += step * e_j * slope[i];
+                    }
+                }
+            });
+        }
+
+        system.update_accelerations(&self.force_model);
+    }
+
+    /// Root mean square of the errors left by [`Self::try_step`], scaled by the tolerance
+    /// of each component.
+    fn error_norm(&self, system: &ParticleSystem<DIM>) -> f64 {
+        let errors = 2 * self.tableau.tableau.stages();
+        let mut sum = 0.0;
+        let mut count = 0;
+
+        for n in 0..system.len() {
+            for derivative in 0..2 {
+                let r = system.prev_derivatives[derivative][n];
+                let new_r = system.derivatives[derivative][n];
+                let error = system.scratch[errors + derivative][n];
+
+                for i in 0..DIM {
+                    let scale = self.absolute_tolerance
+                        + self.relative_tolerance * r[i].abs().max(new_r[i].abs());
+
+                    sum += (error[i] / scale).powi(2);
+                    count += 1;
+                }
+            }
+        }
+
+        if count == 0 {
+            return 0.0;
+        }
+
+        (sum / count as f64).sqrt()
+    }
+}
+
+impl<const DIM: usize, F: ForceModel<DIM>> IntegrationMethod<DIM> for AdaptiveRungeKutta<DIM, F> {
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>) {
+        if let Err(error) = self.try_advance_system(system) {
+            panic!("{error}");
+        }
+    }
+
+    fn try_advance_system(&self, system: &mut ParticleSystem<DIM>) -> Result<(), StepError> {
+        let exponent = -1.0 / f64::from(self.tableau.order + 1);
+        let mut remaining = self.delta_t;
+        let mut accepted = true;
+
+        system.save_prev_derivatives();
+        let mut interval_start = self.interval_start.borrow_mut();
+        interval_start.clone_from(&system.prev_derivatives);
+        let initial_step = self.step.get();
+
+        while remaining > self.delta_t * f64::EPSILON {
+            // NOTE: A rejected step is retried from the same previous derivatives
+            if accepted {
+                system.save_prev_derivatives();
+            }
+
+            let proposed = self.step.get();
+            let step = proposed.min(remaining);
+
+            if step <= self.delta_t * f64::EPSILON {
+                system.derivatives.clone_from(&interval_start);
+                system.prev_derivatives.clone_from(&interval_start);
+                self.step.set(initial_step);
+
+                return Err(StepError::StepSizeUnderflow {
+                    elapsed: self.delta_t - remaining,
+                    step,
+                });
+            }
+
+            self.try_step(system, step);
+            let error = self.error_norm(system);
+            accepted = error <= 1.0;
+
+            if accepted {
+                if let Some(observer) = self.step_observer.borrow_mut().as_mut() {
+                    observer(step);
+                }
+
+                remaining -= step;
+
+                let factor = if error == 0.0 {
+                    MAX_STEP_FACTOR
+                } else {
+                    (SAFETY_FACTOR * error.powf(exponent)).clamp(MIN_STEP_FACTOR, MAX_STEP_FACTOR)
+                };
+
+                let mut next_step = step * factor;
+
+                // NOTE: A step shortened to land on the end of the interval should not shrink the next one
+                if step < proposed {
+                    next_step = next_step.max(proposed);
+                }
+
+                self.step.set(next_step.min(self.max_step));
+            } else {
+                // NOTE: An error which is not finite, as from forces which blew up, says nothing
+                // of the step it needs, so the step shrinks as much as it may
+                let factor = if error.is_finite() {
+                    (SAFETY_FACTOR * error.powf(exponent)).clamp(MIN_STEP_FACTOR, SAFETY_FACTOR)
+                } else {
+                    MIN_STEP_FACTOR
+                };
+                self.step.set(step * factor);
+            }
+        }
+
+        system.prev_derivatives.clone_from(&interval_start);
+
+        Ok(())
+    }
+
+    /// The size of the next internal step.
+    fn state(&self) -> Vec<f64> {
+        vec![self.step.get()]
+    }
+
+    fn restore_state(&self, state: &[f64]) {
+        self.step.set(state[0]);
+    }
+}
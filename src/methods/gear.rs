@@ -0,0 +1,282 @@
+use std::fmt::Display;
+
+use crate::{forces::ForceModel, system::ParticleSystem};
+
+use super::{runge_kutta::runge_kutta_step, ButcherTableau, IntegrationMethod};
+
+const MIN_GEAR_ORDER: usize = 2;
+const MAX_GEAR_ORDER: usize = 5;
+
+/// Runge-Kutta steps taken by the starter between two samples of the acceleration.
+const STARTER_SUBSTEPS: usize = 4;
+
+/// Corrector coefficients for each order, for forces that only depend on the positions.
+const POSITION_DEPENDENT_COEFFICIENTS: [&[f64]; MAX_GEAR_ORDER + 1] = [
+    &[],
+    &[],
+    &[0.0, 1.0, 1.0],
+    &[1.0 / 6.0, 5.0 / 6.0, 1.0, 1.0 / 3.0],
+    &[19.0 / 120.0, 3.0 / 4.0, 1.0, 1.0 / 2.0, 1.0 / 12.0],
+    &[
+        3.0 / 20.0,
+        251.0 / 360.0,
+        1.0,
+        11.0 / 18.0,
+        1.0 / 6.0,
+        1.0 / 60.0,
+    ],
+];
+
+/// Corrector coefficients for each order, for forces that also depend on the velocities.
+const VELOCITY_DEPENDENT_COEFFICIENTS: [&[f64]; MAX_GEAR_ORDER + 1] = [
+    &[],
+    &[],
+    &[0.0, 1.0, 1.0],
+    &[1.0 / 6.0, 5.0 / 6.0, 1.0, 1.0 / 3.0],
+    &[19.0 / 90.0, 3.0 / 4.0, 1.0, 1.0 / 2.0, 1.0 / 12.0],
+    &[
+        3.0 / 16.0,
+        251.0 / 360.0,
+        1.0,
+        11.0 / 18.0,
+        1.0 / 6.0,
+        1.0 / 60.0,
+    ],
+];
+
+#[derive(Debug, PartialEq)]
+pub enum GearError {
+    UnsupportedOrder(usize),
+    ParticleCount {
+        expected: usize,
+        found: usize,
+    },
+    DerivativeCount {
+        particle_id: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for GearError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GearError::UnsupportedOrder(order) => write!(
+                f,
+                "Gear order {order} is not supported, it must be between {MIN_GEAR_ORDER} and {MAX_GEAR_ORDER}"
+            ),
+            GearError::ParticleCount { expected, found } => write!(
+                f,
+                "higher derivatives were given for {found} particles but the system has {expected}"
+            ),
+            GearError::DerivativeCount {
+                particle_id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "particle {particle_id} has {found} derivatives but Gear needs {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GearError {}
+
+pub struct GearPredictorCorrector<const DIM: usize, F> {
+    force_model: F,
+    coefficients: &'static [f64],
+    delta_t: f64,
+}
+
+impl<const DIM: usize, F: ForceModel<DIM>> GearPredictorCorrector<DIM, F> {
+    /// Creates a Gear predictor corrector of the given `order`, the highest derivative
+    /// of the position it keeps track of.
+    ///
+    /// Every particle of the system is extended with the derivatives above the acceleration
+    /// at its index in `higher_derivatives`, which holds one entry per particle, and must
+    /// then hold exactly `order + 1` derivatives.
+    pub fn new(
+        force_model: F,
+        force_depends_on_velocity: bool,
+        order: usize,
+        system: &mut ParticleSystem<DIM>,
+        higher_derivatives: Vec<Vec<[f64; DIM]>>,
+        delta_t: f64,
+    ) -> Result<Self, GearError> {
+        if !(MIN_GEAR_ORDER..=MAX_GEAR_ORDER).contains(&order) {
+            return Err(GearError::UnsupportedOrder(order));
+        }
+
+        if higher_derivatives.len() != system.len() {
+            return Err(GearError::ParticleCount {
+                expected: system.len(),
+                found: higher_derivatives.len(),
+            });
+        }
+
+        for (n, derivatives) in higher_derivatives.iter().enumerate() {
+            let found = system.derivative_count() + derivatives.len();
+            if found != order + 1 {
+                return Err(GearError::DerivativeCount {
+                    particle_id: system.id(n),
+                    expected: order + 1,
+                    found,
+                });
+            }
+        }
+
+        for k in 0..(order + 1).saturating_sub(system.derivative_count()) {
+            system.push_derivative(higher_derivatives.iter().map(|d| d[k]).collect());
+        }
+
+        let coefficients = if force_depends_on_velocity {
+            VELOCITY_DEPENDENT_COEFFICIENTS[order]
+        } else {
+            POSITION_DEPENDENT_COEFFICIENTS[order]
+        };
+
+        Ok(Self {
+            force_model,
+            coefficients,
+            delta_t,
+        })
+    }
+
+    /// Creates a Gear predictor corrector of the given `order`, estimating the derivatives
+    /// above the acceleration of every particle instead of requiring them.
+    ///
+    /// The system is integrated two steps of `delta_t` backwards and forwards in time with
+    /// a finely stepped Runge-Kutta method, and the derivatives of the acceleration are
+    /// obtained from central finite differences of the sampled accelerations.
+    pub fn bootstrap(
+        force_model: F,
+        force_depends_on_velocity: bool,
+        order: usize,
+        system: &mut ParticleSystem<DIM>,
+        delta_t: f64,
+    ) -> Result<Self, GearError> {
+        if !(MIN_GEAR_ORDER..=MAX_GEAR_ORDER).contains(&order) {
+            return Err(GearError::UnsupportedOrder(order));
+        }
+
+        let backward = sample_accelerations(&force_model, system, -delta_t);
+        let forward = sample_accelerations(&force_model, system, delta_t);
+
+        let mut higher_derivatives = Vec::with_capacity(system.len());
+        for (n, acceleration) in system.accelerations().iter().enumerate() {
+            let a = [
+                backward[1][n],
+                backward[0][n],
+                *acceleration,
+                forward[0][n],
+                forward[1][n],
+            ];
+
+            let mut derivatives = [[0.0; DIM]; 3];
+            for i in 0..DIM {
+                derivatives[0][i] =
+                    (a[0][i] - 8.0 * a[1][i] + 8.0 * a[3][i] - a[4][i]) / (12.0 * delta_t);
+                derivatives[1][i] = (-a[0][i] + 16.0 * a[1][i] - 30.0 * a[2][i] + 16.0 * a[3][i]
+                    - a[4][i])
+                    / (12.0 * delta_t.powi(2));
+                derivatives[2][i] =
+                    (-a[0][i] + 2.0 * a[1][i] - 2.0 * a[3][i] + a[4][i]) / (2.0 * delta_t.powi(3));
+            }
+
+            higher_derivatives.push(derivatives[..order - 2].to_vec());
+        }
+
+        Self::new(
+            force_model,
+            force_depends_on_velocity,
+            order,
+            system,
+            higher_derivatives,
+            delta_t,
+        )
+    }
+
+    #[must_use]
+    pub fn order(&self) -> usize {
+        self.coefficients.len() - 1
+    }
+}
+
+/// Returns the accelerations of the particles after one and two steps of `delta_t`.
+fn sample_accelerations<const DIM: usize, F: ForceModel<DIM>>(
+    force_model: &F,
+    system: &ParticleSystem<DIM>,
+    delta_t: f64,
+) -> [Vec<[f64; DIM]>; 2] {
+    let substep = delta_t / STARTER_SUBSTEPS as f64;
+    let mut current = system.clone();
+
+    let mut sample = || {
+        for _ in 0..STARTER_SUBSTEPS {
+            runge_kutta_step(
+                force_model,
+                &ButcherTableau::CLASSIC_RK4,
+                substep,
+                &mut current,
+            );
+        }
+
+        current.accelerations().to_vec()
+    };
+
+    [sample(), sample()]
+}
+
+impl<const DIM: usize, F: ForceModel<DIM>> IntegrationMethod<DIM>
+    for GearPredictorCorrector<DIM, F>
+{
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>) {
+        let order = self.order();
+
+        // NOTE: delta_t^n / n! for every n up to the order
+        let mut taylor_factors = [1.0; MAX_GEAR_ORDER + 1];
+        for n in 1..=order {
+            taylor_factors[n] = taylor_factors[n - 1] * self.delta_t / n as f64;
+        }
+
+        system.save_prev_derivatives();
+
+        // Predict
+        for k in 0..=order {
+            system.update_derivative(k, |n, derivative, system| {
+                let r = &system.prev_derivatives;
+
+                for j in k + 1..=order {
+                    for i in 0..DIM {
+                        derivative[i] += taylor_factors[j - k] * r[j][n][i];
+                    }
+                }
+            });
+        }
+
+        system.evaluate_forces(&self.force_model);
+
+        // Correct
+        system.resize_scratch(1);
+        system.update_scratch(0, |n, delta_acc, system| {
+            let new_acceleration = system.forces[n].map(|f| f / system.mass(n));
+            let predicted_acceleration = system.accelerations()[n];
+
+            for i in 0..DIM {
+                delta_acc[i] =
+                    (new_acceleration[i] - predicted_acceleration[i]) * taylor_factors[2];
+            }
+        });
+
+        for (k, alpha) in self.coefficients.iter().enumerate() {
+            system.update_derivative(k, |n, derivative, system| {
+                let delta_acc = system.scratch[0][n];
+
+                for i in 0..DIM {
+                    derivative[i] += alpha * delta_acc[i] / taylor_factors[k];
+                }
+            });
+        }
+    }
+}
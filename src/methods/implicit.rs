@@ -0,0 +1,532 @@
+use crate::{
+    forces::{ForceJacobian, ForceModel},
+    parallel,
+    particle::ParticleState,
+    system::ParticleSystem,
+};
+
+use super::{IntegrationMethod, StepError};
+
+const DEFAULT_NEWTON_TOLERANCE: f64 = 1e-12;
+const DEFAULT_NEWTON_ITERATIONS: usize = 20;
+
+/// Implicit one step schemes for the equations of motion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImplicitScheme {
+    /// First order and L-stable, it damps the stiffest modes away.
+    BackwardEuler,
+    /// Second order and symplectic, it evaluates the force halfway through the step.
+    Midpoint,
+    /// Second order Crank-Nicolson method, averaging the forces at both ends of the step.
+    Trapezoidal,
+}
+
+/// Weights of a scheme, which all solve for the new velocity `v1` from
+///
+/// ```text
+/// r_e = r0 + delta_t * (position_weights.0 * v0 + position_weights.1 * v1)
+/// v_e = (1 - velocity_weight) * v0 + velocity_weight * v1
+/// v1  = v0 + delta_t * ((1 - implicit_weight) * a0 + implicit_weight * a(r_e, v_e))
+/// r1  = r0 + delta_t * ((1 - drift_weight) * v0 + drift_weight * v1)
+/// ```
+struct SchemeWeights {
+    position_weights: (f64, f64),
+    velocity_weight: f64,
+    implicit_weight: f64,
+    drift_weight: f64,
+}
+
+impl ImplicitScheme {
+    fn weights(self) -> SchemeWeights {
+        match self {
+            ImplicitScheme::BackwardEuler => SchemeWeights {
+                position_weights: (0.0, 1.0),
+                velocity_weight: 1.0,
+                implicit_weight: 1.0,
+                drift_weight: 1.0,
+            },
+            ImplicitScheme::Midpoint => SchemeWeights {
+                position_weights: (0.25, 0.25),
+                velocity_weight: 0.5,
+                implicit_weight: 1.0,
+                drift_weight: 0.5,
+            },
+            ImplicitScheme::Trapezoidal => SchemeWeights {
+                position_weights: (0.5, 0.5),
+                velocity_weight: 1.0,
+                implicit_weight: 0.5,
+                drift_weight: 0.5,
+            },
+        }
+    }
+}
+
+/// Implicit integrator solving each step with Newton's method over the whole system.
+///
+/// The Newton matrix holds a block for every particle and one for every pair of
+/// particles coupled by the forces, so pair forces are as implicit as the forces on
+/// each particle alone. The Jacobians of the force model are used when it provides them,
+/// and are otherwise approximated with forward finite differences, which for the
+/// couplings takes two evaluations of the whole system per particle and dimension. The
+/// linear system of each iteration is solved with BiCGSTAB, preconditioned with the
+/// blocks of the particles.
+///
+/// # Panics
+///
+/// [`IntegrationMethod::advance_system`] panics when Newton's method does not converge
+/// within the iteration limit, where [`IntegrationMethod::try_advance_system`] returns a
+/// [`StepError`] instead, so that a step which is too large can be recovered from.
+pub struct Implicit<const DIM: usize, F> {
+    force_model: F,
+    scheme: ImplicitScheme,
+    delta_t: f64,
+    tolerance: f64,
+    max_iterations: usize,
+}
+
+impl<const DIM: usize, F: ForceModel<DIM>> Implicit<DIM, F> {
+    pub fn new(force_model: F, scheme: ImplicitScheme, delta_t: f64) -> Self {
+        Self {
+            force_model,
+            scheme,
+            delta_t,
+            tolerance: DEFAULT_NEWTON_TOLERANCE,
+            max_iterations: DEFAULT_NEWTON_ITERATIONS,
+        }
+    }
+
+    pub fn backward_euler(force_model: F, delta_t: f64) -> Self {
+        Self::new(force_model, ImplicitScheme::BackwardEuler, delta_t)
+    }
+
+    pub fn midpoint(force_model: F, delta_t: f64) -> Self {
+        Self::new(force_model, ImplicitScheme::Midpoint, delta_t)
+    }
+
+    pub fn trapezoidal(force_model: F, delta_t: f64) -> Self {
+        Self::new(force_model, ImplicitScheme::Trapezoidal, delta_t)
+    }
+
+    /// Sets the relative tolerance on the velocity update and the iteration limit of
+    /// the Newton solver. The linear systems are solved to the same tolerance.
+    #[must_use]
+    pub fn with_newton(mut self, tolerance: f64, max_iterations: usize) -> Self {
+        self.tolerance = tolerance;
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Derivatives of the acceleration with respect to the position and velocity.
+    fn acceleration_jacobian(
+        &self,
+        particle: &ParticleState<DIM>,
+        system: &ParticleSystem<DIM>,
+    ) -> ForceJacobian<DIM> {
+        if let Some(jacobian) = self.force_model.jacobian(particle, system) {
+            return per_unit_mass(jacobian, particle.mass);
+        }
+
+        let r = [particle.position, particle.velocity];
+        let acceleration = self.force_model.acceleration(particle, system);
+        let mut jacobian = ForceJacobian::zero();
+
+        for j in 0..DIM {
+            for (derivative, column) in [(0, &mut jacobian.position), (1, &mut jacobian.velocity)] {
+                let step = finite_difference_step(r[derivative][j]);
+
+                let mut perturbed = r;
+                perturbed[derivative][j] += step;
+
+                let new_p = ParticleState {
+                    position: perturbed[0],
+                    velocity: perturbed[1],
+                    ..*particle
+                };
+                let perturbed_acceleration = self.force_model.acceleration(&new_p, system);
+
+                for i in 0..DIM {
+                    column[i][j] = (perturbed_acceleration[i] - acceleration[i]) / step;
+                }
+            }
+        }
+
+        jacobian
+    }
+
+    /// Derivatives of the acceleration of every particle with respect to the position and
+    /// velocity of each other particle, from forward finite differences of the forces on
+    /// the whole system, given its accelerations.
+    fn finite_difference_couplings(
+        &self,
+        system: &ParticleSystem<DIM>,
+    ) -> Vec<Vec<(usize, ForceJacobian<DIM>)>> {
+        let mut couplings = vec![Vec::new(); system.len()];
+        let mut perturbed = system.clone();
+
+        for m in 0..system.len() {
+            let mut columns = vec![ForceJacobian::zero(); system.len()];
+
+            for derivative in 0..2 {
+                for j in 0..DIM {
+                    let value = system.derivatives[derivative][m][j];
+                    let step = finite_difference_step(value);
+
+                    perturbed.derivatives[derivative][m][j] = value + step;
+                    perturbed.evaluate_forces(&self.force_model);
+                    perturbed.derivatives[derivative][m][j] = value;
+
+                    for (n, column) in columns.iter_mut().enumerate() {
+                        let column = if derivative == 0 {
+                            &mut column.position
+                        } else {
+                            &mut column.velocity
+                        };
+
+                        for (i, row) in column.iter_mut().enumerate() {
+                            let acceleration = perturbed.forces[n][i] / system.mass(n);
+                            row[j] = (acceleration - system.derivatives[2][n][i]) / step;
+                        }
+                    }
+                }
+            }
+
+            for (n, column) in columns.into_iter().enumerate() {
+                if n != m && column != ForceJacobian::zero() {
+                    couplings[n].push((m, column));
+                }
+            }
+        }
+
+        couplings
+    }
+
+    /// Derivative of the residual of every particle with respect to the velocities at the
+    /// end of the step, with the system at the evaluation state and its accelerations.
+    fn newton_matrix(&self, system: &ParticleSystem<DIM>) -> NewtonMatrix<DIM> {
+        let weights = self.scheme.weights();
+        let delta_t = self.delta_t;
+
+        let block = |jacobian: &ForceJacobian<DIM>| {
+            let mut block = [[0.0; DIM]; DIM];
+            for (i, row) in block.iter_mut().enumerate() {
+                for (j, entry) in row.iter_mut().enumerate() {
+                    *entry = -delta_t
+                        * weights.implicit_weight
+                        * (delta_t * weights.position_weights.1 * jacobian.position[i][j]
+                            + weights.velocity_weight * jacobian.velocity[i][j]);
+                }
+            }
+
+            block
+        };
+
+        let rows = parallel::map(&system.derivatives[0], |n, _| {
+            let particle = system.state(n);
+
+            let mut diagonal = block(&self.acceleration_jacobian(&particle, system));
+            for (i, row) in diagonal.iter_mut().enumerate() {
+                row[i] += 1.0;
+            }
+
+            let couplings =
+                self.force_model
+                    .coupling_jacobians(&particle, system)
+                    .map(|couplings| {
+                        couplings
+                            .iter()
+                            .map(|(m, jacobian)| (*m, per_unit_mass(*jacobian, particle.mass)))
+                            .collect::<Vec<_>>()
+                    });
+
+            (diagonal, couplings)
+        });
+
+        let (diagonal, couplings): (Vec<_>, Vec<_>) = rows.into_iter().unzip();
+        let couplings = match couplings.into_iter().collect::<Option<Vec<_>>>() {
+            Some(couplings) => couplings,
+            None => self.finite_difference_couplings(system),
+        };
+
+        NewtonMatrix {
+            diagonal,
+            couplings: couplings
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|(m, jacobian)| (*m, block(jacobian)))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<const DIM: usize, F: ForceModel<DIM>> IntegrationMethod<DIM> for Implicit<DIM, F> {
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>) {
+        if let Err(error) = self.try_advance_system(system) {
+            panic!("{error}");
+        }
+    }
+
+    fn try_advance_system(&self, system: &mut ParticleSystem<DIM>) -> Result<(), StepError> {
+        let delta_t = self.delta_t;
+        let weights = self.scheme.weights();
+        system.save_prev_derivatives();
+
+        // NOTE: Start from an explicit Euler step of the velocities
+        system.resize_scratch(2);
+        system.update_scratch(0, |n, velocity, system| {
+            let r = &system.prev_derivatives;
+            for i in 0..DIM {
+                velocity[i] = r[1][n][i] + delta_t * r[2][n][i];
+            }
+        });
+
+        let mut converged = false;
+        for _ in 0..self.max_iterations {
+            self.move_to_evaluation(system);
+            system.update_accelerations(&self.force_model);
+
+            system.update_scratch(1, |n, residual, system| {
+                let r = &system.prev_derivatives;
+                let velocity = system.scratch[0][n];
+                let acceleration = system.derivatives[2][n];
+
+                for i in 0..DIM {
+                    residual[i] = velocity[i]
+                        - r[1][n][i]
+                        - delta_t
+                            * ((1.0 - weights.implicit_weight) * r[2][n][i]
+                                + weights.implicit_weight * acceleration[i]);
+                }
+            });
+
+            let correction = self
+                .newton_matrix(system)
+                .solve(&system.scratch[1], self.tolerance);
+
+            converged = true;
+            for (velocity, correction) in system.scratch[0].iter_mut().zip(&correction) {
+                for i in 0..DIM {
+                    velocity[i] -= correction[i];
+                    converged &= correction[i].abs() <= self.tolerance * (1.0 + velocity[i].abs());
+                }
+            }
+
+            if converged {
+                break;
+            }
+        }
+
+        if !converged {
+            for (derivative, prev) in system.derivatives.iter_mut().zip(&system.prev_derivatives) {
+                derivative.copy_from_slice(prev);
+            }
+
+            return Err(StepError::NotConverged {
+                iterations: self.max_iterations,
+            });
+        }
+
+        system.update_derivative(0, |n, position, system| {
+            let r = &system.prev_derivatives;
+            let velocity = system.scratch[0][n];
+
+            for i in 0..DIM {
+                position[i] = r[0][n][i]
+                    + delta_t
+                        * ((1.0 - weights.drift_weight) * r[1][n][i]
+                            + weights.drift_weight * velocity[i]);
+            }
+        });
+        system.derivatives[1].copy_from_slice(&system.scratch[0]);
+
+        system.update_accelerations(&self.force_model);
+
+        Ok(())
+    }
+}
+
+impl<const DIM: usize, F: ForceModel<DIM>> Implicit<DIM, F> {
+    /// Moves every particle to where the forces are evaluated, given the estimates of
+    /// the velocities at the end of the step in the first scratch array.
+    fn move_to_evaluation(&self, system: &mut ParticleSystem<DIM>) {
+        let delta_t = self.delta_t;
+        let weights = self.scheme.weights();
+
+        system.update_derivative(0, |n, position, system| {
+            let r = &system.prev_derivatives;
+            let velocity = system.scratch[0][n];
+
+            for i in 0..DIM {
+                position[i] = r[0][n][i]
+                    + delta_t
+                        * (weights.position_weights.0 * r[1][n][i]
+                            + weights.position_weights.1 * velocity[i]);
+            }
+        });
+        system.update_derivative(1, |n, evaluation_velocity, system| {
+            let r = &system.prev_derivatives;
+            let velocity = system.scratch[0][n];
+
+            for i in 0..DIM {
+                evaluation_velocity[i] =
+                    r[1][n][i] + weights.velocity_weight * (velocity[i] - r[1][n][i]);
+            }
+        });
+    }
+}
+
+/// Sparse matrix of `DIM` by `DIM` blocks, with a block on the diagonal for every
+/// particle and the blocks coupling it to other particles in its row.
+struct NewtonMatrix<const DIM: usize> {
+    diagonal: Vec<[[f64; DIM]; DIM]>,
+    couplings: Vec<Vec<(usize, [[f64; DIM]; DIM])>>,
+}
+
+impl<const DIM: usize> NewtonMatrix<DIM> {
+    fn multiply(&self, x: &[[f64; DIM]]) -> Vec<[f64; DIM]> {
+        parallel::map(&self.diagonal, |n, diagonal| {
+            let mut product = multiply_block(diagonal, &x[n]);
+
+            for (m, block) in &self.couplings[n] {
+                let coupling = multiply_block(block, &x[*m]);
+                for i in 0..DIM {
+                    product[i] += coupling[i];
+                }
+            }
+
+            product
+        })
+    }
+
+    /// Solves the diagonal blocks alone, leaving the rows with a singular block as they are.
+    fn precondition(&self, x: &[[f64; DIM]]) -> Vec<[f64; DIM]> {
+        parallel::map(&self.diagonal, |n, diagonal| {
+            solve(*diagonal, x[n]).unwrap_or(x[n])
+        })
+    }
+
+    /// Solves `self * x = rhs` with the preconditioned BiCGSTAB method until the residual
+    /// falls below `tolerance` relative to `rhs`, or returns the last iterate after as
+    /// many iterations as there are unknowns.
+    fn solve(&self, rhs: &[[f64; DIM]], tolerance: f64) -> Vec<[f64; DIM]> {
+        let mut x = vec![[0.0; DIM]; rhs.len()];
+        let target = tolerance * dot(rhs, rhs).sqrt();
+
+        let mut residual = rhs.to_vec();
+        let shadow = rhs.to_vec();
+        let mut direction = vec![[0.0; DIM]; rhs.len()];
+        let mut product = vec![[0.0; DIM]; rhs.len()];
+        let (mut rho, mut alpha, mut omega) = (1.0, 1.0, 1.0);
+
+        for _ in 0..rhs.len() * DIM {
+            if dot(&residual, &residual).sqrt() <= target {
+                break;
+            }
+
+            let new_rho = dot(&shadow, &residual);
+            if new_rho == 0.0 || omega == 0.0 {
+                break;
+            }
+
+            let beta = new_rho / rho * alpha / omega;
+            rho = new_rho;
+            for ((d, r), p) in direction.iter_mut().zip(&residual).zip(&product) {
+                for i in 0..DIM {
+                    d[i] = r[i] + beta * (d[i] - omega * p[i]);
+                }
+            }
+
+            let preconditioned_direction = self.precondition(&direction);
+            product = self.multiply(&preconditioned_direction);
+            alpha = rho / dot(&shadow, &product);
+            axpy(&mut x, alpha, &preconditioned_direction);
+            axpy(&mut residual, -alpha, &product);
+
+            if dot(&residual, &residual).sqrt() <= target {
+                break;
+            }
+
+            let preconditioned_residual = self.precondition(&residual);
+            let residual_product = self.multiply(&preconditioned_residual);
+            omega = dot(&residual_product, &residual) / dot(&residual_product, &residual_product);
+            axpy(&mut x, omega, &preconditioned_residual);
+            axpy(&mut residual, -omega, &residual_product);
+        }
+
+        x
+    }
+}
+
+fn multiply_block<const DIM: usize>(block: &[[f64; DIM]; DIM], x: &[f64; DIM]) -> [f64; DIM] {
+    block.map(|row| (0..DIM).map(|j| row[j] * x[j]).sum())
+}
+
+/// Sum of the products of the components of `a` and `b`, in the order of the particles.
+fn dot<const DIM: usize>(a: &[[f64; DIM]], b: &[[f64; DIM]]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (0..DIM).map(|i| a[i] * b[i]).sum::<f64>())
+        .sum()
+}
+
+/// Adds `factor` times `x` to `y`.
+fn axpy<const DIM: usize>(y: &mut [[f64; DIM]], factor: f64, x: &[[f64; DIM]]) {
+    for (y, x) in y.iter_mut().zip(x) {
+        for i in 0..DIM {
+            y[i] += factor * x[i];
+        }
+    }
+}
+
+/// Divides the derivatives of a force by `mass` into those of the acceleration.
+fn per_unit_mass<const DIM: usize>(jacobian: ForceJacobian<DIM>, mass: f64) -> ForceJacobian<DIM> {
+    ForceJacobian {
+        position: jacobian.position.map(|row| row.map(|x| x / mass)),
+        velocity: jacobian.velocity.map(|row| row.map(|x| x / mass)),
+    }
+}
+
+/// Step of the forward finite differences around `value`.
+fn finite_difference_step(value: f64) -> f64 {
+    f64::EPSILON.sqrt() * value.abs().max(1.0)
+}
+
+/// Solves the linear system `matrix * x = rhs` by Gaussian elimination with partial
+/// pivoting, returning `None` when the matrix is singular.
+fn solve<const DIM: usize>(
+    mut matrix: [[f64; DIM]; DIM],
+    mut rhs: [f64; DIM],
+) -> Option<[f64; DIM]> {
+    for column in 0..DIM {
+        let pivot = (column..DIM)
+            .max_by(|&a, &b| matrix[a][column].abs().total_cmp(&matrix[b][column].abs()))?;
+        if matrix[pivot][column] == 0.0 {
+            return None;
+        }
+
+        matrix.swap(column, pivot);
+        rhs.swap(column, pivot);
+
+        for row in column + 1..DIM {
+            let factor = matrix[row][column] / matrix[column][column];
+            let pivot_row = matrix[column];
+            for (k, value) in matrix[row].iter_mut().enumerate().skip(column) {
+                *value -= factor * pivot_row[k];
+            }
+            rhs[row] -= factor * rhs[column];
+        }
+    }
+
+    let mut solution = [0.0; DIM];
+    for row in (0..DIM).rev() {
+        let mut value = rhs[row];
+        for k in row + 1..DIM {
+            value -= matrix[row][k] * solution[k];
+        }
+        solution[row] = value / matrix[row][row];
+    }
+
+    Some(solution)
+}
@@ -0,0 +1,159 @@
+use crate::{forces::ForceModel, system::ParticleSystem};
+
+use super::IntegrationMethod;
+
+/// Coefficients of an explicit Runge-Kutta method.
+///
+/// `a` is the strictly lower triangular matrix of the method, stored row by row,
+/// `b` the weights of each stage and `c` the nodes where each stage is evaluated.
+#[derive(Debug, Clone, Copy)]
+pub struct ButcherTableau {
+    pub a: &'static [&'static [f64]],
+    pub b: &'static [f64],
+    pub c: &'static [f64],
+}
+
+impl ButcherTableau {
+    pub const MIDPOINT: Self = Self {
+        a: &[&[], &[1.0 / 2.0]],
+        b: &[0.0, 1.0],
+        c: &[0.0, 1.0 / 2.0],
+    };
+
+    pub const HEUN: Self = Self {
+        a: &[&[], &[1.0]],
+        b: &[1.0 / 2.0, 1.0 / 2.0],
+        c: &[0.0, 1.0],
+    };
+
+    pub const CLASSIC_RK4: Self = Self {
+        a: &[&[], &[1.0 / 2.0], &[0.0, 1.0 / 2.0], &[0.0, 0.0, 1.0]],
+        b: &[1.0 / 6.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 6.0],
+        c: &[0.0, 1.0 / 2.0, 1.0 / 2.0, 1.0],
+    };
+
+    pub const THREE_EIGHTHS_RULE: Self = Self {
+        a: &[&[], &[1.0 / 3.0], &[-1.0 / 3.0, 1.0], &[1.0, -1.0, 1.0]],
+        b: &[1.0 / 8.0, 3.0 / 8.0, 3.0 / 8.0, 1.0 / 8.0],
+        c: &[0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0],
+    };
+
+    #[must_use]
+    pub fn stages(&self) -> usize {
+        self.b.len()
+    }
+}
+
+/// Explicit Runge-Kutta method driven by a [`ButcherTableau`].
+///
+/// The second order equation of motion is integrated as the first order system
+/// `r' = v`, `v' = a(r, v)`.
+pub struct ExplicitRungeKutta<const DIM: usize, F> {
+    force_model: F,
+    tableau: ButcherTableau,
+    delta_t: f64,
+}
+
+impl<const DIM: usize, F: ForceModel<DIM>> ExplicitRungeKutta<DIM, F> {
+    pub fn new(force_model: F, tableau: ButcherTableau, delta_t: f64) -> Self {
+        Self {
+            force_model,
+            tableau,
+            delta_t,
+        }
+    }
+
+    pub fn midpoint(force_model: F, delta_t: f64) -> Self {
+        Self::new(force_model, ButcherTableau::MIDPOINT, delta_t)
+    }
+
+    pub fn heun(force_model: F, delta_t: f64) -> Self {
+        Self::new(force_model, ButcherTableau::HEUN, delta_t)
+    }
+
+    pub fn classic(force_model: F, delta_t: f64) -> Self {
+        Self::new(force_model, ButcherTableau::CLASSIC_RK4, delta_t)
+    }
+
+    pub fn three_eighths_rule(force_model: F, delta_t: f64) -> Self {
+        Self::new(force_model, ButcherTableau::THREE_EIGHTHS_RULE, delta_t)
+    }
+}
+
+impl<const DIM: usize, F: ForceModel<DIM>> IntegrationMethod<DIM> for ExplicitRungeKutta<DIM, F> {
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>) {
+        runge_kutta_step(&self.force_model, &self.tableau, self.delta_t, system);
+    }
+}
+
+/// Evaluates the stages of `tableau` for a step of `delta_t` from the previous derivatives
+/// of the system, leaving the slopes of the positions and the velocities on stage `j` in
+/// the scratch arrays `2 * j` and `2 * j + 1`.
+pub(super) fn runge_kutta_stages<const DIM: usize, F: ForceModel<DIM>>(
+    force_model: &F,
+    tableau: &ButcherTableau,
+    delta_t: f64,
+    system: &mut ParticleSystem<DIM>,
+) {
+    system.resize_scratch(2 * tableau.stages());
+
+    // NOTE: The first stage is evaluated at the start of the step, where the acceleration is known
+    system.scratch[0].copy_from_slice(&system.prev_derivatives[1]);
+    system.scratch[1].copy_from_slice(&system.prev_derivatives[2]);
+
+    for (stage, a) in tableau.a.iter().enumerate().skip(1) {
+        for derivative in 0..2 {
+            system.update_derivative(derivative, |n, stage_value, system| {
+                *stage_value = system.prev_derivatives[derivative][n];
+
+                for (j, a_j) in a.iter().enumerate() {
+                    let slope = system.scratch[2 * j + derivative][n];
+                    for i in 0..DIM {
+                        stage_value[i] += delta_t * a_j * slope[i];
+                    }
+                }
+            });
+        }
+
+        system.update_accelerations(force_model);
+
+        system.scratch[2 * stage].copy_from_slice(&system.derivatives[1]);
+        system.scratch[2 * stage + 1].copy_from_slice(&system.derivatives[2]);
+    }
+}
+
+/// Sets the positions and velocities of the system to those of the previous derivatives
+/// advanced `delta_t` with the stage slopes left by [`runge_kutta_stages`], combined with
+/// the `weights`.
+pub(super) fn combine_stages<const DIM: usize>(
+    weights: &[f64],
+    delta_t: f64,
+    system: &mut ParticleSystem<DIM>,
+) {
+    for derivative in 0..2 {
+        system.update_derivative(derivative, |n, new_value, system| {
+            *new_value = system.prev_derivatives[derivative][n];
+
+            for (j, b_j) in weights.iter().enumerate() {
+                let slope = system.scratch[2 * j + derivative][n];
+                for i in 0..DIM {
+                    new_value[i] += delta_t * b_j * slope[i];
+                }
+            }
+        });
+    }
+}
+
+pub(super) fn runge_kutta_step<const DIM: usize, F: ForceModel<DIM>>(
+    force_model: &F,
+    tableau: &ButcherTableau,
+    delta_t: f64,
+    system: &mut ParticleSystem<DIM>,
+) {
+    system.save_prev_derivatives();
+
+    runge_kutta_stages(force_model, tableau, delta_t, system);
+    combine_stages(tableau.b, delta_t, system);
+
+    system.update_accelerations(force_model);
+}
@@ -0,0 +1,162 @@
+use crate::{forces::ForceModel, system::ParticleSystem};
+
+use super::IntegrationMethod;
+
+const FOREST_RUTH_THETA: f64 = 1.351_207_191_959_657_8;
+
+const PEFRL_XI: f64 = 0.178_617_895_844_809_1;
+const PEFRL_LAMBDA: f64 = -0.212_341_831_062_605_4;
+const PEFRL_CHI: f64 = -0.066_264_582_669_818_5;
+
+/// Symplectic method composed of alternating drifts and kicks.
+///
+/// A step starts and ends with a drift: the position advances `drifts[0] * delta_t`
+/// with the current velocity, then the velocity advances `kicks[0] * delta_t` with the
+/// acceleration at the new position, and so on.
+///
+/// The forces must depend on the positions alone, as required for the composition to be
+/// symplectic. A kick evaluates forces which depend on the velocities at the velocity
+/// before it, an error of first order which the composition does not cancel, so on a
+/// damped oscillator every composition converges at first order.
+/// [`Integration::method`](crate::Integration::method) refuses to build them for such
+/// forces.
+pub struct Symplectic<const DIM: usize, F> {
+    force_model: F,
+    drifts: Vec<f64>,
+    kicks: Vec<f64>,
+    delta_t: f64,
+}
+
+impl<const DIM: usize, F: ForceModel<DIM>> Symplectic<DIM, F> {
+    pub fn new(force_model: F, drifts: Vec<f64>, kicks: Vec<f64>, delta_t: f64) -> Self {
+        assert_eq!(
+            drifts.len(),
+            kicks.len() + 1,
+            "a symplectic composition needs one more drift than kicks"
+        );
+
+        Self {
+            force_model,
+            drifts,
+            kicks,
+            delta_t,
+        }
+    }
+
+    /// Fourth order method of Forest and Ruth.
+    pub fn forest_ruth(force_model: F, delta_t: f64) -> Self {
+        let theta = FOREST_RUTH_THETA;
+
+        Self::new(
+            force_model,
+            vec![
+                theta / 2.0,
+                (1.0 - theta) / 2.0,
+                (1.0 - theta) / 2.0,
+                theta / 2.0,
+            ],
+            vec![theta, 1.0 - 2.0 * theta, theta],
+            delta_t,
+        )
+    }
+
+    /// Position extended Forest-Ruth like method of Omelyan, Mryglod and Folk,
+    /// a fourth order method with a much smaller error constant than Forest-Ruth.
+    pub fn pefrl(force_model: F, delta_t: f64) -> Self {
+        Self::new(
+            force_model,
+            vec![
+                PEFRL_XI,
+                PEFRL_CHI,
+                1.0 - 2.0 * (PEFRL_CHI + PEFRL_XI),
+                PEFRL_CHI,
+                PEFRL_XI,
+            ],
+            vec![
+                (1.0 - 2.0 * PEFRL_LAMBDA) / 2.0,
+                PEFRL_LAMBDA,
+                PEFRL_LAMBDA,
+                (1.0 - 2.0 * PEFRL_LAMBDA) / 2.0,
+            ],
+            delta_t,
+        )
+    }
+
+    /// Yoshida's triple jump composition of the leap-frog method, for any even `order`.
+    ///
+    /// Each jump from order `2k` to `2k + 2` composes three steps of the previous method,
+    /// so the number of force evaluations grows as `3^(order / 2 - 1)`.
+    pub fn yoshida(force_model: F, order: u32, delta_t: f64) -> Self {
+        assert!(
+            order >= 2 && order.is_multiple_of(2),
+            "the order of a Yoshida composition must be even"
+        );
+
+        let mut drifts = vec![0.5, 0.5];
+        let mut kicks = vec![1.0];
+
+        for k in 1..order / 2 {
+            let root = 2f64.powf(1.0 / f64::from(2 * k + 1));
+            let w_1 = 1.0 / (2.0 - root);
+            let w_0 = 1.0 - 2.0 * w_1;
+
+            (drifts, kicks) = compose(&drifts, &kicks, &[w_1, w_0, w_1]);
+        }
+
+        Self::new(force_model, drifts, kicks, delta_t)
+    }
+}
+
+/// Concatenates the steps of a composition scaled by each of the `weights`,
+/// merging the last drift of each step with the first drift of the next one.
+fn compose(drifts: &[f64], kicks: &[f64], weights: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let mut composed_drifts = vec![0.0];
+    let mut composed_kicks = Vec::new();
+
+    for weight in weights {
+        *composed_drifts.last_mut().unwrap() += weight * drifts[0];
+        composed_drifts.extend(drifts[1..].iter().map(|d| weight * d));
+        composed_kicks.extend(kicks.iter().map(|k| weight * k));
+    }
+
+    (composed_drifts, composed_kicks)
+}
+
+impl<const DIM: usize, F: ForceModel<DIM>> Symplectic<DIM, F> {
+    fn drift(&self, system: &mut ParticleSystem<DIM>, drift: f64) {
+        let delta_t = self.delta_t;
+
+        system.update_derivative(0, |n, position, system| {
+            let velocity = system.velocities()[n];
+
+            for i in 0..DIM {
+                position[i] += drift * delta_t * velocity[i];
+            }
+        });
+    }
+}
+
+impl<const DIM: usize, F: ForceModel<DIM>> IntegrationMethod<DIM> for Symplectic<DIM, F> {
+    fn advance_system(&self, system: &mut ParticleSystem<DIM>) {
+        let delta_t = self.delta_t;
+        system.save_prev_derivatives();
+
+        for (drift, kick) in self.drifts.iter().zip(&self.kicks) {
+            self.drift(system, *drift);
+
+            system.update_accelerations(&self.force_model);
+            system.update_derivative(1, |n, velocity, system| {
+                let acceleration = system.accelerations()[n];
+
+                for i in 0..DIM {
+                    velocity[i] += kick * delta_t * acceleration[i];
+                }
+            });
+        }
+
+        let last_drift = self.drifts[self.kicks.len()];
+        self.drift(system, last_drift);
+
+        system.update_accelerations(&self.force_model);
+    }
+}
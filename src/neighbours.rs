@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+/// Particles binned in a grid of cubic cells with a side of at least the interaction
+/// cutoff, so every pair of particles closer than the cutoff lies in the same cell or in
+/// adjacent ones.
+///
+/// Only the occupied cells are stored, so the particles may spread over any region.
+#[derive(Debug, Clone)]
+pub struct CellList<const DIM: usize> {
+    cell_size: f64,
+    cells: HashMap<[i64; DIM], Vec<usize>>,
+    particle_cells: Vec<[i64; DIM]>,
+}
+
+impl<const DIM: usize> CellList<DIM> {
+    /// Bins the particles at `positions`, referring to each one by its index.
+    #[must_use]
+    pub fn new(cutoff: f64, positions: &[[f64; DIM]]) -> Self {
+        assert!(cutoff > 0.0, "the cutoff of a cell list must be positive");
+
+        let mut cells: HashMap<[i64; DIM], Vec<usize>> = HashMap::new();
+        let mut particle_cells = Vec::with_capacity(positions.len());
+
+        for (n, position) in positions.iter().enumerate() {
+            let cell = cell_of(position, cutoff);
+
+            cells.entry(cell).or_default().push(n);
+            particle_cells.push(cell);
+        }
+
+        Self {
+            cell_size: cutoff,
+            cells,
+            particle_cells,
+        }
+    }
+
+    /// Indices of the particles in the cell of `position` and in its adjacent cells,
+    /// which include every particle closer than the cutoff.
+    pub fn neighbours(&self, position: &[f64; DIM]) -> impl Iterator<Item = usize> + '_ {
+        let cell = cell_of(position, self.cell_size);
+
+        adjacent_cells(cell)
+            .filter_map(|adjacent| self.cells.get(&adjacent))
+            .flatten()
+            .copied()
+    }
+
+    /// Calls `f` with the indices `n < m` of every pair of particles in the same or in
+    /// adjacent cells, once per pair.
+    ///
+    /// Pairs are visited in a deterministic order, by increasing `n` and then by cell.
+    pub fn for_each_pair(&self, mut f: impl FnMut(usize, usize)) {
+        for (n, cell) in self.particle_cells.iter().enumerate() {
+            for adjacent in adjacent_cells(*cell) {
+                let Some(others) = self.cells.get(&adjacent) else {
+                    continue;
+                };
+
+                for &m in others.iter().filter(|&&m| m > n) {
+                    f(n, m);
+                }
+            }
+        }
+    }
+}
+
+fn cell_of<const DIM: usize>(position: &[f64; DIM], cell_size: f64) -> [i64; DIM] {
+    position.map(|x| (x / cell_size).floor() as i64)
+}
+
+/// The `3^DIM` cells around `cell`, including itself, in a fixed order.
+fn adjacent_cells<const DIM: usize>(cell: [i64; DIM]) -> impl Iterator<Item = [i64; DIM]> {
+    (0..3_usize.pow(DIM as u32)).map(move |mut offsets| {
+        let mut adjacent = cell;
+
+        for coordinate in &mut adjacent {
+            *coordinate += (offsets % 3) as i64 - 1;
+            offsets /= 3;
+        }
+
+        adjacent
+    })
+}
@@ -0,0 +1,71 @@
+//! Loops over particles which run in parallel with the `parallel` feature, and
+//! sequentially otherwise.
+//!
+//! Every helper keeps the order of its results and each item is computed independently,
+//! so both builds produce bitwise identical results. Slices shorter than
+//! `MIN_PARALLEL_LEN` are looped over sequentially in either build, as splitting them
+//! between threads costs more than it saves.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Bound required from closures shared between threads, which is only `Sync` with the
+/// `parallel` feature.
+#[cfg(feature = "parallel")]
+pub(crate) trait MaybeSync: Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Sync + ?Sized> MaybeSync for T {}
+
+/// Bound required from closures shared between threads, which is only `Sync` with the
+/// `parallel` feature.
+#[cfg(not(feature = "parallel"))]
+pub(crate) trait MaybeSync {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> MaybeSync for T {}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) use {sequential_for_each_mut as for_each_mut, sequential_map as map};
+
+/// Fewest items which are split between threads.
+#[cfg(feature = "parallel")]
+const MIN_PARALLEL_LEN: usize = 512;
+
+#[cfg(feature = "parallel")]
+pub(crate) fn map<T: Sync, U: Send>(items: &[T], f: impl Fn(usize, &T) -> U + Sync) -> Vec<U> {
+    if items.len() < MIN_PARALLEL_LEN {
+        return sequential_map(items, f);
+    }
+
+    items
+        .par_iter()
+        .enumerate()
+        .map(|(n, item)| f(n, item))
+        .collect()
+}
+
+#[cfg(feature = "parallel")]
+pub(crate) fn for_each_mut<T: Send>(items: &mut [T], f: impl Fn(usize, &mut T) + Sync) {
+    if items.len() < MIN_PARALLEL_LEN {
+        return sequential_for_each_mut(items, f);
+    }
+
+    items
+        .par_iter_mut()
+        .enumerate()
+        .for_each(|(n, item)| f(n, item));
+}
+
+pub(crate) fn sequential_map<T, U>(items: &[T], f: impl Fn(usize, &T) -> U) -> Vec<U> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(n, item)| f(n, item))
+        .collect()
+}
+
+pub(crate) fn sequential_for_each_mut<T>(items: &mut [T], f: impl Fn(usize, &mut T)) {
+    items
+        .iter_mut()
+        .enumerate()
+        .for_each(|(n, item)| f(n, item));
+}
@@ -1,9 +1,25 @@
-#[derive(Debug, PartialEq)]
+/// Kinematic state and properties of a particle, as seen by the force models.
+///
+/// It may hold a trial state of a particle of a
+/// [`ParticleSystem`](crate::system::ParticleSystem), which it is matched with by its id.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleState<const DIM: usize> {
+    pub id: usize,
+    pub position: [f64; DIM],
+    pub velocity: [f64; DIM],
+    pub radius: f64,
+    pub mass: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Particle<const DIM: usize> {
     id: usize,
 
     derivatives: Vec<[f64; DIM]>,
     prev_derivatives: Vec<[f64; DIM]>,
+    /// Rotation about the three axes of space, the third perpendicular to the plane of the
+    /// first two, whatever the dimension the particle moves in
+    angular_velocity: [f64; 3],
 
     radius: f64,
     mass: f64,
@@ -23,11 +39,20 @@ impl<const DIM: usize> Particle<DIM> {
             id,
             derivatives: vec![r, v, a],
             prev_derivatives: vec![r, v, a],
+            angular_velocity: [0.0; 3],
             radius,
             mass,
         }
     }
 
+    /// Sets the angular velocity of the particle, which is zero unless set and only changes
+    /// under a [`Spin`](crate::spin::Spin) observer.
+    #[must_use]
+    pub fn with_angular_velocity(mut self, angular_velocity: [f64; 3]) -> Self {
+        self.angular_velocity = angular_velocity;
+        self
+    }
+
     #[must_use]
     pub fn id(&self) -> usize {
         self.id
@@ -48,6 +73,22 @@ impl<const DIM: usize> Particle<DIM> {
         &self.derivatives
     }
 
+    #[must_use]
+    pub fn angular_velocity(&self) -> [f64; 3] {
+        self.angular_velocity
+    }
+
+    #[must_use]
+    pub fn state(&self) -> ParticleState<DIM> {
+        ParticleState {
+            id: self.id,
+            position: self.derivatives[0],
+            velocity: self.derivatives[1],
+            radius: self.radius,
+            mass: self.mass,
+        }
+    }
+
     #[must_use]
     pub fn get_distance(&self, other: &Self) -> f64 {
         let mut distance = 0.0;
@@ -71,11 +112,15 @@ impl<const DIM: usize> Particle<DIM> {
         std::mem::replace(&mut self.derivatives, derivatives)
     }
 
-    pub(crate) fn cloned_derivatives(&self) -> Vec<[f64; DIM]> {
-        self.derivatives.clone()
+    pub(crate) fn derivatives_mut(&mut self) -> &mut Vec<[f64; DIM]> {
+        &mut self.derivatives
+    }
+
+    pub(crate) fn prev_derivatives_mut(&mut self) -> &mut Vec<[f64; DIM]> {
+        &mut self.prev_derivatives
     }
 
-    pub(crate) fn add_derivative(&mut self, derivative: [f64; DIM]) {
-        self.derivatives.push(derivative);
+    pub(crate) fn set_angular_velocity(&mut self, angular_velocity: [f64; 3]) {
+        self.angular_velocity = angular_velocity;
     }
 }
@@ -0,0 +1,307 @@
+//! Driver of the integration loop, which scenarios extend with observers instead of
+//! writing the loop themselves.
+
+use std::ops::ControlFlow;
+
+use crate::{
+    checkpoint::{Checkpoint, CheckpointError, ParticleSnapshot},
+    events::{Event, EventDetector},
+    methods::{IntegrationMethod, StepError},
+    particle::ParticleState,
+    system::ParticleSystem,
+    Integration,
+};
+
+/// Behaviour plugged into a [`Simulation`], called after every step and at every output.
+///
+/// Returning [`ControlFlow::Break`] from either method stops the simulation, handing the
+/// value it holds to the caller, which may for instance be the reason it was stopped or an
+/// error. Closures are turned into observers with [`Simulation::on_step`] and
+/// [`Simulation::on_output`].
+pub trait Observer<const DIM: usize, B = ()> {
+    /// Called after every step with the time the system has reached. Observers may change
+    /// the system here, for instance removing particles from it.
+    fn after_step(&mut self, _time: f64, _system: &mut ParticleSystem<DIM>) -> ControlFlow<B> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called at the end of every output interval, and on the state the simulation stops
+    /// at when an observer stops it in between.
+    fn at_output(&mut self, _time: f64, _system: &ParticleSystem<DIM>) -> ControlFlow<B> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Observer calling a closure after every step.
+pub struct OnStep<F>(F);
+
+impl<const DIM: usize, B, F> Observer<DIM, B> for OnStep<F>
+where
+    F: FnMut(f64, &mut ParticleSystem<DIM>) -> ControlFlow<B>,
+{
+    fn after_step(&mut self, time: f64, system: &mut ParticleSystem<DIM>) -> ControlFlow<B> {
+        (self.0)(time, system)
+    }
+}
+
+/// Observer calling a closure at every output.
+pub struct OnOutput<F>(F);
+
+impl<const DIM: usize, B, F> Observer<DIM, B> for OnOutput<F>
+where
+    F: FnMut(f64, &ParticleSystem<DIM>) -> ControlFlow<B>,
+{
+    fn at_output(&mut self, time: f64, system: &ParticleSystem<DIM>) -> ControlFlow<B> {
+        (self.0)(time, system)
+    }
+}
+
+/// Number of steps of `delta_t` in an output interval of `output_delta_t`, rounded so that
+/// time steps which divide the interval are not truncated to one step less. It is `None`
+/// when the interval holds no step.
+#[must_use]
+pub fn steps_per_output(output_delta_t: f64, delta_t: f64) -> Option<usize> {
+    let steps = (output_delta_t / delta_t).round() as usize;
+
+    (steps > 0).then_some(steps)
+}
+
+/// Advances a system with an integration method, calling its observers in the order they
+/// were added after every step and at the end of every output interval.
+///
+/// The time is counted from zero in steps of `delta_t`, which adaptive methods take as
+/// several internal steps.
+///
+/// # Panics
+///
+/// Stepping panics when the integration method cannot take a step, unless a handler was
+/// given with [`Simulation::on_step_error`] to stop the simulation instead.
+pub struct Simulation<'a, const DIM: usize, B = ()> {
+    system: ParticleSystem<DIM>,
+    integration_method: Box<dyn IntegrationMethod<DIM> + 'a>,
+    delta_t: f64,
+    steps_per_output: usize,
+    steps: usize,
+    observers: Vec<Box<dyn Observer<DIM, B> + 'a>>,
+    step_error: Option<Box<dyn FnMut(StepError) -> B + 'a>>,
+}
+
+impl<'a, const DIM: usize, B> Simulation<'a, DIM, B> {
+    /// Simulation with an output after every step, until changed with
+    /// [`Simulation::with_steps_per_output`].
+    #[must_use]
+    pub fn new(
+        system: ParticleSystem<DIM>,
+        integration_method: Box<dyn IntegrationMethod<DIM> + 'a>,
+        delta_t: f64,
+    ) -> Self {
+        Self {
+            system,
+            integration_method,
+            delta_t,
+            steps_per_output: 1,
+            steps: 0,
+            observers: Vec::new(),
+            step_error: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_steps_per_output(mut self, steps_per_output: usize) -> Self {
+        assert!(steps_per_output > 0, "an output interval must hold a step");
+
+        self.steps_per_output = steps_per_output;
+        self
+    }
+
+    pub fn observe(&mut self, observer: impl Observer<DIM, B> + 'a) -> &mut Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    pub fn on_step(
+        &mut self,
+        after_step: impl FnMut(f64, &mut ParticleSystem<DIM>) -> ControlFlow<B> + 'a,
+    ) -> &mut Self {
+        self.observe(OnStep(after_step))
+    }
+
+    pub fn on_output(
+        &mut self,
+        at_output: impl FnMut(f64, &ParticleSystem<DIM>) -> ControlFlow<B> + 'a,
+    ) -> &mut Self {
+        self.observe(OnOutput(at_output))
+    }
+
+    /// Stops the simulation with the value `handler` returns when the integration method
+    /// cannot take a step, which leaves the system at the last step it took, without
+    /// calling the observers.
+    pub fn on_step_error(&mut self, handler: impl FnMut(StepError) -> B + 'a) -> &mut Self {
+        self.step_error = Some(Box::new(handler));
+        self
+    }
+
+    /// Calls `handler` on every event of a particle from now on, when `event_function`
+    /// falls to zero, as described in [`EventDetector`].
+    pub fn on_event(
+        &mut self,
+        event_function: impl Fn(&ParticleState<DIM>) -> f64 + 'a,
+        handler: impl FnMut(&Event<DIM>, &mut ParticleSystem<DIM>) -> ControlFlow<B> + 'a,
+    ) -> &mut Self {
+        let detector = EventDetector::new(&self.system, self.time(), event_function, handler);
+        self.observe(detector)
+    }
+
+    #[must_use]
+    pub fn system(&self) -> &ParticleSystem<DIM> {
+        &self.system
+    }
+
+    #[must_use]
+    pub fn into_system(self) -> ParticleSystem<DIM> {
+        self.system
+    }
+
+    /// Number of steps taken so far.
+    #[must_use]
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    #[must_use]
+    pub fn time(&self) -> f64 {
+        self.steps as f64 * self.delta_t
+    }
+
+    /// Number of output intervals completed so far.
+    #[must_use]
+    pub fn outputs(&self) -> usize {
+        self.steps / self.steps_per_output
+    }
+
+    /// Snapshot of the simulation, integrating with the method of kind `integration`.
+    #[must_use]
+    pub fn checkpoint(&self, integration: Integration) -> Checkpoint {
+        Checkpoint {
+            integration,
+            delta_t: self.delta_t,
+            steps: self.steps,
+            steps_per_output: self.steps_per_output,
+            method_state: self.integration_method.state(),
+            particles: self
+                .system
+                .to_particles()
+                .iter()
+                .map(ParticleSnapshot::new)
+                .collect(),
+            seed: None,
+        }
+    }
+
+    /// Replaces the system, time and state of the integration method with those of the
+    /// `checkpoint`, which must have been taken with the same kind of method as this
+    /// simulation's, `integration`, at the same time step and steps per output.
+    ///
+    /// Observers keep the state they have, so those which track the system, like the
+    /// event detectors of [`Simulation::on_event`], should be added after restoring it.
+    pub fn restore(
+        &mut self,
+        checkpoint: &Checkpoint,
+        integration: Integration,
+    ) -> Result<(), CheckpointError> {
+        // NOTE: The time step must match to the bit, or the run would not continue exactly
+        if checkpoint.integration != integration || checkpoint.delta_t != self.delta_t {
+            return Err(CheckpointError::Integration {
+                expected: (integration, self.delta_t),
+                found: (checkpoint.integration, checkpoint.delta_t),
+            });
+        }
+
+        // NOTE: Outputs would otherwise fall at other times than those already written
+        if checkpoint.steps_per_output != self.steps_per_output {
+            return Err(CheckpointError::StepsPerOutput {
+                expected: self.steps_per_output,
+                found: checkpoint.steps_per_output,
+            });
+        }
+
+        let method_state_len = self.integration_method.state().len();
+        if checkpoint.method_state.len() != method_state_len {
+            return Err(CheckpointError::MethodState {
+                expected: method_state_len,
+                found: checkpoint.method_state.len(),
+            });
+        }
+
+        self.system = checkpoint.system(self.system.derivative_count())?;
+        self.integration_method
+            .restore_state(&checkpoint.method_state);
+        self.steps = checkpoint.steps;
+
+        Ok(())
+    }
+
+    /// Calls the output observers on the current state, as done at the end of every output
+    /// interval, for instance to output the initial state.
+    pub fn output(&mut self) -> ControlFlow<B> {
+        let time = self.time();
+        let system = &self.system;
+
+        self.observers
+            .iter_mut()
+            .try_for_each(|observer| observer.at_output(time, system))
+    }
+
+    /// Advances the system one step and calls the observers, returning whether one of them
+    /// or the handler of [`Simulation::on_step_error`] stopped the simulation.
+    pub fn step(&mut self) -> ControlFlow<B> {
+        if let Err(error) = self.integration_method.try_advance_system(&mut self.system) {
+            let Some(handler) = self.step_error.as_mut() else {
+                panic!("{error}");
+            };
+
+            return ControlFlow::Break(handler(error));
+        }
+        self.steps += 1;
+
+        let time = self.time();
+        let system = &mut self.system;
+        let stepped = self
+            .observers
+            .iter_mut()
+            .try_for_each(|observer| observer.after_step(time, system));
+
+        if stepped.is_break() {
+            // NOTE: Output the state the simulation stops at, even between outputs. An output
+            // observer stopping it as well takes precedence, as it may be reporting an error
+            self.output()?;
+
+            return stepped;
+        }
+
+        if self.steps.is_multiple_of(self.steps_per_output) {
+            self.output()
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+
+    /// Takes up to `steps` steps, stopping early if an observer asks to.
+    pub fn run_steps(&mut self, steps: usize) -> ControlFlow<B> {
+        (0..steps).try_for_each(|_| self.step())
+    }
+
+    /// Runs `outputs` output intervals, stopping early if an observer asks to.
+    pub fn run_outputs(&mut self, outputs: usize) -> ControlFlow<B> {
+        self.run_steps(outputs * self.steps_per_output)
+    }
+
+    /// Runs until an observer stops the simulation, returning the value it stopped with.
+    pub fn run(&mut self) -> B {
+        loop {
+            if let ControlFlow::Break(value) = self.step() {
+                return value;
+            }
+        }
+    }
+}
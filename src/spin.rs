@@ -0,0 +1,319 @@
+//! Rotation of spheres moving on a floor, which couples to their translation through the
+//! friction at the points where they touch the floor, each other and the walls.
+//!
+//! The particles are taken for solid spheres resting on the plane of the first two axes,
+//! the third pointing up from it. Friction acts between two surfaces while they slide
+//! past each other, until they stick. A ball shot without spin slides on the cloth
+//! until friction makes it roll, and top, back and side spin bend its path. Rolling
+//! friction then slows the ball down until it rests.
+//!
+//! The friction is applied as impulses after every step of the integration method, in
+//! an operator splitting which is first order in the time step. The integration method
+//! must take the velocities from the system at the start of every step. Verlet and its leap
+//! frog form recover them from earlier positions instead, which would undo the impulses.
+
+use std::ops::ControlFlow;
+
+use crate::{neighbours::CellList, simulation::Observer, system::ParticleSystem};
+
+/// Change of the speed at which a point on the surface of a solid sphere of unit mass
+/// slides, per unit of impulse along the surface there: 1 from the translation and 5/2
+/// from the rotation, with a moment of inertia of 2/5 times the mass and squared radius.
+const SLIP_COMPLIANCE: f64 = 7.0 / 2.0;
+
+/// Observer spinning the particles under the friction of the floor, and optionally of their
+/// contacts with each other and with walls, as described in the [module](self) docs.
+#[derive(Debug, Clone, Copy)]
+pub struct Spin {
+    delta_t: f64,
+    gravity: f64,
+    sliding_friction: f64,
+    rolling_friction: f64,
+    spinning_friction: f64,
+    contact_friction: Option<ContactFriction>,
+    wall_friction: Option<WallFriction>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ContactFriction {
+    coefficient: f64,
+    constant: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct WallFriction {
+    coefficient: f64,
+    constant: f64,
+    bounds: [f64; 2],
+}
+
+impl Spin {
+    /// Spin under the friction of a floor with a `sliding_friction` coefficient, for a
+    /// simulation stepping by `delta_t`.
+    #[must_use]
+    pub fn new(delta_t: f64, gravity: f64, sliding_friction: f64) -> Self {
+        Self {
+            delta_t,
+            gravity,
+            sliding_friction,
+            rolling_friction: 0.0,
+            spinning_friction: 0.0,
+            contact_friction: None,
+            wall_friction: None,
+        }
+    }
+
+    /// Slows down the particles rolling on the floor, which the sliding friction leaves
+    /// rolling forever, by the `coefficient` of the rolling friction. It takes the place
+    /// of a [`KineticFriction`](crate::forces::KineticFriction), which would slow down the
+    /// translation alone and leave the particles sliding.
+    #[must_use]
+    pub fn with_rolling_friction(mut self, coefficient: f64) -> Self {
+        self.rolling_friction = coefficient;
+        self
+    }
+
+    /// Slows down the spin about the vertical axis with the friction of the floor under a
+    /// particle turning in place.
+    #[must_use]
+    pub fn with_spinning_friction(mut self, coefficient: f64) -> Self {
+        self.spinning_friction = coefficient;
+        self
+    }
+
+    /// Adds friction between touching particles, pressed together as by a
+    /// [`SoftSphereContact`](crate::forces::SoftSphereContact) of `constant`.
+    #[must_use]
+    pub fn with_contact_friction(mut self, coefficient: f64, constant: f64) -> Self {
+        self.contact_friction = Some(ContactFriction {
+            coefficient,
+            constant,
+        });
+        self
+    }
+
+    /// Adds friction against the walls of a
+    /// [`WallContact`](crate::forces::WallContact) of `constant` and `bounds`.
+    #[must_use]
+    pub fn with_wall_friction(mut self, coefficient: f64, constant: f64, bounds: [f64; 2]) -> Self {
+        self.wall_friction = Some(WallFriction {
+            coefficient,
+            constant,
+            bounds,
+        });
+        self
+    }
+
+    fn apply_contact_friction(&self, system: &mut ParticleSystem<2>, friction: ContactFriction) {
+        let largest_radius = (0..system.len())
+            .map(|n| system.radius(n))
+            .fold(0.0, f64::max);
+        if largest_radius <= 0.0 {
+            return;
+        }
+
+        let mut pairs = Vec::new();
+        CellList::new(2.0 * largest_radius, system.positions())
+            .for_each_pair(|n, m| pairs.push((n, m)));
+
+        for (n, m) in pairs {
+            let delta_r = sub(planar(system.positions()[m]), planar(system.positions()[n]));
+            let distance = norm(delta_r);
+            let overlap = system.radius(n) + system.radius(m) - distance;
+            if overlap <= 0.0 || distance == 0.0 {
+                continue;
+            }
+
+            // NOTE: Points of contact on each sphere, relative to their centres
+            let normal = scale(delta_r, 1.0 / distance);
+            let contact = scale(normal, system.radius(n));
+            let other_contact = scale(normal, -system.radius(m));
+
+            let slip = sub(
+                surface_velocity(system, n, contact),
+                surface_velocity(system, m, other_contact),
+            );
+            let slip = sub(slip, scale(normal, dot(slip, normal)));
+
+            let largest_impulse = friction.coefficient * friction.constant * overlap * self.delta_t;
+            let compliance = SLIP_COMPLIANCE * (1.0 / system.mass(n) + 1.0 / system.mass(m));
+            let impulse = friction_impulse(slip, compliance, largest_impulse);
+
+            apply_impulse(system, n, contact, impulse);
+            apply_impulse(system, m, other_contact, scale(impulse, -1.0));
+        }
+    }
+
+    fn apply_wall_friction(&self, system: &mut ParticleSystem<2>, friction: WallFriction) {
+        for n in 0..system.len() {
+            let position = system.positions()[n];
+            let radius = system.radius(n);
+
+            for i in 0..2 {
+                // NOTE: Depth and outward normal of the wall the particle presses into
+                let (penetration, direction) = if position[i] <= radius {
+                    (radius - position[i], -1.0)
+                } else if position[i] >= friction.bounds[i] - radius {
+                    (position[i] - (friction.bounds[i] - radius), 1.0)
+                } else {
+                    continue;
+                };
+
+                let mut normal = [0.0; 3];
+                normal[i] = direction;
+                let contact = scale(normal, radius);
+
+                let slip = surface_velocity(system, n, contact);
+                let slip = sub(slip, scale(normal, dot(slip, normal)));
+
+                let largest_impulse =
+                    friction.coefficient * friction.constant * penetration * self.delta_t;
+                let compliance = SLIP_COMPLIANCE / system.mass(n);
+                let impulse = friction_impulse(slip, compliance, largest_impulse);
+
+                apply_impulse(system, n, contact, impulse);
+            }
+        }
+    }
+
+    fn apply_floor_friction(&self, system: &mut ParticleSystem<2>) {
+        for n in 0..system.len() {
+            let radius = system.radius(n);
+            let mass = system.mass(n);
+            let contact = [0.0, 0.0, -radius];
+
+            let slip = surface_velocity(system, n, contact);
+            let largest_impulse = self.sliding_friction * mass * self.gravity * self.delta_t;
+            let impulse = friction_impulse(slip, SLIP_COMPLIANCE / mass, largest_impulse);
+            apply_impulse(system, n, contact, impulse);
+
+            // NOTE: Only the rolling friction acts once the sliding friction stops the slip
+            let rolls = norm(slip) <= SLIP_COMPLIANCE / mass * largest_impulse;
+            if self.rolling_friction > 0.0 && rolls && radius > 0.0 {
+                self.apply_rolling_friction(system, n);
+            }
+
+            if self.spinning_friction > 0.0 && radius > 0.0 {
+                // NOTE: Torque of the friction spread over the patch the sphere rests on
+                let largest_change =
+                    5.0 * self.spinning_friction * self.gravity * self.delta_t / (2.0 * radius);
+                let spin = &mut system.angular_velocities[n][2];
+                *spin -= spin.signum() * spin.abs().min(largest_change);
+            }
+        }
+    }
+
+    /// Slows down particle `n`, which rolls, changing its rotation along with its
+    /// translation so that it keeps rolling.
+    fn apply_rolling_friction(&self, system: &mut ParticleSystem<2>, n: usize) {
+        let radius = system.radius(n);
+        let velocity = planar(system.velocities()[n]);
+        let speed = norm(velocity);
+        if speed == 0.0 {
+            return;
+        }
+
+        let change = speed.min(self.rolling_friction * self.gravity * self.delta_t);
+        let delta_v = scale(velocity, -change / speed);
+
+        let velocity = &mut system.derivatives[1][n];
+        for i in 0..2 {
+            velocity[i] += delta_v[i];
+        }
+
+        // NOTE: The rotation which rolls at the change of the velocity
+        let delta_omega = scale(cross([0.0, 0.0, 1.0], delta_v), 1.0 / radius);
+        let angular_velocity = &mut system.angular_velocities[n];
+        for i in 0..2 {
+            angular_velocity[i] += delta_omega[i];
+        }
+    }
+}
+
+impl<B> Observer<2, B> for Spin {
+    fn after_step(&mut self, _time: f64, system: &mut ParticleSystem<2>) -> ControlFlow<B> {
+        if let Some(friction) = self.contact_friction {
+            self.apply_contact_friction(system, friction);
+        }
+        if let Some(friction) = self.wall_friction {
+            self.apply_wall_friction(system, friction);
+        }
+        self.apply_floor_friction(system);
+
+        ControlFlow::Continue(())
+    }
+}
+
+/// Impulse of the friction against `slip`, as large as `largest_impulse` while the
+/// surfaces slide and just enough to stop them sliding otherwise, given the `compliance`
+/// of the slip to impulses.
+fn friction_impulse(slip: [f64; 3], compliance: f64, largest_impulse: f64) -> [f64; 3] {
+    let slip_speed = norm(slip);
+    if slip_speed == 0.0 {
+        return [0.0; 3];
+    }
+
+    let impulse = (slip_speed / compliance).min(largest_impulse);
+    scale(slip, -impulse / slip_speed)
+}
+
+/// Velocity of the point of particle `n` at `contact` from its centre.
+fn surface_velocity(system: &ParticleSystem<2>, n: usize, contact: [f64; 3]) -> [f64; 3] {
+    add(
+        planar(system.velocities()[n]),
+        cross(system.angular_velocities[n], contact),
+    )
+}
+
+/// Applies `impulse` at the point `contact` from the centre of particle `n`, leaving out
+/// the vertical change of the velocity, which the floor takes up.
+fn apply_impulse(system: &mut ParticleSystem<2>, n: usize, contact: [f64; 3], impulse: [f64; 3]) {
+    let mass = system.mass(n);
+    let moment_of_inertia = system.moment_of_inertia(n);
+
+    let velocity = &mut system.derivatives[1][n];
+    for i in 0..2 {
+        velocity[i] += impulse[i] / mass;
+    }
+
+    // NOTE: Point particles have no rotation to change
+    if moment_of_inertia > 0.0 {
+        let angular_velocity = &mut system.angular_velocities[n];
+        let torque = cross(contact, impulse);
+        for i in 0..3 {
+            angular_velocity[i] += torque[i] / moment_of_inertia;
+        }
+    }
+}
+
+fn planar(vector: [f64; 2]) -> [f64; 3] {
+    [vector[0], vector[1], 0.0]
+}
+
+fn add(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    std::array::from_fn(|i| a[i] + b[i])
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    std::array::from_fn(|i| a[i] - b[i])
+}
+
+fn scale(a: [f64; 3], factor: f64) -> [f64; 3] {
+    a.map(|x| factor * x)
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    (0..3).map(|i| a[i] * b[i]).sum()
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn norm(a: [f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
@@ -0,0 +1,320 @@
+use crate::{
+    forces::ForceModel,
+    parallel::{self, MaybeSync},
+    particle::{Particle, ParticleState},
+};
+
+/// Particles stored as a structure of arrays, where each derivative of the position of
+/// every particle lies in one contiguous array.
+///
+/// Integrators advance a system in place, reusing its arrays and scratch buffers from one
+/// step to the next, so once the first step has sized the buffers stepping a system only
+/// allocates what the force model itself does. Every particle holds the same number of
+/// derivatives: the position, velocity and acceleration, and any higher derivatives an
+/// integrator keeps track of.
+#[derive(Debug, Clone)]
+pub struct ParticleSystem<const DIM: usize> {
+    ids: Vec<usize>,
+    radii: Vec<f64>,
+    masses: Vec<f64>,
+
+    /// Each derivative of the position, from the position up, for every particle
+    pub(crate) derivatives: Vec<Vec<[f64; DIM]>>,
+    /// Derivatives at the start of the last step
+    pub(crate) prev_derivatives: Vec<Vec<[f64; DIM]>>,
+    /// Angular velocity of every particle, which integrators leave alone
+    pub(crate) angular_velocities: Vec<[f64; 3]>,
+
+    /// Arrays the integrators keep their intermediate results in
+    pub(crate) scratch: Vec<Vec<[f64; DIM]>>,
+    /// Forces of the last evaluation of a force model
+    pub(crate) forces: Vec<[f64; DIM]>,
+}
+
+impl<const DIM: usize> ParticleSystem<DIM> {
+    /// Copies the `particles` into a system, in the same order.
+    ///
+    /// # Panics
+    ///
+    /// If the particles do not all hold the same number of derivatives.
+    #[must_use]
+    pub fn new(particles: &[Particle<DIM>]) -> Self {
+        let derivative_count = particles.first().map_or(3, |p| p.derivatives().len());
+        assert!(
+            particles
+                .iter()
+                .all(|p| p.derivatives().len() == derivative_count),
+            "every particle of a system must hold the same number of derivatives"
+        );
+
+        let gather = |derivatives: fn(&Particle<DIM>) -> &Vec<[f64; DIM]>| {
+            (0..derivative_count)
+                .map(|k| particles.iter().map(|p| derivatives(p)[k]).collect())
+                .collect()
+        };
+
+        Self {
+            ids: particles.iter().map(Particle::id).collect(),
+            radii: particles.iter().map(Particle::radius).collect(),
+            masses: particles.iter().map(Particle::mass).collect(),
+            derivatives: gather(Particle::derivatives),
+            prev_derivatives: gather(Particle::prev_derivatives),
+            angular_velocities: particles.iter().map(Particle::angular_velocity).collect(),
+            scratch: Vec::new(),
+            forces: vec![[0.0; DIM]; particles.len()],
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    #[must_use]
+    pub fn id(&self, n: usize) -> usize {
+        self.ids[n]
+    }
+
+    #[must_use]
+    pub fn radius(&self, n: usize) -> f64 {
+        self.radii[n]
+    }
+
+    #[must_use]
+    pub fn mass(&self, n: usize) -> f64 {
+        self.masses[n]
+    }
+
+    /// Moment of inertia of particle `n` about its centre, taking it for a solid sphere.
+    #[must_use]
+    pub fn moment_of_inertia(&self, n: usize) -> f64 {
+        2.0 / 5.0 * self.masses[n] * self.radii[n].powi(2)
+    }
+
+    /// Number of derivatives each particle holds, counting the position.
+    #[must_use]
+    pub fn derivative_count(&self) -> usize {
+        self.derivatives.len()
+    }
+
+    /// Derivative `order` of the position of every particle.
+    #[must_use]
+    pub fn derivatives(&self, order: usize) -> &[[f64; DIM]] {
+        &self.derivatives[order]
+    }
+
+    #[must_use]
+    pub fn positions(&self) -> &[[f64; DIM]] {
+        self.derivatives(0)
+    }
+
+    #[must_use]
+    pub fn velocities(&self) -> &[[f64; DIM]] {
+        self.derivatives(1)
+    }
+
+    #[must_use]
+    pub fn accelerations(&self) -> &[[f64; DIM]] {
+        self.derivatives(2)
+    }
+
+    /// Angular velocity of every particle, as described in [`Particle::angular_velocity`].
+    #[must_use]
+    pub fn angular_velocities(&self) -> &[[f64; 3]] {
+        &self.angular_velocities
+    }
+
+    #[must_use]
+    pub fn state(&self, n: usize) -> ParticleState<DIM> {
+        ParticleState {
+            id: self.ids[n],
+            position: self.derivatives[0][n],
+            velocity: self.derivatives[1][n],
+            radius: self.radii[n],
+            mass: self.masses[n],
+        }
+    }
+
+    pub fn states(&self) -> impl Iterator<Item = ParticleState<DIM>> + '_ {
+        (0..self.len()).map(|n| self.state(n))
+    }
+
+    /// Copies the particle `n` out of the system.
+    #[must_use]
+    pub fn particle(&self, n: usize) -> Particle<DIM> {
+        let mut particle = Particle::new(
+            self.ids[n],
+            self.derivatives[0][n],
+            self.derivatives[1][n],
+            self.derivatives[2][n],
+            self.radii[n],
+            self.masses[n],
+        )
+        .with_angular_velocity(self.angular_velocities[n]);
+        particle.set_derivatives(self.derivatives.iter().map(|d| d[n]).collect());
+        particle.set_prev_derivatives(self.prev_derivatives.iter().map(|d| d[n]).collect());
+
+        particle
+    }
+
+    #[must_use]
+    pub fn to_particles(&self) -> Vec<Particle<DIM>> {
+        (0..self.len()).map(|n| self.particle(n)).collect()
+    }
+
+    /// Copies the derivatives and angular velocity of every particle back into `particles`,
+    /// which must be the particles the system was created from.
+    pub fn write_to(&self, particles: &mut [Particle<DIM>]) {
+        assert_eq!(
+            particles.len(),
+            self.len(),
+            "the particles do not match the system"
+        );
+
+        for (n, particle) in particles.iter_mut().enumerate() {
+            let derivatives = particle.derivatives_mut();
+            derivatives.clear();
+            derivatives.extend(self.derivatives.iter().map(|d| d[n]));
+
+            let prev_derivatives = particle.prev_derivatives_mut();
+            prev_derivatives.clear();
+            prev_derivatives.extend(self.prev_derivatives.iter().map(|d| d[n]));
+
+            particle.set_angular_velocity(self.angular_velocities[n]);
+        }
+    }
+
+    /// Removes the particles for which `keep` returns false, keeping the order of the rest.
+    pub fn retain(&mut self, mut keep: impl FnMut(&ParticleState<DIM>) -> bool) {
+        let kept: Vec<bool> = self.states().map(|state| keep(&state)).collect();
+
+        retain_kept(&mut self.ids, &kept);
+        retain_kept(&mut self.radii, &kept);
+        retain_kept(&mut self.masses, &kept);
+        retain_kept(&mut self.angular_velocities, &kept);
+        for derivative in self
+            .derivatives
+            .iter_mut()
+            .chain(&mut self.prev_derivatives)
+        {
+            retain_kept(derivative, &kept);
+        }
+
+        self.forces.truncate(self.len());
+    }
+
+    /// Sets the acceleration of every particle to the one `force_model` gives at the
+    /// current positions and velocities.
+    pub fn update_accelerations<F: ForceModel<DIM>>(&mut self, force_model: &F) {
+        self.evaluate_forces(force_model);
+
+        let masses = &self.masses;
+        let forces = &self.forces;
+        parallel::for_each_mut(&mut self.derivatives[2], |n, acceleration| {
+            *acceleration = forces[n].map(|f| f / masses[n]);
+        });
+    }
+
+    /// Evaluates `force_model` on the whole system, leaving the forces in `self.forces`.
+    pub(crate) fn evaluate_forces<F: ForceModel<DIM>>(&mut self, force_model: &F) {
+        let mut forces = std::mem::take(&mut self.forces);
+        forces.clear();
+        forces.resize(self.len(), [0.0; DIM]);
+
+        force_model.add_forces(self, &mut forces);
+        self.forces = forces;
+    }
+
+    /// Keeps the current derivatives as the previous ones, at the start of a step.
+    pub(crate) fn save_prev_derivatives(&mut self) {
+        for (prev, current) in self.prev_derivatives.iter_mut().zip(&self.derivatives) {
+            prev.copy_from_slice(current);
+        }
+    }
+
+    /// Moves the previous derivatives to the first scratch arrays before saving the
+    /// current ones, for methods which also need the state before the previous one.
+    pub(crate) fn shift_prev_derivatives(&mut self) {
+        self.resize_scratch(self.derivative_count());
+
+        for (earlier, prev) in self.scratch.iter_mut().zip(&mut self.prev_derivatives) {
+            std::mem::swap(earlier, prev);
+        }
+
+        self.save_prev_derivatives();
+    }
+
+    /// Makes room for `count` scratch arrays with an entry per particle.
+    pub(crate) fn resize_scratch(&mut self, count: usize) {
+        if self.scratch.len() < count {
+            self.scratch.resize_with(count, Vec::new);
+        }
+
+        for array in &mut self.scratch {
+            array.resize(self.ids.len(), [0.0; DIM]);
+        }
+    }
+
+    /// Appends a higher derivative of the position, given for every particle.
+    pub(crate) fn push_derivative(&mut self, derivative: Vec<[f64; DIM]>) {
+        assert_eq!(derivative.len(), self.len());
+
+        self.prev_derivatives.push(derivative.clone());
+        self.derivatives.push(derivative);
+    }
+
+    /// Updates the derivative `order` of every particle in place with `update`, which
+    /// is given the index of the particle and the rest of the system.
+    ///
+    /// The array being updated is moved out of the system meanwhile, so `update` must
+    /// only access it through the value it is given.
+    pub(crate) fn update_derivative(
+        &mut self,
+        order: usize,
+        update: impl Fn(usize, &mut [f64; DIM], &Self) + MaybeSync,
+    ) {
+        let mut derivative = std::mem::take(&mut self.derivatives[order]);
+
+        let system = &*self;
+        parallel::for_each_mut(&mut derivative, |n, value| update(n, value, system));
+        self.derivatives[order] = derivative;
+    }
+
+    /// Updates the previous derivative `order` of every particle in place, like
+    /// [`ParticleSystem::update_derivative`].
+    pub(crate) fn update_prev_derivative(
+        &mut self,
+        order: usize,
+        update: impl Fn(usize, &mut [f64; DIM], &Self) + MaybeSync,
+    ) {
+        let mut derivative = std::mem::take(&mut self.prev_derivatives[order]);
+
+        let system = &*self;
+        parallel::for_each_mut(&mut derivative, |n, value| update(n, value, system));
+        self.prev_derivatives[order] = derivative;
+    }
+
+    /// Updates the scratch array `index` in place, like
+    /// [`ParticleSystem::update_derivative`].
+    pub(crate) fn update_scratch(
+        &mut self,
+        index: usize,
+        update: impl Fn(usize, &mut [f64; DIM], &Self) + MaybeSync,
+    ) {
+        let mut array = std::mem::take(&mut self.scratch[index]);
+
+        let system = &*self;
+        parallel::for_each_mut(&mut array, |n, value| update(n, value, system));
+        self.scratch[index] = array;
+    }
+}
+
+fn retain_kept<T>(values: &mut Vec<T>, kept: &[bool]) {
+    let mut kept = kept.iter();
+    values.retain(|_| *kept.next().unwrap());
+}
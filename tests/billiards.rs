@@ -0,0 +1,173 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const GRAVITY: f64 = 9.81;
+const SLIDING_FRICTION: f64 = 0.2;
+const ROLLING_FRICTION: f64 = 0.1;
+const SPEED: f64 = 1.0;
+const START: f64 = 0.5;
+
+/// Table with a single ball and the friction of the spin section, with no holes to fall in.
+fn single_ball_scenario() -> String {
+    format!(
+        r#"{{
+            "table": {{"length": 2.24, "width": 1.12}},
+            "forces": {{
+                "contact_stiffness": 1e4,
+                "cushion_stiffness": 1e4,
+                "rolling_friction": {ROLLING_FRICTION}
+            }},
+            "holes": [],
+            "balls": [{{"position": [{START}, 0.56], "radius": 0.0285, "mass": 0.17}}],
+            "spin": {{"sliding_friction": {SLIDING_FRICTION}}}
+        }}"#
+    )
+}
+
+/// Empty directory of the temporary files of a test, unique to it.
+fn test_directory(test: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("billiards-{test}-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+
+    directory
+}
+
+/// Runs the billiards binary with `args`, panicking with its error output if it fails.
+fn run_billiards(args: &[&str], directory: &Path) {
+    let run = Command::new(env!("CARGO_BIN_EXE_billiards"))
+        .args(args)
+        .current_dir(directory)
+        .output()
+        .unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
+
+/// Initial positions of the balls of the default scenario with the noise of `seed`.
+fn initial_positions(seed: u64, directory: &Path) -> Vec<String> {
+    let path = format!("positions-{seed}.txt");
+    let seed = seed.to_string();
+    run_billiards(
+        &[
+            "euler",
+            "-o",
+            "1e-4",
+            "-m",
+            "1e-4",
+            "--seed",
+            &seed,
+            "--data-output-path",
+            &path,
+        ],
+        directory,
+    );
+
+    // NOTE: The first block holds the time of the initial state and a line per ball
+    let output = fs::read_to_string(directory.join(path)).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    let ball_count = lines.len() / 2 - 1;
+    lines[1..=ball_count]
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn runs_with_the_same_seed_start_from_the_same_positions() {
+    let directory = test_directory("seeds");
+
+    let first = initial_positions(7, &directory);
+    let repeated = initial_positions(7, &directory);
+    let other = initial_positions(8, &directory);
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(first, repeated);
+    assert_ne!(first, other);
+}
+
+#[test]
+fn ensembles_write_a_row_per_run() {
+    let directory = test_directory("ensemble");
+    run_billiards(
+        &[
+            "euler",
+            "-o",
+            "1e-3",
+            "-m",
+            "1e-3",
+            "--seed",
+            "3",
+            "--ensemble-size",
+            "4",
+            "--ensemble-output-path",
+            "ensemble.csv",
+        ],
+        &directory,
+    );
+
+    let output = fs::read_to_string(directory.join("ensemble.csv")).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    let mut lines = output.lines();
+    let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    let column = |name| header.iter().position(|&column| column == name).unwrap();
+
+    assert_eq!(rows.len(), 4);
+    for (n, row) in rows.iter().enumerate() {
+        assert_eq!(row.len(), header.len());
+        assert_eq!(row[column("seed")], (3 + n).to_string());
+        let time: f64 = row[column("time")].parse().unwrap();
+        assert!((time - 1e-3).abs() < 1e-12, "run stopped at {time}");
+    }
+}
+
+#[test]
+fn shot_ball_with_spin_rolls_the_distance_of_its_friction() {
+    let directory = std::env::temp_dir().join(format!("billiards-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let scenario = directory.join("scenario.json");
+    let positions = directory.join("positions.csv");
+    fs::write(&scenario, single_ball_scenario()).unwrap();
+
+    let run = Command::new(env!("CARGO_BIN_EXE_billiards"))
+        .args(["velocity-verlet", "-s", "1e-4", "-o", "0.01", "-m", "5"])
+        .args(["--shot-angle", "0", "--shot-speed", &SPEED.to_string()])
+        .arg("--scenario")
+        .arg(&scenario)
+        .arg("--data-output-path")
+        .arg(&positions)
+        .output()
+        .unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+
+    // NOTE: The last line holds the position of the ball at rest, where the run stopped
+    let output = fs::read_to_string(&positions).unwrap();
+    let last_line = output.lines().last().unwrap();
+    let position: Vec<f64> = last_line
+        .split_whitespace()
+        .map(|x| x.parse().unwrap())
+        .collect();
+    fs::remove_dir_all(&directory).unwrap();
+
+    // NOTE: The ball slides until it rolls at 5 / 7 of its speed, and the rolling
+    // friction alone stops it from there
+    let sliding_distance = 12.0 * SPEED.powi(2) / (49.0 * SLIDING_FRICTION * GRAVITY);
+    let rolling_distance = (5.0 / 7.0 * SPEED).powi(2) / (2.0 * ROLLING_FRICTION * GRAVITY);
+    let expected_position = START + sliding_distance + rolling_distance;
+    assert!(
+        (position[0] - expected_position).abs() < 1e-4,
+        "ball rested at {} instead of {expected_position}",
+        position[0]
+    );
+}
@@ -0,0 +1,119 @@
+use clap::ValueEnum;
+use integration_dynamics::{
+    checkpoint::{Checkpoint, CheckpointError},
+    forces::{ForceModel, LinearDamper, LinearSpring},
+    particle::Particle,
+    simulation::Simulation,
+    system::ParticleSystem,
+    Integration, MethodSettings,
+};
+
+const DELTA_T: f64 = 1e-2;
+const STEPS: usize = 50;
+
+fn force_model(damping: f64) -> impl ForceModel<2> {
+    LinearSpring::new(1e2, [0.0; 2]).plus(LinearDamper::new(damping))
+}
+
+fn oscillators() -> ParticleSystem<2> {
+    ParticleSystem::new(&[
+        Particle::new(0, [1.0, 0.0], [0.0, 1.0], [-1e2, 0.0], 0.0, 1.0),
+        Particle::new(1, [0.0, -0.5], [2.0, 0.0], [0.0, 25.0], 0.0, 2.0),
+    ])
+}
+
+/// Simulation of the oscillators, with the method of kind `integration` set up on them.
+fn simulation<'a>(integration: Integration) -> Simulation<'a, 2> {
+    let mut system = oscillators();
+
+    // NOTE: The symplectic compositions need forces which depend on the positions alone
+    let damped = integration.supports_velocity_dependent_forces();
+    let method = integration
+        .method(
+            force_model(if damped { 0.5 } else { 0.0 }),
+            &mut system,
+            DELTA_T,
+            MethodSettings {
+                force_depends_on_velocity: damped,
+                ..MethodSettings::default()
+            },
+        )
+        .unwrap();
+
+    Simulation::new(system, method, DELTA_T)
+}
+
+#[test]
+fn resumed_simulations_continue_exactly() {
+    let path = std::env::temp_dir().join(format!("checkpoint-{}.json", std::process::id()));
+
+    for &integration in Integration::value_variants() {
+        let mut uninterrupted = simulation(integration);
+        let _ = uninterrupted.run_steps(2 * STEPS);
+
+        let mut interrupted = simulation(integration);
+        let _ = interrupted.run_steps(STEPS);
+        interrupted.checkpoint(integration).save(&path).unwrap();
+
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        assert_eq!(checkpoint, interrupted.checkpoint(integration));
+        assert_eq!(checkpoint.time(), interrupted.time());
+
+        let mut resumed = simulation(integration);
+        resumed.restore(&checkpoint, integration).unwrap();
+        let _ = resumed.run_steps(STEPS);
+
+        assert_eq!(resumed.steps(), uninterrupted.steps());
+        assert_eq!(
+            resumed.checkpoint(integration),
+            uninterrupted.checkpoint(integration),
+            "{integration:?} did not resume exactly"
+        );
+    }
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn checkpoints_of_other_methods_are_rejected() {
+    let checkpoint = simulation(Integration::Beeman).checkpoint(Integration::Beeman);
+
+    let mut simulation = simulation(Integration::ImplicitMidpoint);
+    assert!(matches!(
+        simulation.restore(&checkpoint, Integration::ImplicitMidpoint),
+        Err(CheckpointError::Integration { .. })
+    ));
+}
+
+#[test]
+fn checkpoints_with_other_outputs_are_rejected() {
+    let checkpoint = simulation(Integration::Beeman)
+        .with_steps_per_output(5)
+        .checkpoint(Integration::Beeman);
+
+    let mut simulation = simulation(Integration::Beeman);
+    assert!(matches!(
+        simulation.restore(&checkpoint, Integration::Beeman),
+        Err(CheckpointError::StepsPerOutput {
+            expected: 1,
+            found: 5
+        })
+    ));
+}
+
+#[test]
+fn checkpoints_must_hold_every_derivative_the_method_needs() {
+    let mut checkpoint = simulation(Integration::GearPredictorCorrector)
+        .checkpoint(Integration::GearPredictorCorrector);
+    checkpoint.particles[1].derivatives.pop();
+
+    let mut simulation = simulation(Integration::GearPredictorCorrector);
+    assert!(matches!(
+        simulation.restore(&checkpoint, Integration::GearPredictorCorrector),
+        Err(CheckpointError::DerivativeCount {
+            particle_id: 1,
+            expected: 6,
+            found: 5
+        })
+    ));
+}
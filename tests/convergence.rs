@@ -0,0 +1,371 @@
+use std::{cell::RefCell, rc::Rc};
+
+use clap::ValueEnum;
+use integration_dynamics::{
+    convergence::fit_order,
+    forces::{ForceModel, LinearDamper, LinearSpring},
+    methods::{AdaptiveRungeKutta, IntegrationMethod, StepError, Symplectic},
+    particle::Particle,
+    simulation::Simulation,
+    system::ParticleSystem,
+    Integration, MethodError, MethodSettings,
+};
+
+const MASS: f64 = 70.0;
+const SPRING_CONSTANT: f64 = 1e4;
+const DAMPING_CONSTANT: f64 = 1e2;
+const AMPLITUDE: f64 = 1.0;
+
+const MAX_TIME: f64 = 1.0;
+const OUTPUT_DELTA_T: f64 = 1e-2;
+const DELTA_T: f64 = 2e-3;
+
+const ORDER_TOLERANCE: f64 = 0.1;
+const ADAPTIVE_TOLERANCE: f64 = 1e-8;
+const GEAR_ORDER: usize = 5;
+
+/// Damped oscillator of the oscillator binary, with its analytic solution.
+struct Oscillator {
+    damping_constant: f64,
+}
+
+impl Oscillator {
+    fn force_model(&self) -> impl ForceModel<1> + 'static {
+        LinearSpring::new(SPRING_CONSTANT, [0.0]).plus(LinearDamper::new(self.damping_constant))
+    }
+
+    fn particle(&self) -> Particle<1> {
+        let position = AMPLITUDE;
+        let velocity = -AMPLITUDE * self.damping_constant / (2.0 * MASS);
+        let acceleration = (-SPRING_CONSTANT * position - self.damping_constant * velocity) / MASS;
+
+        Particle::new(0, [position], [velocity], [acceleration], 0.0, MASS)
+    }
+
+    fn analytic_solution(&self, t: f64) -> f64 {
+        let a = -self.damping_constant / (2.0 * MASS);
+        let b = SPRING_CONSTANT / MASS - self.damping_constant.powi(2) / (4.0 * MASS.powi(2));
+
+        AMPLITUDE * (a * t).exp() * (b.sqrt() * t).cos()
+    }
+
+    fn method(
+        &self,
+        integration: &Integration,
+        gear_order: usize,
+        system: &mut ParticleSystem<1>,
+        delta_t: f64,
+    ) -> Box<dyn IntegrationMethod<1>> {
+        integration
+            .method(
+                self.force_model(),
+                system,
+                delta_t,
+                MethodSettings {
+                    absolute_tolerance: ADAPTIVE_TOLERANCE,
+                    relative_tolerance: ADAPTIVE_TOLERANCE,
+                    max_step: None,
+                    gear_order,
+                    force_depends_on_velocity: self.damping_constant != 0.0,
+                    step_observer: None,
+                },
+            )
+            .unwrap()
+    }
+
+    /// Root mean square error of the position against the analytic solution.
+    fn error(&self, integration: &Integration, gear_order: usize, delta_t: f64) -> f64 {
+        let mut system = ParticleSystem::new(&[self.particle()]);
+        let method = self.method(integration, gear_order, &mut system, delta_t);
+
+        self.method_error(method.as_ref(), system, delta_t)
+    }
+
+    /// Root mean square error of the position of `system`, stepped by `method`, against the
+    /// analytic solution.
+    fn method_error(
+        &self,
+        method: &dyn IntegrationMethod<1>,
+        mut system: ParticleSystem<1>,
+        delta_t: f64,
+    ) -> f64 {
+        let steps_per_output = (OUTPUT_DELTA_T / delta_t).round() as usize;
+        let outputs = (MAX_TIME / OUTPUT_DELTA_T).round() as usize;
+
+        let mut squared_error = 0.0;
+        for output in 1..=outputs {
+            for _ in 0..steps_per_output {
+                method.advance_system(&mut system);
+            }
+
+            let time = output as f64 * OUTPUT_DELTA_T;
+            let position = system.positions()[0][0];
+            squared_error += (position - self.analytic_solution(time)).powi(2);
+        }
+
+        (squared_error / outputs as f64).sqrt()
+    }
+
+    fn convergence_order(&self, integration: &Integration, gear_order: usize) -> f64 {
+        let error = self.error(integration, gear_order, DELTA_T);
+        let halved_error = self.error(integration, gear_order, DELTA_T / 2.0);
+
+        (error / halved_error).log2()
+    }
+}
+
+/// Textbook global order of each method, and whether it applies with velocity dependent
+/// forces. Symplectic compositions only keep their order for conservative forces, and
+/// adaptive methods have no fixed step to converge with.
+fn expected_order(integration: &Integration) -> Option<(f64, bool)> {
+    match integration {
+        Integration::Euler
+        | Integration::EulerMod
+        | Integration::EulerPredictorCorrector
+        | Integration::ImplicitEuler => Some((1.0, true)),
+        Integration::Verlet
+        | Integration::VerletLeapFrog
+        | Integration::VelocityVerlet
+        | Integration::Beeman
+        | Integration::RungeKuttaMidpoint
+        | Integration::Heun
+        | Integration::ImplicitMidpoint
+        | Integration::Trapezoidal => Some((2.0, true)),
+        Integration::RungeKutta4 | Integration::RungeKutta38 => Some((4.0, true)),
+        Integration::GearPredictorCorrector => Some((5.0, true)),
+        Integration::ForestRuth | Integration::Pefrl => Some((4.0, false)),
+        Integration::Yoshida6 => Some((6.0, false)),
+        Integration::DormandPrince | Integration::Fehlberg => None,
+    }
+}
+
+#[test]
+fn methods_converge_at_their_order() {
+    let mut failures = Vec::new();
+
+    for integration in Integration::value_variants() {
+        let Some((expected, supports_damping)) = expected_order(integration) else {
+            continue;
+        };
+
+        let oscillator = Oscillator {
+            damping_constant: if supports_damping {
+                DAMPING_CONSTANT
+            } else {
+                0.0
+            },
+        };
+        let order = oscillator.convergence_order(integration, GEAR_ORDER);
+
+        if (order - expected).abs() > ORDER_TOLERANCE {
+            failures.push(format!(
+                "{integration:?}: order {order:.3}, expected {expected}"
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn symplectic_methods_are_refused_velocity_dependent_forces() {
+    let oscillator = Oscillator {
+        damping_constant: DAMPING_CONSTANT,
+    };
+
+    for integration in [
+        Integration::ForestRuth,
+        Integration::Yoshida6,
+        Integration::Pefrl,
+    ] {
+        let mut system = ParticleSystem::new(&[oscillator.particle()]);
+        let method = integration.method(
+            oscillator.force_model(),
+            &mut system,
+            DELTA_T,
+            MethodSettings {
+                force_depends_on_velocity: true,
+                ..MethodSettings::default()
+            },
+        );
+
+        assert_eq!(
+            method.err(),
+            Some(MethodError::VelocityDependentForces(integration))
+        );
+    }
+}
+
+#[test]
+fn symplectic_methods_fall_to_first_order_with_damping() {
+    let oscillator = Oscillator {
+        damping_constant: DAMPING_CONSTANT,
+    };
+    let order = |method: &dyn Fn(f64) -> Symplectic<1, _>| {
+        let error = |delta_t| {
+            let system = ParticleSystem::new(&[oscillator.particle()]);
+            oscillator.method_error(&method(delta_t), system, delta_t)
+        };
+
+        (error(DELTA_T) / error(DELTA_T / 2.0)).log2()
+    };
+
+    for (name, order) in [
+        (
+            "Forest-Ruth",
+            order(&|delta_t| Symplectic::forest_ruth(oscillator.force_model(), delta_t)),
+        ),
+        (
+            "Yoshida",
+            order(&|delta_t| Symplectic::yoshida(oscillator.force_model(), 6, delta_t)),
+        ),
+        (
+            "PEFRL",
+            order(&|delta_t| Symplectic::pefrl(oscillator.force_model(), delta_t)),
+        ),
+    ] {
+        assert!(
+            (order - 1.0).abs() <= ORDER_TOLERANCE,
+            "{name} converged at order {order:.3} with damping"
+        );
+    }
+}
+
+#[test]
+fn adaptive_methods_meet_their_tolerance() {
+    let oscillator = Oscillator {
+        damping_constant: DAMPING_CONSTANT,
+    };
+
+    for integration in [Integration::DormandPrince, Integration::Fehlberg] {
+        let error = oscillator.error(&integration, GEAR_ORDER, OUTPUT_DELTA_T);
+
+        assert!(
+            error < 10.0 * ADAPTIVE_TOLERANCE,
+            "{integration:?}: error {error:e} above tolerance"
+        );
+    }
+}
+
+#[test]
+fn adaptive_methods_keep_the_start_of_the_interval_as_previous_derivatives() {
+    let oscillator = Oscillator {
+        damping_constant: DAMPING_CONSTANT,
+    };
+    let particle = oscillator.particle();
+
+    // NOTE: The output interval holds many internal steps at this tolerance
+    let method = AdaptiveRungeKutta::dormand_prince(
+        oscillator.force_model(),
+        OUTPUT_DELTA_T,
+        ADAPTIVE_TOLERANCE,
+        ADAPTIVE_TOLERANCE,
+    );
+    let mut simulation = Simulation::<1>::new(
+        ParticleSystem::new(std::slice::from_ref(&particle)),
+        Box::new(method),
+        OUTPUT_DELTA_T,
+    );
+    let _ = simulation.step();
+
+    let snapshot = &simulation.checkpoint(Integration::DormandPrince).particles[0];
+    let start: Vec<Vec<f64>> = particle.derivatives().iter().map(|d| d.to_vec()).collect();
+    assert_eq!(snapshot.prev_derivatives, start);
+}
+
+#[test]
+fn adaptive_methods_step_up_to_the_max_step_and_land_on_the_interval() {
+    let free = |_: &_, _: &_| [0.0];
+    let particle = Particle::new(0, [0.0], [1.0], [0.0], 0.0, MASS);
+
+    let steps = Rc::new(RefCell::new(Vec::new()));
+    let record_step = {
+        let steps = Rc::clone(&steps);
+        move |step| steps.borrow_mut().push(step)
+    };
+
+    // NOTE: Free flight has no error, so the steps grow as fast as they may
+    let interval = 1.0;
+    let max_step = 0.3;
+    let method = AdaptiveRungeKutta::dormand_prince(free, interval, 1e-9, 1e-6)
+        .with_max_step(max_step)
+        .with_step_observer(record_step);
+    method.advance_system(&mut ParticleSystem::new(&[particle]));
+
+    let steps = steps.borrow();
+    assert_eq!(steps.len(), 4);
+    assert!(steps.iter().all(|&step| step <= max_step));
+    assert!((steps.iter().sum::<f64>() - interval).abs() < 1e-12);
+}
+
+#[test]
+fn adaptive_methods_stop_on_forces_which_are_not_finite() {
+    let oscillator = Oscillator {
+        damping_constant: DAMPING_CONSTANT,
+    };
+    let blown_up = |_: &_, _: &_| [f64::NAN];
+    let particle = oscillator.particle();
+    let mut system = ParticleSystem::new(std::slice::from_ref(&particle));
+
+    let method = AdaptiveRungeKutta::dormand_prince(blown_up, DELTA_T, 1e-9, 1e-6);
+    let error = method.try_advance_system(&mut system).unwrap_err();
+
+    assert!(
+        matches!(error, StepError::StepSizeUnderflow { elapsed, .. } if elapsed == 0.0),
+        "{error:?}"
+    );
+    assert_eq!(system.positions(), [particle.state().position]);
+    assert_eq!(system.velocities(), [particle.state().velocity]);
+}
+
+#[test]
+fn gear_predictor_corrector_converges_at_each_order() {
+    let oscillator = Oscillator {
+        damping_constant: DAMPING_CONSTANT,
+    };
+
+    for gear_order in 2..=4 {
+        let order = oscillator.convergence_order(&Integration::GearPredictorCorrector, gear_order);
+
+        assert!(
+            (order - gear_order as f64).abs() <= ORDER_TOLERANCE,
+            "Gear of order {gear_order} converged at order {order:.3}"
+        );
+    }
+}
+
+/// Mean squared errors of a method of `order` at each of `delta_ts`.
+fn synthetic_errors(delta_ts: &[f64], order: f64) -> Vec<f64> {
+    delta_ts
+        .iter()
+        .map(|delta_t| 3.0 * delta_t.powf(2.0 * order))
+        .collect()
+}
+
+#[test]
+fn fitted_order_is_the_slope_of_the_errors() {
+    let delta_ts = [1e-2, 5e-3, 2e-3, 1e-3];
+
+    for order in [1.0, 2.0, 4.0] {
+        let fitted = fit_order(&delta_ts, &synthetic_errors(&delta_ts, order), 0.0).unwrap();
+        assert!(
+            (fitted - order).abs() < 1e-9,
+            "fitted {fitted} to order {order}"
+        );
+    }
+}
+
+#[test]
+fn errors_which_are_not_finite_or_below_the_floor_are_not_fitted() {
+    let delta_ts = [1e-1, 1e-2, 5e-3, 2e-3, 1e-3];
+    let mut errors = synthetic_errors(&delta_ts, 2.0);
+    errors[0] = f64::INFINITY;
+    errors[4] = 1e-30;
+
+    let fitted = fit_order(&delta_ts, &errors, 1e-20).unwrap();
+    assert!((fitted - 2.0).abs() < 1e-9, "fitted {fitted} to order 2");
+
+    errors[1] = f64::NAN;
+    errors[2] = 0.0;
+    assert_eq!(fit_order(&delta_ts, &errors, 1e-20), None);
+}
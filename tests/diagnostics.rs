@@ -0,0 +1,135 @@
+use integration_dynamics::{
+    diagnostics::Diagnostics,
+    forces::{
+        ConstantField, ForceModel, LinearDamper, LinearSpring, Pairwise, SoftSphereContact,
+        WallContact,
+    },
+    methods::{IntegrationMethod, VelocityVerlet},
+    particle::{Particle, ParticleState},
+    system::ParticleSystem,
+};
+
+const CONSTANT: f64 = 1e4;
+const RADIUS: f64 = 0.5;
+const BOUNDS: [f64; 2] = [3.0, 2.0];
+
+fn disc(id: usize, position: [f64; 2], velocity: [f64; 2]) -> Particle<2> {
+    Particle::new(id, position, velocity, [0.0; 2], RADIUS, 1.0 + id as f64)
+}
+
+/// Overlapping discs, two of them also pressing against the walls.
+fn overlapping_discs() -> Vec<Particle<2>> {
+    vec![
+        disc(0, [0.4, 0.6], [1.0, 0.0]),
+        disc(1, [1.3, 0.8], [0.0, -1.0]),
+        disc(2, [2.1, 1.1], [-0.5, 0.5]),
+        disc(3, [2.7, 1.6], [0.0, 0.0]),
+    ]
+}
+
+#[test]
+fn forces_are_minus_the_gradient_of_the_potential_energy() {
+    let force_model = Pairwise::with_cutoff(SoftSphereContact::new(CONSTANT), 2.0 * RADIUS)
+        .plus(WallContact::new(CONSTANT, BOUNDS))
+        .plus(LinearSpring::new(10.0, [1.0, 1.0]))
+        .plus(ConstantField::new([0.0, -9.8]));
+
+    let particles = overlapping_discs();
+    let forces = force_model.forces(&ParticleSystem::new(&particles));
+    let step = 1e-6;
+
+    for (n, force) in forces.iter().enumerate() {
+        for (i, component) in force.iter().enumerate() {
+            let displaced_energy = |displacement: f64| {
+                let displaced: Vec<_> = particles
+                    .iter()
+                    .map(|particle| {
+                        let mut state = particle.state();
+                        if state.id == n {
+                            state.position[i] += displacement;
+                        }
+
+                        disc(state.id, state.position, state.velocity)
+                    })
+                    .collect();
+
+                force_model
+                    .potential_energy(&ParticleSystem::new(&displaced))
+                    .unwrap()
+            };
+            let gradient = (displaced_energy(step) - displaced_energy(-step)) / (2.0 * step);
+
+            assert!(
+                (component + gradient).abs() <= 1e-6 * CONSTANT,
+                "particle {n}: force {component} against gradient {gradient}"
+            );
+        }
+    }
+}
+
+#[test]
+fn potential_energy_is_unknown_for_closures() {
+    let force_model = LinearSpring::new(CONSTANT, [0.0; 2])
+        .plus(|particle: &ParticleState<2>, _: &ParticleSystem<2>| particle.velocity.map(|v| -v));
+
+    let diagnostics = Diagnostics::of_particles(&overlapping_discs(), &force_model);
+    assert_eq!(diagnostics.potential_energy, None);
+    assert_eq!(diagnostics.total_energy(), None);
+}
+
+#[test]
+fn damped_spring_energy() {
+    let force_model = LinearSpring::new(4.0, [1.0]).plus(LinearDamper::new(2.0));
+    let particle = Particle::new(0, [3.0], [-2.0], [0.0], 0.0, 0.5);
+
+    let diagnostics = Diagnostics::of_particles(&[particle], &force_model);
+    assert_eq!(diagnostics.kinetic_energy, 1.0);
+    assert_eq!(diagnostics.potential_energy, Some(8.0));
+    assert_eq!(diagnostics.total_energy(), Some(9.0));
+    assert_eq!(diagnostics.linear_momentum, [-1.0]);
+}
+
+#[test]
+fn spinning_particles_add_their_rotation() {
+    let particle = disc(1, [0.0, 1.0], [1.0, 0.0]).with_angular_velocity([3.0, 0.0, 4.0]);
+
+    // NOTE: The moment of inertia of the solid sphere is 2/5 m r^2 = 0.2
+    let diagnostics = Diagnostics::of_particles(&[particle], &ConstantField::new([0.0; 2]));
+    assert!((diagnostics.kinetic_energy - 3.5).abs() <= 1e-12);
+    assert!((diagnostics.angular_momentum[0][1] + 1.2).abs() <= 1e-12);
+    assert_eq!(
+        diagnostics.angular_momentum[1][0],
+        -diagnostics.angular_momentum[0][1]
+    );
+}
+
+#[test]
+fn collisions_conserve_momentum() {
+    let force_model = Pairwise::with_cutoff(SoftSphereContact::new(CONSTANT), 2.0 * RADIUS);
+    let method = VelocityVerlet::new(force_model, 1e-4);
+
+    let mut system = ParticleSystem::new(&[
+        disc(0, [0.0, 0.0], [1.0, 0.2]),
+        disc(1, [1.5, 0.3], [-1.0, 0.0]),
+        disc(2, [0.8, 1.4], [0.0, -1.0]),
+    ]);
+    system.update_accelerations(&force_model);
+    let initial = Diagnostics::new(&system, &force_model);
+
+    for _ in 0..10_000 {
+        method.advance_system(&mut system);
+    }
+    let last = Diagnostics::new(&system, &force_model);
+
+    // NOTE: The discs must have collided for the test to mean anything
+    assert_ne!(system.velocities()[0], [1.0, 0.2]);
+
+    for i in 0..2 {
+        assert!((last.linear_momentum[i] - initial.linear_momentum[i]).abs() <= 1e-9);
+    }
+    assert!((last.angular_momentum[0][1] - initial.angular_momentum[0][1]).abs() <= 1e-9);
+    assert_eq!(last.angular_momentum[0][1], -last.angular_momentum[1][0]);
+
+    let energy_error = (last.total_energy().unwrap() - initial.total_energy().unwrap()).abs();
+    assert!(energy_error <= 1e-3 * initial.kinetic_energy);
+}
@@ -0,0 +1,114 @@
+use integration_dynamics::{
+    forces::{ForceModel, KineticFriction, Pairwise, SoftSphereContact, WallContact},
+    methods::ExplicitRungeKutta,
+    particle::{Particle, ParticleState},
+    simulation::Simulation,
+    system::ParticleSystem,
+};
+
+const CONSTANT: f64 = 1e4;
+const RESTITUTION: f64 = 0.8;
+const DELTA_T: f64 = 1e-5;
+const STEPS: usize = 50_000;
+
+/// System after the method of the classic Runge-Kutta has run it for `STEPS` steps.
+fn run(force_model: impl ForceModel<1> + 'static, particles: &[Particle<1>]) -> ParticleSystem<1> {
+    let method = ExplicitRungeKutta::classic(force_model, DELTA_T);
+    let mut simulation =
+        Simulation::<1>::new(ParticleSystem::new(particles), Box::new(method), DELTA_T);
+    let _ = simulation.run_steps(STEPS);
+
+    simulation.into_system()
+}
+
+#[test]
+fn head_on_collisions_keep_the_restitution_of_the_approach_speed() {
+    let system = run(
+        Pairwise::new(SoftSphereContact::with_restitution(CONSTANT, RESTITUTION)),
+        &[
+            Particle::new(0, [0.0], [1.0], [0.0], 0.1, 1.0),
+            Particle::new(1, [0.3], [-0.5], [0.0], 0.1, 3.0),
+        ],
+    );
+
+    let velocities = system.velocities();
+    let separation_speed = velocities[1][0] - velocities[0][0];
+    // NOTE: The dashpot switches on inside a step, which bounds the accuracy to its size
+    assert!((separation_speed - RESTITUTION * 1.5).abs() < 1e-4);
+
+    // NOTE: The dashpot acts on both particles alike, so it keeps the momentum
+    let momentum = velocities[0][0] + 3.0 * velocities[1][0];
+    assert!((momentum - (1.0 - 1.5)).abs() < 1e-9);
+}
+
+#[test]
+fn walls_send_particles_back_with_the_restitution_of_their_speed() {
+    let system = run(
+        WallContact::with_restitution(CONSTANT, [1.0], RESTITUTION),
+        &[Particle::new(0, [0.7], [1.0], [0.0], 0.1, 2.0)],
+    );
+
+    assert!((system.velocities()[0][0] + RESTITUTION).abs() < 1e-4);
+}
+
+#[test]
+fn friction_stops_particles_after_the_distance_it_takes_them() {
+    let (coefficient, gravity, creep_speed) = (0.2, 10.0, 1e-4);
+    let system = run(
+        KineticFriction::new(coefficient, gravity, creep_speed),
+        &[Particle::new(0, [0.0], [0.5], [0.0], 0.1, 2.0)],
+    );
+
+    let speed = system.velocities()[0][0];
+    let stopping_distance = 0.5_f64.powi(2) / (2.0 * coefficient * gravity);
+    assert!(speed.abs() < creep_speed);
+    assert!((system.positions()[0][0] - stopping_distance).abs() < 1e-5);
+}
+
+#[test]
+fn dissipative_jacobians_match_finite_differences() {
+    let particle = ParticleState {
+        id: 0,
+        position: [0.15, 0.95],
+        velocity: [0.3, 0.7],
+        radius: 0.1,
+        mass: 2.0,
+    };
+    let other = Particle::new(1, [0.25, 1.02], [-0.4, 0.1], [0.0; 2], 0.1, 1.0);
+    let system = ParticleSystem::new(&[
+        Particle::new(0, [0.0; 2], [0.0; 2], [0.0; 2], 0.1, 2.0),
+        other,
+    ]);
+
+    let force_model = Pairwise::new(SoftSphereContact::with_restitution(CONSTANT, RESTITUTION))
+        .plus(WallContact::with_restitution(
+            CONSTANT,
+            [1.0, 1.0],
+            RESTITUTION,
+        ))
+        .plus(KineticFriction::new(0.2, 10.0, 1e-3));
+    let jacobian = force_model.jacobian(&particle, &system).unwrap();
+    let force = force_model.force(&particle, &system);
+
+    let step = 1e-7;
+    for j in 0..2 {
+        for (derivative, column) in [(0, &jacobian.position), (1, &jacobian.velocity)] {
+            let mut perturbed = particle;
+            if derivative == 0 {
+                perturbed.position[j] += step;
+            } else {
+                perturbed.velocity[j] += step;
+            }
+            let perturbed_force = force_model.force(&perturbed, &system);
+
+            for i in 0..2 {
+                let finite_difference = (perturbed_force[i] - force[i]) / step;
+                assert!(
+                    (column[i][j] - finite_difference).abs() < 1e-4 * CONSTANT,
+                    "derivative {derivative} [{i}][{j}]: {} against {finite_difference}",
+                    column[i][j]
+                );
+            }
+        }
+    }
+}
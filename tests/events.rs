@@ -0,0 +1,107 @@
+use std::ops::ControlFlow;
+
+use integration_dynamics::{
+    forces::ConstantField,
+    methods::{ExplicitRungeKutta, VelocityVerlet},
+    particle::Particle,
+    simulation::Simulation,
+    system::ParticleSystem,
+};
+
+const GRAVITY: f64 = 9.8;
+const DELTA_T: f64 = 0.1;
+
+/// Balls thrown up from different heights, which land on the floor at the origin.
+fn thrown_balls() -> ParticleSystem<2> {
+    let ball = |id, height: f64, speed: f64| {
+        Particle::new(id, [0.0, height], [1.0, speed], [0.0, -GRAVITY], 0.0, 1.0)
+    };
+
+    ParticleSystem::new(&[ball(0, 2.0, 3.0), ball(1, 1.0, 1.0), ball(2, 5.0, 0.0)])
+}
+
+fn landing_time(height: f64, speed: f64) -> f64 {
+    (speed + (speed.powi(2) + 2.0 * GRAVITY * height).sqrt()) / GRAVITY
+}
+
+#[test]
+fn events_are_located_inside_the_step_in_time_order() {
+    let method = VelocityVerlet::new(ConstantField::new([0.0, -GRAVITY]), DELTA_T);
+    let mut landings = Vec::new();
+
+    let mut simulation = Simulation::new(thrown_balls(), Box::new(method), DELTA_T);
+    simulation.on_event(
+        |ball| ball.position[1],
+        |landing, balls| {
+            landings.push(*landing);
+            balls.retain(|ball| ball.id != landing.particle.id);
+
+            if balls.is_empty() {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+    );
+    simulation.run();
+    drop(simulation);
+
+    let ids: Vec<_> = landings.iter().map(|landing| landing.particle.id).collect();
+    assert_eq!(ids, [1, 2, 0]);
+
+    // NOTE: Velocity Verlet and the cubic interpolation are exact for a parabola
+    for (landing, (height, speed)) in landings.iter().zip([(1.0, 1.0), (5.0, 0.0), (2.0, 3.0)]) {
+        let time = landing_time(height, speed);
+
+        assert!((landing.time - time).abs() < 1e-10);
+        assert!(landing.particle.position[1].abs() < 1e-10);
+        assert!((landing.particle.position[0] - time).abs() < 1e-10);
+        assert!((landing.particle.velocity[1] - (speed - GRAVITY * time)).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn event_times_are_more_accurate_than_the_step() {
+    let method = ExplicitRungeKutta::heun(ConstantField::new([0.0, -GRAVITY]), DELTA_T);
+    let mut landing_times = Vec::new();
+
+    let mut simulation = Simulation::new(thrown_balls(), Box::new(method), DELTA_T);
+    simulation.on_event(
+        |ball| ball.position[1] + 0.5,
+        |landing, _| {
+            landing_times.push((landing.particle.id, landing.time));
+            ControlFlow::<()>::Continue(())
+        },
+    );
+    let _ = simulation.run_steps(30);
+    drop(simulation);
+
+    assert_eq!(landing_times.len(), 3);
+    for (id, time) in landing_times {
+        let (height, speed) = [(2.0, 3.0), (1.0, 1.0), (5.0, 0.0)][id];
+        let expected = landing_time(height + 0.5, speed);
+
+        assert!((time - expected).abs() < 1e-3 * DELTA_T);
+    }
+}
+
+#[test]
+fn events_are_only_detected_on_falling_to_zero() {
+    let method = VelocityVerlet::new(ConstantField::new([0.0, -GRAVITY]), DELTA_T);
+    let mut ids = Vec::new();
+
+    let mut simulation = Simulation::new(thrown_balls(), Box::new(method), DELTA_T);
+    // NOTE: Ball 0 rises above this height and falls back, ball 1 never reaches it and
+    // ball 2 starts above it
+    simulation.on_event(
+        |ball| ball.position[1] - 2.1,
+        |event, _| {
+            ids.push(event.particle.id);
+            ControlFlow::<()>::Continue(())
+        },
+    );
+    let _ = simulation.run_steps(20);
+    drop(simulation);
+
+    assert_eq!(ids, [0, 2]);
+}
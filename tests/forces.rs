@@ -0,0 +1,224 @@
+use integration_dynamics::{
+    forces::{
+        ConstantField, ForceJacobian, ForceModel, LinearDamper, LinearSpring, Pairwise,
+        SoftSphereContact, WallContact,
+    },
+    particle::Particle,
+    system::ParticleSystem,
+};
+
+const TOLERANCE: f64 = 1e-9;
+
+/// Single particle of `mass` and `radius` at `position` moving at `velocity`.
+fn system(position: [f64; 2], velocity: [f64; 2], radius: f64, mass: f64) -> ParticleSystem<2> {
+    ParticleSystem::new(&[Particle::new(0, position, velocity, [0.0; 2], radius, mass)])
+}
+
+fn assert_vector(actual: [f64; 2], expected: [f64; 2]) {
+    for i in 0..2 {
+        assert!(
+            (actual[i] - expected[i]).abs() < TOLERANCE,
+            "{actual:?} differs from {expected:?}"
+        );
+    }
+}
+
+fn assert_jacobian(actual: ForceJacobian<2>, position: [[f64; 2]; 2], velocity: [[f64; 2]; 2]) {
+    for i in 0..2 {
+        assert_vector(actual.position[i], position[i]);
+        assert_vector(actual.velocity[i], velocity[i]);
+    }
+}
+
+#[test]
+fn linear_spring_pulls_towards_its_anchor() {
+    let system = system([4.0, 3.0], [1.0, 1.0], 0.1, 2.0);
+    let particle = system.state(0);
+    let spring = LinearSpring::new(2.0, [1.0, -1.0]);
+
+    assert_vector(spring.force(&particle, &system), [-6.0, -8.0]);
+    assert_jacobian(
+        spring.jacobian(&particle, &system).unwrap(),
+        [[-2.0, 0.0], [0.0, -2.0]],
+        [[0.0; 2]; 2],
+    );
+    assert_eq!(spring.potential_energy(&system), Some(25.0));
+}
+
+#[test]
+fn linear_damper_opposes_the_velocity() {
+    let system = system([4.0, 3.0], [1.0, -2.0], 0.1, 2.0);
+    let particle = system.state(0);
+    let damper = LinearDamper::new(3.0);
+
+    assert_vector(damper.force(&particle, &system), [-3.0, 6.0]);
+    assert_jacobian(
+        ForceModel::<2>::jacobian(&damper, &particle, &system).unwrap(),
+        [[0.0; 2]; 2],
+        [[-3.0, 0.0], [0.0, -3.0]],
+    );
+    assert_eq!(
+        ForceModel::<2>::potential_energy(&damper, &system),
+        Some(0.0)
+    );
+}
+
+#[test]
+fn constant_field_weighs_the_mass() {
+    let system = system([4.0, 3.0], [1.0, -2.0], 0.1, 2.0);
+    let particle = system.state(0);
+    let gravity = ConstantField::new([0.0, -9.81]);
+
+    assert_vector(gravity.force(&particle, &system), [0.0, -19.62]);
+    assert_jacobian(
+        gravity.jacobian(&particle, &system).unwrap(),
+        [[0.0; 2]; 2],
+        [[0.0; 2]; 2],
+    );
+    assert!((gravity.potential_energy(&system).unwrap() - 58.86).abs() < TOLERANCE);
+}
+
+#[test]
+fn soft_spheres_push_apart_along_the_line_of_centres() {
+    // NOTE: Centres 0.5 apart along (0.6, 0.8), with radii summing to 0.6
+    let system = ParticleSystem::new(&[
+        Particle::new(0, [0.0, 0.0], [0.0; 2], [0.0; 2], 0.3, 1.0),
+        Particle::new(1, [0.3, 0.4], [0.0; 2], [0.0; 2], 0.3, 1.0),
+    ]);
+    let contact = Pairwise::new(SoftSphereContact::new(100.0));
+
+    assert_vector(contact.force(&system.state(0), &system), [-6.0, -8.0]);
+    assert_vector(contact.force(&system.state(1), &system), [6.0, 8.0]);
+
+    // NOTE: -k [(1 - R / d) I + (R / d) n n^T], with R / d = 1.2
+    assert_jacobian(
+        contact.jacobian(&system.state(0), &system).unwrap(),
+        [[-23.2, -57.6], [-57.6, -56.8]],
+        [[0.0; 2]; 2],
+    );
+    assert!((contact.potential_energy(&system).unwrap() - 0.5).abs() < TOLERANCE);
+}
+
+#[test]
+fn soft_spheres_with_restitution_damp_their_approach() {
+    // NOTE: Head-on approach at a relative speed of 2, overlapping by 0.1
+    let system = ParticleSystem::new(&[
+        Particle::new(0, [0.0, 0.0], [1.0, 0.0], [0.0; 2], 0.3, 1.0),
+        Particle::new(1, [0.5, 0.0], [-1.0, 0.0], [0.0; 2], 0.3, 1.0),
+    ]);
+    let contact = Pairwise::new(SoftSphereContact::with_restitution(100.0, 0.0));
+    let particle = system.state(0);
+
+    // NOTE: A perfectly inelastic contact is critically damped for the reduced mass of 0.5
+    let damping = 2.0 * (100.0_f64 * 0.5).sqrt();
+
+    assert_vector(
+        contact.force(&particle, &system),
+        [-10.0 - 2.0 * damping, 0.0],
+    );
+
+    // NOTE: Moving sideways turns the line of centres, and the damping force with it
+    assert_jacobian(
+        contact.jacobian(&particle, &system).unwrap(),
+        [[-100.0, 0.0], [0.0, 20.0 + 4.0 * damping]],
+        [[-damping, 0.0], [0.0, 0.0]],
+    );
+}
+
+#[test]
+fn separated_spheres_do_not_interact() {
+    let system = ParticleSystem::new(&[
+        Particle::new(0, [0.0, 0.0], [1.0, 0.0], [0.0; 2], 0.2, 1.0),
+        Particle::new(1, [0.3, 0.4], [-1.0, 0.0], [0.0; 2], 0.2, 1.0),
+    ]);
+    let contact = Pairwise::new(SoftSphereContact::with_restitution(100.0, 0.5));
+
+    assert_vector(contact.force(&system.state(0), &system), [0.0; 2]);
+    assert_eq!(
+        contact.jacobian(&system.state(0), &system),
+        Some(ForceJacobian::zero())
+    );
+    assert_eq!(contact.potential_energy(&system), Some(0.0));
+}
+
+#[test]
+fn spheres_with_coincident_centres_do_not_interact() {
+    let system = ParticleSystem::new(&[
+        Particle::new(0, [0.5, 0.5], [1.0, 0.0], [0.0; 2], 0.2, 1.0),
+        Particle::new(1, [0.5, 0.5], [-1.0, 0.0], [0.0; 2], 0.2, 1.0),
+    ]);
+    let contact = Pairwise::new(SoftSphereContact::with_restitution(100.0, 0.5));
+
+    // NOTE: The line of centres is undefined, which would otherwise spread NaN
+    assert_vector(contact.force(&system.state(0), &system), [0.0; 2]);
+    assert_vector(contact.force(&system.state(1), &system), [0.0; 2]);
+    assert_eq!(
+        contact.jacobian(&system.state(0), &system),
+        Some(ForceJacobian::zero())
+    );
+}
+
+#[test]
+fn walls_push_back_the_depth_a_particle_reaches_into_them() {
+    // NOTE: 0.05 into the lower wall along x and 0.07 into the upper wall along y
+    let system = system([0.05, 0.97], [1.0, -2.0], 0.1, 4.0);
+    let particle = system.state(0);
+    let walls = WallContact::new(100.0, [1.0, 1.0]);
+
+    assert_vector(walls.force(&particle, &system), [5.0, -7.0]);
+    assert_jacobian(
+        walls.jacobian(&particle, &system).unwrap(),
+        [[-100.0, 0.0], [0.0, -100.0]],
+        [[0.0; 2]; 2],
+    );
+    assert!((walls.potential_energy(&system).unwrap() - 0.37).abs() < TOLERANCE);
+}
+
+#[test]
+fn walls_with_restitution_damp_only_the_components_in_contact() {
+    // NOTE: Touching the lower wall along x only
+    let system = system([0.05, 0.5], [-1.0, 2.0], 0.1, 4.0);
+    let particle = system.state(0);
+    let walls = WallContact::with_restitution(100.0, [1.0, 1.0], 0.0);
+
+    // NOTE: A perfectly inelastic wall is critically damped for the mass of the particle
+    let damping = 2.0 * (100.0_f64 * 4.0).sqrt();
+
+    assert_vector(walls.force(&particle, &system), [5.0 + damping, 0.0]);
+    assert_jacobian(
+        walls.jacobian(&particle, &system).unwrap(),
+        [[-100.0, 0.0], [0.0, 0.0]],
+        [[-damping, 0.0], [0.0, 0.0]],
+    );
+}
+
+#[test]
+fn sums_add_the_forces_jacobians_and_energies_of_their_terms() {
+    let system = system([4.0, 3.0], [1.0, -2.0], 0.1, 2.0);
+    let particle = system.state(0);
+    let force_model = LinearSpring::new(2.0, [1.0, -1.0])
+        .plus(LinearDamper::new(3.0))
+        .plus(ConstantField::new([0.0, -9.81]));
+
+    assert_vector(
+        force_model.force(&particle, &system),
+        [-6.0 - 3.0, -8.0 + 6.0 - 19.62],
+    );
+    assert_jacobian(
+        force_model.jacobian(&particle, &system).unwrap(),
+        [[-2.0, 0.0], [0.0, -2.0]],
+        [[-3.0, 0.0], [0.0, -3.0]],
+    );
+    assert!((force_model.potential_energy(&system).unwrap() - (25.0 + 58.86)).abs() < TOLERANCE);
+}
+
+#[test]
+fn sums_have_no_jacobian_unless_every_term_does() {
+    let system = system([4.0, 3.0], [1.0, -2.0], 0.1, 2.0);
+    let particle = system.state(0);
+    let unknown = |_: &_, _: &_| [1.0, 1.0];
+    let force_model = LinearSpring::new(2.0, [1.0, -1.0]).plus(unknown);
+
+    assert_vector(force_model.force(&particle, &system), [-5.0, -7.0]);
+    assert_eq!(force_model.jacobian(&particle, &system), None);
+}
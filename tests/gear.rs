@@ -0,0 +1,101 @@
+use integration_dynamics::{
+    forces::{ForceModel, LinearSpring},
+    methods::{GearError, GearPredictorCorrector},
+    particle::Particle,
+    system::ParticleSystem,
+};
+
+const DELTA_T: f64 = 1e-3;
+
+fn force_model() -> impl ForceModel<1> {
+    LinearSpring::new(1.0, [0.0])
+}
+
+fn oscillators() -> ParticleSystem<1> {
+    ParticleSystem::new(&[
+        Particle::new(0, [1.0], [0.0], [-1.0], 0.0, 1.0),
+        Particle::new(1, [0.0], [1.0], [0.0], 0.0, 1.0),
+    ])
+}
+
+#[test]
+fn orders_outside_the_coefficients_are_unsupported() {
+    for order in [0, 1, 6] {
+        let new = GearPredictorCorrector::new(
+            force_model(),
+            false,
+            order,
+            &mut oscillators(),
+            vec![Vec::new(); 2],
+            DELTA_T,
+        );
+        assert_eq!(new.err(), Some(GearError::UnsupportedOrder(order)));
+
+        let bootstrap = GearPredictorCorrector::bootstrap(
+            force_model(),
+            false,
+            order,
+            &mut oscillators(),
+            DELTA_T,
+        );
+        assert_eq!(bootstrap.err(), Some(GearError::UnsupportedOrder(order)));
+    }
+}
+
+#[test]
+fn every_particle_needs_its_higher_derivatives() {
+    let new = GearPredictorCorrector::new(
+        force_model(),
+        false,
+        3,
+        &mut oscillators(),
+        vec![vec![[0.0]]],
+        DELTA_T,
+    );
+
+    assert_eq!(
+        new.err(),
+        Some(GearError::ParticleCount {
+            expected: 2,
+            found: 1
+        })
+    );
+}
+
+#[test]
+fn every_particle_needs_the_derivatives_of_the_order() {
+    // NOTE: Order 4 needs the jerk and the snap above the three derivatives of the system
+    let new = GearPredictorCorrector::new(
+        force_model(),
+        false,
+        4,
+        &mut oscillators(),
+        vec![vec![[0.0], [1.0]], vec![[-1.0]]],
+        DELTA_T,
+    );
+
+    assert_eq!(
+        new.err(),
+        Some(GearError::DerivativeCount {
+            particle_id: 1,
+            expected: 5,
+            found: 4
+        })
+    );
+}
+
+#[test]
+fn higher_derivatives_extend_the_system() {
+    let mut system = oscillators();
+    let new = GearPredictorCorrector::new(
+        force_model(),
+        false,
+        4,
+        &mut system,
+        vec![vec![[0.0], [1.0]], vec![[-1.0], [0.0]]],
+        DELTA_T,
+    );
+
+    assert!(new.is_ok());
+    assert_eq!(system.derivative_count(), 5);
+}
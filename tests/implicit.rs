@@ -0,0 +1,246 @@
+use integration_dynamics::{
+    forces::{
+        ForceJacobian, ForceModel, LinearSpring, PairInteraction, Pairwise, SoftSphereContact,
+        WallContact,
+    },
+    methods::{Euler, Implicit, IntegrationMethod, StepError, VelocityVerlet},
+    particle::{Particle, ParticleState},
+    simulation::Simulation,
+    system::ParticleSystem,
+};
+
+const MASS: f64 = 1.0;
+const SPRING_CONSTANT: f64 = 1e4;
+/// Five times the stability limit of explicit methods, `2 / omega`.
+const DELTA_T: f64 = 0.1;
+const STEPS: usize = 1_000;
+
+fn spring() -> LinearSpring<1> {
+    LinearSpring::new(SPRING_CONSTANT, [0.0])
+}
+
+fn energy(particle: &Particle<1>) -> f64 {
+    let r = particle.derivatives();
+
+    0.5 * MASS * r[1][0].powi(2) + 0.5 * SPRING_CONSTANT * r[0][0].powi(2)
+}
+
+/// Integrates the stiff oscillator and returns its final energy relative to the initial one.
+fn energy_ratio(method: &dyn IntegrationMethod<1>) -> f64 {
+    let mut particles = [Particle::new(
+        0,
+        [1.0],
+        [0.0],
+        [-SPRING_CONSTANT / MASS],
+        0.0,
+        MASS,
+    )];
+    let initial_energy = energy(&particles[0]);
+
+    for _ in 0..STEPS {
+        method.advance_step(&mut particles);
+    }
+
+    energy(&particles[0]) / initial_energy
+}
+
+#[test]
+fn explicit_method_diverges_on_stiff_spring() {
+    let ratio = energy_ratio(&VelocityVerlet::new(spring(), DELTA_T));
+    assert!(!ratio.is_finite() || ratio > 1e6, "energy ratio {ratio}");
+}
+
+#[test]
+fn backward_euler_damps_stiff_spring() {
+    assert!(energy_ratio(&Implicit::backward_euler(spring(), DELTA_T)) < 1e-6);
+}
+
+#[test]
+fn midpoint_and_trapezoidal_conserve_stiff_spring_energy() {
+    for method in [
+        Implicit::midpoint(spring(), DELTA_T),
+        Implicit::trapezoidal(spring(), DELTA_T),
+    ] {
+        let ratio = energy_ratio(&method);
+        assert!((ratio - 1.0).abs() < 1e-8, "energy ratio {ratio}");
+    }
+}
+
+#[test]
+fn finite_difference_jacobian_matches_analytic_one() {
+    let spring = spring();
+    let closure = move |particle: &ParticleState<1>, system: &ParticleSystem<1>| {
+        spring.force(particle, system)
+    };
+
+    let analytic = energy_ratio(&Implicit::trapezoidal(spring, DELTA_T));
+    let finite_difference = energy_ratio(&Implicit::trapezoidal(closure, DELTA_T));
+
+    assert!((analytic - finite_difference).abs() < 1e-8);
+}
+
+/// Kinetic energy, relative to the initial one, of two stiff spheres of unlike masses
+/// bouncing between each other and the walls of a box for five seconds.
+fn contact_energy_ratio(method: &dyn IntegrationMethod<1>) -> f64 {
+    let mut particles = [
+        Particle::new(0, [0.15], [0.5], [0.0], 0.1, 1.0),
+        Particle::new(1, [0.35], [-0.5], [0.0], 0.1, 3.0),
+    ];
+    let kinetic_energy = |particles: &[Particle<1>]| -> f64 {
+        particles
+            .iter()
+            .map(|p| 0.5 * p.mass() * p.derivatives()[1][0].powi(2))
+            .sum()
+    };
+    let initial_energy = kinetic_energy(&particles);
+
+    for _ in 0..500 {
+        method.advance_step(&mut particles);
+    }
+
+    kinetic_energy(&particles) / initial_energy
+}
+
+#[test]
+fn pair_forces_are_implicit_at_stiff_contacts() {
+    // NOTE: Each contact lasts about three steps, far too few for explicit Euler
+    let delta_t = 1e-2;
+    let contacts = || Pairwise::new(SoftSphereContact::new(1e4)).plus(WallContact::new(1e4, [0.5]));
+
+    let ratio = contact_energy_ratio(&Euler::new(contacts(), delta_t));
+    assert!(
+        !ratio.is_finite() || ratio > 1e6,
+        "explicit energy ratio {ratio}"
+    );
+
+    let ratio = contact_energy_ratio(&Implicit::backward_euler(contacts(), delta_t));
+    assert!(ratio < 1.0, "backward Euler energy ratio {ratio}");
+
+    // NOTE: One sided contacts do not keep the energy exactly, but with the pair forces
+    // held at the start of the step it grows a thousandfold
+    let ratio = contact_energy_ratio(&Implicit::trapezoidal(contacts(), delta_t));
+    assert!(ratio < 1.5, "trapezoidal energy ratio {ratio}");
+}
+
+/// Spring of rest length zero binding every pair of particles.
+struct Bond;
+
+impl PairInteraction<2> for Bond {
+    fn pair_force(&self, particle: &ParticleState<2>, other: &ParticleState<2>) -> [f64; 2] {
+        [0, 1].map(|i| SPRING_CONSTANT * (other.position[i] - particle.position[i]))
+    }
+
+    fn pair_jacobian(
+        &self,
+        _particle: &ParticleState<2>,
+        _other: &ParticleState<2>,
+    ) -> Option<ForceJacobian<2>> {
+        Some(ForceJacobian {
+            position: [[-SPRING_CONSTANT, 0.0], [0.0, -SPRING_CONSTANT]],
+            velocity: [[0.0; 2]; 2],
+        })
+    }
+
+    fn pair_potential_energy(
+        &self,
+        particle: &ParticleState<2>,
+        other: &ParticleState<2>,
+    ) -> Option<f64> {
+        let squared_length: f64 = (0..2)
+            .map(|i| (other.position[i] - particle.position[i]).powi(2))
+            .sum();
+
+        Some(0.5 * SPRING_CONSTANT * squared_length)
+    }
+}
+
+/// Energy, relative to the initial one, of two particles of unlike masses bound by a
+/// stiff [`Bond`] and stepped well past the stability limit of explicit methods.
+fn bond_energy_ratio(method: &dyn IntegrationMethod<2>) -> f64 {
+    // NOTE: The bond vibrates at sqrt(k / reduced mass), about 115 rad/s, so the step
+    // spans almost six radians of it
+    let delta_t = 0.05;
+    let bond = Pairwise::new(Bond);
+    let mut system = ParticleSystem::new(&[
+        Particle::new(0, [0.0, 0.0], [0.0, 1.0], [0.0; 2], 0.0, 1.0),
+        Particle::new(1, [0.1, 0.05], [0.5, -0.2], [0.0; 2], 0.0, 3.0),
+    ]);
+    system.update_accelerations(&bond);
+
+    let energy = |system: &ParticleSystem<2>| {
+        let kinetic_energy: f64 = system
+            .states()
+            .map(|p| 0.5 * p.mass * (p.velocity[0].powi(2) + p.velocity[1].powi(2)))
+            .sum();
+
+        kinetic_energy + bond.potential_energy(system).unwrap()
+    };
+    let initial_energy = energy(&system);
+
+    for _ in 0..(5.0 / delta_t) as usize {
+        method.advance_system(&mut system);
+    }
+
+    energy(&system) / initial_energy
+}
+
+#[test]
+fn stiff_pair_couplings_are_solved_together() {
+    let delta_t = 0.05;
+
+    let ratio = bond_energy_ratio(&VelocityVerlet::new(Pairwise::new(Bond), delta_t));
+    assert!(
+        !ratio.is_finite() || ratio > 1e6,
+        "explicit energy ratio {ratio}"
+    );
+
+    let ratio = bond_energy_ratio(&Implicit::backward_euler(Pairwise::new(Bond), delta_t));
+    assert!(ratio < 1.0, "backward Euler energy ratio {ratio}");
+
+    for method in [
+        Implicit::midpoint(Pairwise::new(Bond), delta_t),
+        Implicit::trapezoidal(Pairwise::new(Bond), delta_t),
+    ] {
+        let ratio = bond_energy_ratio(&method);
+        assert!((ratio - 1.0).abs() < 1e-8, "energy ratio {ratio}");
+    }
+}
+
+#[test]
+fn finite_difference_couplings_match_analytic_ones() {
+    let delta_t = 0.05;
+    let closure = |particle: &ParticleState<2>, system: &ParticleSystem<2>| {
+        Pairwise::new(Bond).force(particle, system)
+    };
+
+    let analytic = bond_energy_ratio(&Implicit::trapezoidal(Pairwise::new(Bond), delta_t));
+    let finite_difference = bond_energy_ratio(&Implicit::trapezoidal(closure, delta_t));
+
+    assert!((analytic - finite_difference).abs() < 1e-8);
+}
+
+#[test]
+#[should_panic(expected = "did not converge")]
+fn steps_which_do_not_converge_panic() {
+    let method = Implicit::trapezoidal(Pairwise::new(Bond), 0.05).with_newton(1e-12, 1);
+    bond_energy_ratio(&method);
+}
+
+#[test]
+fn steps_which_do_not_converge_stop_the_simulation_at_the_last_step() {
+    let method = Implicit::trapezoidal(Pairwise::new(Bond), 0.05).with_newton(1e-12, 1);
+    let particles = [
+        Particle::new(0, [0.0, 0.0], [0.0, 1.0], [0.0; 2], 0.0, 1.0),
+        Particle::new(1, [0.1, 0.05], [0.5, -0.2], [0.0; 2], 0.0, 3.0),
+    ];
+
+    let mut simulation = Simulation::new(ParticleSystem::new(&particles), Box::new(method), 0.05);
+    simulation.on_step_error(|error| error);
+
+    assert_eq!(simulation.run(), StepError::NotConverged { iterations: 1 });
+    assert_eq!(simulation.steps(), 0);
+    assert_eq!(
+        simulation.system().positions(),
+        particles.map(|particle| particle.state().position)
+    );
+}
@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+
+use integration_dynamics::{
+    forces::{ForceJacobian, ForceModel, PairInteraction, Pairwise, SoftSphereContact},
+    neighbours::CellList,
+    particle::Particle,
+    system::ParticleSystem,
+};
+
+const RADIUS: f64 = 0.5;
+const CUTOFF: f64 = 2.0 * RADIUS;
+
+/// Scatters `count` particles over a box of side `side` with a linear congruential
+/// sequence, so the positions are reproducible.
+fn scattered<const DIM: usize>(count: usize, side: f64) -> Vec<Particle<DIM>> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+
+    (0..count)
+        .map(|n| {
+            let position = [0.0; DIM].map(|_| side * next() - side / 2.0);
+            Particle::new(n, position, [0.0; DIM], [0.0; DIM], RADIUS, 1.0)
+        })
+        .collect()
+}
+
+fn assert_finds_every_close_pair<const DIM: usize>(particles: &[Particle<DIM>]) {
+    let system = ParticleSystem::new(particles);
+
+    let mut pairs = HashSet::new();
+    CellList::new(CUTOFF, system.positions()).for_each_pair(|n, m| {
+        assert!(n < m);
+        assert!(pairs.insert((n, m)), "pair ({n}, {m}) visited twice");
+    });
+
+    for (n, particle) in particles.iter().enumerate() {
+        for (m, other) in particles.iter().enumerate().skip(n + 1) {
+            if particle.get_distance(other) <= CUTOFF {
+                assert!(pairs.contains(&(n, m)), "pair ({n}, {m}) missed");
+            }
+        }
+    }
+}
+
+#[test]
+fn cell_list_finds_every_close_pair_in_two_dimensions() {
+    assert_finds_every_close_pair(&scattered::<2>(2_000, 40.0));
+}
+
+#[test]
+fn cell_list_finds_every_close_pair_in_three_dimensions() {
+    assert_finds_every_close_pair(&scattered::<3>(2_000, 12.0));
+}
+
+#[test]
+fn neighbours_include_every_close_particle() {
+    let particles = scattered::<2>(500, 20.0);
+    let system = ParticleSystem::new(&particles);
+    let cell_list = CellList::new(CUTOFF, system.positions());
+
+    for particle in &particles {
+        let position = particle.derivatives()[0];
+        let neighbours: HashSet<_> = cell_list.neighbours(&position).collect();
+
+        for (m, other) in particles.iter().enumerate() {
+            if particle.get_distance(other) <= CUTOFF {
+                assert!(neighbours.contains(&m));
+            }
+        }
+    }
+}
+
+#[test]
+fn cutoff_does_not_change_contact_forces() {
+    let system = ParticleSystem::new(&scattered::<2>(2_000, 40.0));
+    let contact = SoftSphereContact::new(1e4);
+
+    let all_pairs = Pairwise::new(contact).forces(&system);
+    let cell_pairs = Pairwise::with_cutoff(contact, CUTOFF).forces(&system);
+
+    for (force, expected) in cell_pairs.iter().zip(&all_pairs) {
+        for i in 0..2 {
+            assert!((force[i] - expected[i]).abs() <= 1e-9);
+        }
+    }
+}
+
+#[test]
+fn forces_and_jacobians_of_single_particles_respect_the_cutoff() {
+    // NOTE: Contacts between the cutoff and the sum of the radii are cut off
+    let cutoff = 0.6 * CUTOFF;
+    let system = ParticleSystem::new(&scattered::<2>(500, 20.0));
+    let contact = SoftSphereContact::new(1e4);
+    let force_model = Pairwise::with_cutoff(contact, cutoff);
+
+    let forces = force_model.forces(&system);
+    for (n, expected) in forces.iter().enumerate() {
+        let particle = system.state(n);
+        let force = force_model.force(&particle, &system);
+        for i in 0..2 {
+            assert!((force[i] - expected[i]).abs() <= 1e-9);
+        }
+
+        let mut expected_jacobian = ForceJacobian::zero();
+        for (m, other) in system.states().enumerate() {
+            let distance = (0..2)
+                .map(|i| (other.position[i] - particle.position[i]).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            if m == n || distance > cutoff {
+                continue;
+            }
+
+            let pair_jacobian = contact.pair_jacobian(&particle, &other).unwrap();
+            for i in 0..2 {
+                for j in 0..2 {
+                    expected_jacobian.position[i][j] += pair_jacobian.position[i][j];
+                    expected_jacobian.velocity[i][j] += pair_jacobian.velocity[i][j];
+                }
+            }
+        }
+        assert_eq!(
+            force_model.jacobian(&particle, &system).unwrap(),
+            expected_jacobian
+        );
+    }
+}
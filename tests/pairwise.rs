@@ -0,0 +1,59 @@
+use integration_dynamics::{
+    forces::{ForceModel, Pairwise, SoftSphereContact},
+    particle::Particle,
+    system::ParticleSystem,
+};
+
+const SPRING_CONSTANT: f64 = 1e4;
+const RADIUS: f64 = 0.5;
+const SIDE: usize = 20;
+
+/// Square lattice of overlapping discs, slightly displaced so every pair is distinct.
+fn lattice() -> Vec<Particle<2>> {
+    (0..SIDE * SIDE)
+        .map(|n| {
+            let (x, y) = ((n % SIDE) as f64, (n / SIDE) as f64);
+            let jitter = 0.05 * ((n * 7919) % 13) as f64 / 13.0;
+
+            Particle::new(
+                n,
+                [0.9 * x + jitter, 0.9 * y - jitter],
+                [0.0; 2],
+                [0.0; 2],
+                RADIUS,
+                1.0,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn pairwise_forces_match_per_particle_forces() {
+    let system = ParticleSystem::new(&lattice());
+    let force_model = Pairwise::new(SoftSphereContact::new(SPRING_CONSTANT));
+
+    for (particle, force) in system.states().zip(force_model.forces(&system)) {
+        let expected = force_model.force(&particle, &system);
+
+        for i in 0..2 {
+            assert!((force[i] - expected[i]).abs() <= 1e-9 * SPRING_CONSTANT);
+        }
+    }
+}
+
+#[test]
+fn pairwise_forces_cancel_out() {
+    let system = ParticleSystem::new(&lattice());
+    let forces = Pairwise::new(SoftSphereContact::new(SPRING_CONSTANT)).forces(&system);
+
+    let largest = forces
+        .iter()
+        .flatten()
+        .fold(0.0_f64, |largest, f| largest.max(f.abs()));
+    assert!(largest > 0.0);
+
+    for i in 0..2 {
+        let total: f64 = forces.iter().map(|force| force[i]).sum();
+        assert!(total.abs() <= 1e-9 * largest * forces.len() as f64);
+    }
+}
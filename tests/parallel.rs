@@ -0,0 +1,117 @@
+#![cfg(feature = "parallel")]
+
+use integration_dynamics::{
+    forces::{ForceModel, Pairwise, SoftSphereContact, WallContact},
+    methods::{ExplicitRungeKutta, Implicit, IntegrationMethod, VelocityVerlet},
+    particle::Particle,
+    system::ParticleSystem,
+};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+const SIDE: usize = 32;
+const RADIUS: f64 = 0.5;
+const SPRING_CONSTANT: f64 = 1e3;
+const DELTA_T: f64 = 1e-3;
+const STEPS: usize = 50;
+
+fn force_model() -> impl ForceModel<2> {
+    let bounds = [SIDE as f64; 2];
+
+    Pairwise::with_cutoff(SoftSphereContact::new(SPRING_CONSTANT), 2.0 * RADIUS)
+        .plus(WallContact::new(SPRING_CONSTANT, bounds))
+}
+
+/// Slightly compressed lattice of discs moving in different directions.
+fn granular_system() -> Vec<Particle<2>> {
+    let force_model = force_model();
+
+    let particles: Vec<_> = (0..SIDE * SIDE)
+        .map(|n| {
+            let (x, y) = ((n % SIDE) as f64, (n / SIDE) as f64);
+            let velocity = [((n * 37) % 11) as f64 - 5.0, ((n * 53) % 7) as f64 - 3.0];
+
+            Particle::new(
+                n,
+                [0.5 + 0.98 * x, 0.5 + 0.98 * y],
+                velocity,
+                [0.0; 2],
+                RADIUS,
+                1.0,
+            )
+        })
+        .collect();
+
+    let mut system = ParticleSystem::new(&particles);
+    system.update_accelerations(&force_model);
+
+    system.to_particles()
+}
+
+fn pool(threads: usize) -> ThreadPool {
+    ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap()
+}
+
+fn run(method: &(dyn IntegrationMethod<2> + Sync), threads: usize) -> Vec<Particle<2>> {
+    pool(threads).install(|| {
+        let mut particles = granular_system();
+        for _ in 0..STEPS {
+            method.advance_step(&mut particles);
+        }
+
+        particles
+    })
+}
+
+fn assert_independent_of_thread_count(method: &(dyn IntegrationMethod<2> + Sync)) {
+    let serial = run(method, 1);
+
+    for threads in [2, 4, 8] {
+        assert!(
+            serial == run(method, threads),
+            "results differ with {threads} threads"
+        );
+    }
+}
+
+#[test]
+fn velocity_verlet_is_independent_of_thread_count() {
+    assert_independent_of_thread_count(&VelocityVerlet::new(force_model(), DELTA_T));
+}
+
+#[test]
+fn runge_kutta_is_independent_of_thread_count() {
+    assert_independent_of_thread_count(&ExplicitRungeKutta::classic(force_model(), DELTA_T));
+}
+
+#[test]
+fn implicit_midpoint_is_independent_of_thread_count() {
+    assert_independent_of_thread_count(&Implicit::midpoint(force_model(), DELTA_T));
+}
+
+#[test]
+fn parallel_forces_match_the_serial_loop() {
+    let system = ParticleSystem::new(&granular_system());
+    let serial_forces = |force_model: &dyn ForceModel<2>| -> Vec<[f64; 2]> {
+        (0..system.len())
+            .map(|n| force_model.force(&system.state(n), &system))
+            .collect()
+    };
+
+    // NOTE: Forces evaluated particle by particle are the same bits in any order
+    let walls = WallContact::new(SPRING_CONSTANT, [SIDE as f64; 2]);
+    assert!(pool(8).install(|| walls.forces(&system)) == serial_forces(&walls));
+
+    // NOTE: Pair forces are summed pair by pair rather than particle by particle, which
+    // rounds differently, but in the same order with any number of threads
+    let force_model = force_model();
+    let forces = pool(8).install(|| force_model.forces(&system));
+    assert!(forces == pool(1).install(|| force_model.forces(&system)));
+    for (force, expected) in forces.iter().zip(serial_forces(&force_model)) {
+        for i in 0..2 {
+            assert!((force[i] - expected[i]).abs() <= 1e-9 * SPRING_CONSTANT);
+        }
+    }
+}
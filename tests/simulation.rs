@@ -0,0 +1,101 @@
+use std::ops::ControlFlow;
+
+use integration_dynamics::{
+    forces::ConstantField,
+    methods::Euler,
+    particle::Particle,
+    simulation::{steps_per_output, Simulation},
+    system::ParticleSystem,
+};
+
+const DELTA_T: f64 = 0.5;
+const STEPS_PER_OUTPUT: usize = 4;
+
+/// Particles falling at a constant acceleration, one of them from a lower height.
+fn falling_particles() -> ParticleSystem<1> {
+    ParticleSystem::new(&[
+        Particle::new(0, [10.0], [0.0], [-1.0], 0.0, 1.0),
+        Particle::new(1, [3.0], [0.0], [-1.0], 0.0, 1.0),
+    ])
+}
+
+fn simulation<'a, B>() -> Simulation<'a, 1, B> {
+    let method = Euler::new(ConstantField::new([-1.0]), DELTA_T);
+
+    Simulation::new(falling_particles(), Box::new(method), DELTA_T)
+        .with_steps_per_output(STEPS_PER_OUTPUT)
+}
+
+#[test]
+fn outputs_at_the_end_of_every_interval() {
+    let mut step_times = Vec::new();
+    let mut output_times = Vec::new();
+
+    let mut simulation = simulation::<()>();
+    simulation
+        .on_step(|time, _| {
+            step_times.push(time);
+            ControlFlow::Continue(())
+        })
+        .on_output(|time, _| {
+            output_times.push(time);
+            ControlFlow::Continue(())
+        });
+
+    assert_eq!(simulation.run_outputs(3), ControlFlow::Continue(()));
+    assert_eq!(simulation.steps(), 3 * STEPS_PER_OUTPUT);
+    drop(simulation);
+
+    let expected_steps: Vec<_> = (1..=12).map(|step| step as f64 * DELTA_T).collect();
+    assert_eq!(step_times, expected_steps);
+    assert_eq!(output_times, [2.0, 4.0, 6.0]);
+}
+
+#[test]
+fn observers_stop_the_simulation_and_see_the_final_state() {
+    let mut output_counts = Vec::new();
+
+    let mut simulation = simulation();
+    simulation
+        .on_step(|_, system| {
+            system.retain(|particle| particle.position[0] > 0.0);
+
+            if system.len() == 1 {
+                ControlFlow::Break("landed")
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .on_output(|time, system| {
+            output_counts.push((time, system.len()));
+            ControlFlow::Continue(())
+        });
+
+    assert_eq!(simulation.run(), "landed");
+
+    // NOTE: Euler is exact at a constant acceleration, so the lower particle falls below the
+    // origin at t = sqrt(6), on the fifth step, in between outputs
+    assert_eq!(simulation.time(), 5.0 * DELTA_T);
+    assert_eq!(simulation.system().len(), 1);
+    drop(simulation);
+
+    assert_eq!(output_counts, [(2.0, 2), (2.5, 1)]);
+}
+
+#[test]
+fn errors_of_the_final_output_take_precedence() {
+    let mut simulation = simulation::<Result<(), &str>>();
+    simulation
+        .on_step(|_, _| ControlFlow::Break(Ok(())))
+        .on_output(|_, _| ControlFlow::Break(Err("output failed")));
+
+    assert_eq!(simulation.step(), ControlFlow::Break(Err("output failed")));
+}
+
+#[test]
+fn steps_per_output_round_to_the_nearest_step() {
+    // NOTE: The quotient falls just short of seven in floating point
+    assert_eq!(steps_per_output(0.7, 0.1), Some(7));
+    assert_eq!(steps_per_output(1e-2, 3e-3), Some(3));
+    assert_eq!(steps_per_output(1e-3, 1e-2), None);
+}
@@ -0,0 +1,88 @@
+use integration_dynamics::{
+    methods::VelocityVerlet, particle::Particle, simulation::Simulation, spin::Spin,
+    system::ParticleSystem,
+};
+
+const DELTA_T: f64 = 1e-4;
+const GRAVITY: f64 = 9.81;
+const SLIDING_FRICTION: f64 = 0.2;
+const RADIUS: f64 = 0.03;
+const SPEED: f64 = 1.0;
+
+/// Ball alone on a floor after a second, shot along the first axis with `angular_velocity`.
+fn shoot(angular_velocity: [f64; 3], spin: Spin) -> Particle<2> {
+    let ball = Particle::new(0, [0.0; 2], [SPEED, 0.0], [0.0; 2], RADIUS, 0.17)
+        .with_angular_velocity(angular_velocity);
+    let no_force = |_: &_, _: &_| [0.0; 2];
+
+    let method = VelocityVerlet::new(no_force, DELTA_T);
+    let mut simulation =
+        Simulation::<2>::new(ParticleSystem::new(&[ball]), Box::new(method), DELTA_T);
+    simulation.observe(spin);
+    let _ = simulation.run_steps(10_000);
+
+    simulation.system().particle(0)
+}
+
+#[test]
+fn sliding_balls_roll_at_five_sevenths_of_their_speed() {
+    let ball = shoot([0.0; 3], Spin::new(DELTA_T, GRAVITY, SLIDING_FRICTION));
+
+    let velocity = ball.derivatives()[1];
+    assert!((velocity[0] - 5.0 / 7.0 * SPEED).abs() < 1e-12);
+    assert_eq!(velocity[1], 0.0);
+
+    // NOTE: Rolling along the first axis turns the ball about the second
+    let angular_velocity = ball.angular_velocity();
+    assert!((angular_velocity[1] * RADIUS - velocity[0]).abs() < 1e-12);
+
+    // NOTE: The ball slides for 2 v / (7 mu g) at a deceleration of mu g
+    let sliding_time = 2.0 * SPEED / (7.0 * SLIDING_FRICTION * GRAVITY);
+    let sliding_distance =
+        SPEED * sliding_time - SLIDING_FRICTION * GRAVITY * sliding_time.powi(2) / 2.0;
+    let expected_position = sliding_distance + 5.0 / 7.0 * SPEED * (1.0 - sliding_time);
+    assert!((ball.derivatives()[0][0] - expected_position).abs() < 1e-3);
+}
+
+#[test]
+fn rolling_friction_stops_balls_once_they_roll() {
+    let rolling_friction = 0.1;
+    let ball = shoot(
+        [0.0; 3],
+        Spin::new(DELTA_T, GRAVITY, SLIDING_FRICTION).with_rolling_friction(rolling_friction),
+    );
+
+    assert_eq!(ball.derivatives()[1], [0.0; 2]);
+    assert!(ball.angular_velocity()[1].abs() * RADIUS < 1e-12);
+
+    // NOTE: The sliding friction alone acts until the ball rolls at 5 / 7 of its speed
+    let sliding_distance = 12.0 * SPEED.powi(2) / (49.0 * SLIDING_FRICTION * GRAVITY);
+    let rolling_distance = (5.0 / 7.0 * SPEED).powi(2) / (2.0 * rolling_friction * GRAVITY);
+    let expected_position = sliding_distance + rolling_distance;
+    assert!((ball.derivatives()[0][0] - expected_position).abs() < 1e-3);
+}
+
+#[test]
+fn back_spin_slows_balls_down_before_they_roll() {
+    let ball = shoot(
+        [0.0, -SPEED / RADIUS, 0.0],
+        Spin::new(DELTA_T, GRAVITY, SLIDING_FRICTION),
+    );
+
+    assert!((ball.derivatives()[1][0] - 3.0 / 7.0 * SPEED).abs() < 1e-12);
+}
+
+#[test]
+fn side_spin_dies_out_without_bending_straight_shots() {
+    let side_spin = 20.0;
+    let spinning_friction = 0.05;
+    let ball = shoot(
+        [0.0, 0.0, side_spin],
+        Spin::new(DELTA_T, GRAVITY, SLIDING_FRICTION).with_spinning_friction(spinning_friction),
+    );
+
+    let stopping_time = 2.0 * RADIUS * side_spin / (5.0 * spinning_friction * GRAVITY);
+    assert!(stopping_time < 1.0);
+    assert_eq!(ball.angular_velocity()[2], 0.0);
+    assert_eq!(ball.derivatives()[1][1], 0.0);
+}
@@ -0,0 +1,85 @@
+use integration_dynamics::{
+    forces::LinearSpring,
+    methods::{IntegrationMethod, Symplectic},
+    particle::Particle,
+};
+
+const MASS: f64 = 1.0;
+const SPRING_CONSTANT: f64 = 1.0;
+const DELTA_T: f64 = 1e-2;
+const STEPS: usize = 1_000_000;
+
+fn energy(particle: &Particle<1>) -> f64 {
+    let r = particle.derivatives();
+
+    0.5 * MASS * r[1][0].powi(2) + 0.5 * SPRING_CONSTANT * r[0][0].powi(2)
+}
+
+/// Integrates the undamped oscillator and returns the largest relative energy error
+/// over the first and the last tenth of the run.
+fn energy_errors(method: &dyn IntegrationMethod<1>) -> (f64, f64) {
+    let mut particles = [Particle::new(
+        0,
+        [1.0],
+        [0.0],
+        [-SPRING_CONSTANT / MASS],
+        0.0,
+        MASS,
+    )];
+    let initial_energy = energy(&particles[0]);
+
+    let mut first_errors: f64 = 0.0;
+    let mut last_errors: f64 = 0.0;
+
+    for step in 0..STEPS {
+        method.advance_step(&mut particles);
+
+        let error = ((energy(&particles[0]) - initial_energy) / initial_energy).abs();
+        if step < STEPS / 10 {
+            first_errors = first_errors.max(error);
+        } else if step >= STEPS - STEPS / 10 {
+            last_errors = last_errors.max(error);
+        }
+    }
+
+    (first_errors, last_errors)
+}
+
+fn assert_bounded_energy_drift(method: &dyn IntegrationMethod<1>, bound: f64) {
+    let (first_errors, last_errors) = energy_errors(method);
+
+    assert!(
+        first_errors < bound,
+        "energy error {first_errors} above {bound}"
+    );
+    assert!(
+        last_errors < bound,
+        "energy error {last_errors} above {bound}"
+    );
+    // NOTE: Allow for round-off, which accumulates over the whole run
+    assert!(last_errors <= 1.1 * first_errors + 1e-12);
+}
+
+fn spring() -> LinearSpring<1> {
+    LinearSpring::new(SPRING_CONSTANT, [0.0])
+}
+
+#[test]
+fn forest_ruth_energy_is_bounded() {
+    assert_bounded_energy_drift(&Symplectic::forest_ruth(spring(), DELTA_T), 1e-9);
+}
+
+#[test]
+fn pefrl_energy_is_bounded() {
+    assert_bounded_energy_drift(&Symplectic::pefrl(spring(), DELTA_T), 1e-10);
+}
+
+#[test]
+fn yoshida_fourth_order_energy_is_bounded() {
+    assert_bounded_energy_drift(&Symplectic::yoshida(spring(), 4, DELTA_T), 1e-9);
+}
+
+#[test]
+fn yoshida_sixth_order_energy_is_bounded() {
+    assert_bounded_energy_drift(&Symplectic::yoshida(spring(), 6, DELTA_T), 1e-12);
+}